@@ -0,0 +1,170 @@
+// anonymize.rs
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use regex::{Captures, Regex};
+
+use crate::SourceFile;
+
+/// Names that are commonly defined by projects but belong to language or
+/// library conventions (trait methods, constructors, entry points)
+const COMMON_NAMES: &[&str] = &[
+    "self", "new", "default", "from", "from_str", "into", "fmt", "clone", "drop",
+    "next", "main", "build", "len", "is_empty", "get", "set", "run", "init",
+    "__init__", "__str__", "__repr__", "constructor", "toString", "render",
+];
+
+/// Consistently renames project-specific identifiers, string literals and
+/// domain terms. Replacements are derived from a hash of the original, so
+/// the same input always maps to the same name across runs.
+pub struct Anonymizer {
+    mapping: BTreeMap<String, String>,
+    terms: Vec<String>,
+    definition: Regex,
+    identifier: Regex,
+    string_literal: Regex,
+}
+
+impl Anonymizer {
+    pub fn new(terms: &str) -> Self {
+        Anonymizer {
+            mapping: BTreeMap::new(),
+            terms: terms
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            definition: Regex::new(
+                r"\b(?:fn|struct|enum|trait|union|class|interface|def|function|func)\s+([A-Za-z_][A-Za-z0-9_]*)",
+            )
+            .expect("invalid definition pattern"),
+            identifier: Regex::new(r"\b[A-Za-z_][A-Za-z0-9_]*\b").expect("invalid identifier pattern"),
+            string_literal: Regex::new(r#""(?:[^"\\\n]|\\.)*""#).expect("invalid string pattern"),
+        }
+    }
+
+    /// Reuse the replacements from a mapping file written by a previous run
+    pub fn load_mapping(&mut self, path: &Path) -> io::Result<()> {
+        let content = fs::read_to_string(path)?;
+        for line in content.lines() {
+            if let Some((original, replacement)) = line.split_once(" => ") {
+                self.mapping.insert(original.to_string(), replacement.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Anonymize the content of every file in place
+    pub fn anonymize(&mut self, files: &mut [SourceFile]) {
+        // Domain terms are replaced anywhere, including inside identifiers
+        for term in self.terms.clone() {
+            let replacement = self.replacement_for(&term, "term");
+            for file in files.iter_mut() {
+                file.content = file.content.replace(&term, &replacement);
+            }
+        }
+
+        // Identifiers defined in the project; names defined elsewhere
+        // (keywords, standard library) are left alone
+        let mut defined = Vec::new();
+        for file in files.iter() {
+            for caps in self.definition.captures_iter(&file.content) {
+                defined.push(caps[1].to_string());
+            }
+        }
+        for name in defined {
+            if name.len() > 2 && !COMMON_NAMES.contains(&name.as_str()) && !self.mapping.contains_key(&name) {
+                let replacement = self.replacement_for(&name, "id");
+                self.mapping.insert(name, replacement);
+            }
+        }
+
+        for file in files.iter_mut() {
+            let content = std::mem::take(&mut file.content);
+            let content = self.replace_strings(&content);
+            file.content = self
+                .identifier
+                .replace_all(&content, |caps: &Captures| {
+                    self.mapping.get(&caps[0]).cloned().unwrap_or_else(|| caps[0].to_string())
+                })
+                .into_owned();
+        }
+    }
+
+    /// Give the files the anonymized paths, once nothing needs their real ones
+    pub fn rename_files(&self, files: &mut [SourceFile]) {
+        for file in files {
+            file.relative_path = self.anonymize_path(&file.relative_path);
+        }
+    }
+
+    /// A file or directory name with the domain terms and identifiers `anonymize`
+    /// replaced in contents replaced the same way, so `PaymentProcessor.java`
+    /// becomes `id_....java` like the class in it
+    pub fn anonymize_name(&self, name: &str) -> String {
+        let mut name = name.to_string();
+        for term in &self.terms {
+            if let Some(replacement) = self.mapping.get(term) {
+                name = name.replace(term, replacement);
+            }
+        }
+        self.identifier
+            .replace_all(&name, |caps: &Captures| self.mapping.get(&caps[0]).cloned().unwrap_or_else(|| caps[0].to_string()))
+            .into_owned()
+    }
+
+    pub fn anonymize_path(&self, path: &Path) -> PathBuf {
+        path.components()
+            .map(|component| match component {
+                Component::Normal(name) => PathBuf::from(self.anonymize_name(&name.to_string_lossy())),
+                other => PathBuf::from(other.as_os_str()),
+            })
+            .collect()
+    }
+
+    /// Original => replacement pairs, one per line
+    pub fn mapping_file(&self) -> String {
+        self.mapping
+            .iter()
+            .map(|(original, replacement)| format!("{} => {}\n", original, replacement))
+            .collect()
+    }
+
+    fn replace_strings(&mut self, content: &str) -> String {
+        let mut mapping = std::mem::take(&mut self.mapping);
+        let replaced = self
+            .string_literal
+            .replace_all(content, |caps: &Captures| {
+                let literal = &caps[0];
+                if literal.len() <= 2 {
+                    return literal.to_string();
+                }
+                mapping
+                    .entry(literal.to_string())
+                    .or_insert_with(|| format!("\"str_{}\"", short_hash(literal)))
+                    .clone()
+            })
+            .into_owned();
+        self.mapping = mapping;
+        replaced
+    }
+
+    fn replacement_for(&mut self, original: &str, prefix: &str) -> String {
+        self.mapping
+            .entry(original.to_string())
+            .or_insert_with(|| format!("{}_{}", prefix, short_hash(original)))
+            .clone()
+    }
+}
+
+/// First 8 hex digits of the FNV-1a hash
+fn short_hash(s: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in s.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:08x}", hash >> 32)
+}
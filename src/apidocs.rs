@@ -0,0 +1,219 @@
+// apidocs.rs
+//! `--with-docs`: an API reference for Rust code, the public items with their
+//! signatures and doc comments, read from the sources rather than rustdoc
+//! (whose JSON output needs a nightly toolchain).
+use std::fmt::Write as _;
+
+use tree_sitter::Node;
+
+use crate::language;
+use crate::skeleton;
+use crate::SourceFile;
+
+/// Types and constants shown whole up to this many lines, signatures otherwise
+const MAX_ITEM_LINES: usize = 20;
+
+/// Public items with a name and something to show
+const ITEMS: &[&str] = &[
+    "function_item", "struct_item", "enum_item", "union_item", "trait_item", "type_item", "const_item",
+    "static_item", "mod_item", "macro_definition",
+];
+
+/// The API reference of every Rust file with public items, as markdown
+pub fn render(files: &[SourceFile]) -> String {
+    let mut out = String::from("# API reference\n");
+    let mut documented = 0;
+    for file in files {
+        let Some(language) = language::of(file).filter(|l| l.name == "Rust") else { continue };
+        let Some(tree) = skeleton::parse(&file.content, language) else { continue };
+        let source = &file.content;
+
+        let module_docs = inner_docs(tree.root_node(), source);
+        let mut items = String::new();
+        document_items(tree.root_node(), source, &mut items);
+        if items.is_empty() && module_docs.is_empty() {
+            continue;
+        }
+        documented += 1;
+        let _ = writeln!(out, "\n## {}\n", file.relative_path.to_string_lossy().replace('\\', "/"));
+        if !module_docs.is_empty() {
+            let _ = writeln!(out, "{}\n", module_docs);
+        }
+        out.push_str(items.trim_end());
+        out.push('\n');
+    }
+    if documented == 0 {
+        out.push_str("\nNo public Rust items found.\n");
+    }
+    out
+}
+
+/// The public items directly inside `node`, and the public methods of its impl blocks
+fn document_items(node: Node, source: &str, out: &mut String) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let kind = child.kind();
+        if kind == "impl_item" {
+            // Trait impls are listed; their methods are documented by the trait
+            if child.child_by_field_name("trait").is_some() {
+                let _ = writeln!(out, "- `{}`\n", signature(child, source));
+                continue;
+            }
+            let Some(body) = child.child_by_field_name("body") else { continue };
+            let mut methods = String::new();
+            let mut body_cursor = body.walk();
+            for item in body.named_children(&mut body_cursor) {
+                if item.kind() == "function_item" && is_public(item, source) {
+                    document(item, source, "####", &mut methods);
+                }
+            }
+            if !methods.is_empty() {
+                let _ = writeln!(out, "### `{}`\n", signature(child, source));
+                out.push_str(&methods);
+            }
+        } else if ITEMS.contains(&kind) && (is_public(child, source) || kind == "macro_definition" && is_exported_macro(child, source)) {
+            document(child, source, "###", out);
+            // Public modules declared inline are documented in place
+            if kind == "mod_item" {
+                if let Some(body) = child.child_by_field_name("body") {
+                    document_items(body, source, out);
+                }
+            }
+        }
+    }
+}
+
+/// One item: its signature in a code block, then its doc comment
+fn document(item: Node, source: &str, heading: &str, out: &mut String) {
+    let name = item.child_by_field_name("name").map(|n| &source[n.byte_range()]).unwrap_or("");
+    let _ = writeln!(out, "{} `{}`\n", heading, name);
+    let _ = writeln!(out, "```rust\n{}\n```\n", declaration(item, source));
+    let docs = outer_docs(item, source);
+    if !docs.is_empty() {
+        let _ = writeln!(out, "{}\n", docs);
+    }
+}
+
+/// Plain `pub`; `pub(crate)` and `pub(super)` aren't part of the API
+fn is_public(item: Node, source: &str) -> bool {
+    let mut cursor = item.walk();
+    let public = item
+        .named_children(&mut cursor)
+        .find(|c| c.kind() == "visibility_modifier")
+        .is_some_and(|v| source[v.byte_range()].trim() == "pub");
+    public
+}
+
+fn is_exported_macro(item: Node, source: &str) -> bool {
+    attributes(item).iter().any(|a| source[a.byte_range()].contains("macro_export"))
+}
+
+/// What to show of an item: whole for short types and constants, the
+/// signature for functions, traits and anything long
+fn declaration(item: Node, source: &str) -> String {
+    if item.kind() == "struct_item" {
+        if let Some(fields) = public_fields(item, source) {
+            return fields;
+        }
+    }
+    let text = source[item.byte_range()].trim();
+    let whole = matches!(item.kind(), "struct_item" | "enum_item" | "union_item" | "type_item" | "const_item" | "static_item");
+    if whole && text.lines().count() <= MAX_ITEM_LINES {
+        return text.to_string();
+    }
+    if item.kind() == "trait_item" {
+        // The signatures of the trait's methods, without default bodies
+        let mut out = format!("{} {{\n", signature(item, source));
+        if let Some(body) = item.child_by_field_name("body") {
+            let mut cursor = body.walk();
+            for member in body.named_children(&mut cursor) {
+                if !matches!(member.kind(), "line_comment" | "block_comment" | "attribute_item") {
+                    let _ = writeln!(out, "    {};", signature(member, source).trim_end_matches(';'));
+                }
+            }
+        }
+        out.push('}');
+        return out;
+    }
+    match item.child_by_field_name("body") {
+        Some(_) if item.kind() != "function_item" => format!("{} {{ ... }}", signature(item, source)),
+        _ => signature(item, source),
+    }
+}
+
+/// A struct with private named fields, showing the public ones only
+fn public_fields(item: Node, source: &str) -> Option<String> {
+    let body = item.child_by_field_name("body").filter(|b| b.kind() == "field_declaration_list")?;
+    let mut cursor = body.walk();
+    let fields: Vec<Node> = body.named_children(&mut cursor).filter(|f| f.kind() == "field_declaration").collect();
+    if fields.iter().all(|f| is_public(*f, source)) {
+        return None;
+    }
+    let mut out = format!("{} {{\n", signature(item, source));
+    for field in fields.iter().filter(|f| is_public(**f, source)) {
+        let _ = writeln!(out, "    {},", &source[field.byte_range()]);
+    }
+    out.push_str("    // private fields\n}");
+    Some(out)
+}
+
+/// The item's text up to its body, on one line
+fn signature(item: Node, source: &str) -> String {
+    let end = item.child_by_field_name("body").map(|b| b.start_byte()).unwrap_or(item.end_byte());
+    source[item.start_byte()..end].split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The `///` and `/** */` comments before an item, skipping its attributes
+fn outer_docs(item: Node, source: &str) -> String {
+    let mut lines = Vec::new();
+    let mut sibling = item.prev_sibling();
+    while let Some(node) = sibling {
+        let text = source[node.byte_range()].trim();
+        if node.kind() == "attribute_item" {
+            sibling = node.prev_sibling();
+            continue;
+        }
+        if let Some(doc) = text.strip_prefix("///") {
+            lines.push(doc.strip_prefix(' ').unwrap_or(doc).to_string());
+        } else if let Some(doc) = text.strip_prefix("/**") {
+            let doc = doc.trim_end_matches("*/");
+            for line in doc.lines().rev() {
+                let line = line.trim();
+                lines.push(line.strip_prefix('*').unwrap_or(line).trim().to_string());
+            }
+        } else {
+            break;
+        }
+        sibling = node.prev_sibling();
+    }
+    lines.reverse();
+    lines.join("\n").trim().to_string()
+}
+
+/// The `//!` comments opening a file
+fn inner_docs(root: Node, source: &str) -> String {
+    let mut lines = Vec::new();
+    let mut cursor = root.walk();
+    for child in root.children(&mut cursor) {
+        let text = source[child.byte_range()].trim();
+        if let Some(doc) = text.strip_prefix("//!") {
+            lines.push(doc.strip_prefix(' ').unwrap_or(doc).to_string());
+        } else if child.kind() != "line_comment" {
+            break;
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// The `#[...]` attributes right before an item
+fn attributes(item: Node) -> Vec<Node> {
+    let mut found = Vec::new();
+    let mut sibling = item.prev_sibling();
+    while let Some(node) = sibling.filter(|n| matches!(n.kind(), "attribute_item" | "line_comment" | "block_comment")) {
+        if node.kind() == "attribute_item" {
+            found.push(node);
+        }
+        sibling = node.prev_sibling();
+    }
+    found
+}
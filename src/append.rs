@@ -0,0 +1,74 @@
+// append.rs
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::tree::FileTree;
+use crate::SourceFile;
+
+/// Files from earlier `--append` runs, kept next to the outputs they produced
+const STATE_FILE: &str = ".append-state.json";
+
+/// Files accumulated by earlier `--append` runs into this output directory
+pub fn load(output_dir: &Path) -> io::Result<Vec<SourceFile>> {
+    let path = output_dir.join(STATE_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let state: Value = serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Couldn't read {}: {}", path.display(), e)))?;
+
+    let files = state["files"].as_array().map(Vec::as_slice).unwrap_or_default();
+    Ok(files
+        .iter()
+        .filter_map(|file| {
+            Some(SourceFile {
+                relative_path: PathBuf::from(file["path"].as_str()?),
+                content: file["content"].as_str()?.to_string(),
+                size: file["size"].as_u64().unwrap_or(0),
+                modified: file["modified"].as_u64().map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+                tokens: file["tokens"].as_u64().unwrap_or(0) as usize,
+                // Already reported by the run that found them
+                redactions: Vec::new(),
+            })
+        })
+        .collect())
+}
+
+/// Add the earlier runs' files that this run didn't produce; paths this run
+/// produced replace their earlier version. Returns how many were carried over.
+pub fn merge(files: &mut Vec<SourceFile>, tree: &mut FileTree, previous: Vec<SourceFile>) -> usize {
+    let current: HashSet<PathBuf> = files.iter().map(|f| f.relative_path.clone()).collect();
+    let mut carried = 0;
+    for file in previous {
+        if current.contains(&file.relative_path) {
+            continue;
+        }
+        tree.add_file(&file.relative_path, file.size, file.tokens);
+        files.push(file);
+        carried += 1;
+    }
+    carried
+}
+
+/// Record the accumulated files for the next `--append` run
+pub fn save(output_dir: &Path, files: &[SourceFile]) -> io::Result<()> {
+    let files: Vec<Value> = files
+        .iter()
+        .map(|f| {
+            json!({
+                "path": f.relative_path.to_string_lossy().replace('\\', "/"),
+                "size": f.size,
+                "modified": f.modified.and_then(|m| m.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs()),
+                "tokens": f.tokens,
+                "content": f.content,
+            })
+        })
+        .collect();
+    let state = serde_json::to_string(&json!({ "files": files }))?;
+    fs::write(output_dir.join(STATE_FILE), state)
+}
@@ -0,0 +1,88 @@
+// archive.rs
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+
+use ignore::WalkBuilder;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Single-file form of the output directory for `--archive`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarZst,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::TarZst => "tar.zst",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tar.zst" | "tzst" => Ok(ArchiveFormat::TarZst),
+            "zip" => Ok(ArchiveFormat::Zip),
+            _ => Err(format!("Unknown archive format '{}' (expected tar.zst or zip)", s)),
+        }
+    }
+}
+
+/// Where outputs are generated before being archived, so an existing
+/// directory named like the output is never touched
+pub fn staging_dir(output: &Path) -> PathBuf {
+    env::temp_dir()
+        .join(format!("llm-context-gen-{}", process::id()))
+        .join(entry_root(output))
+}
+
+/// Pack the staged outputs into `<output>.<extension>`, with entries under the
+/// output directory's name, then remove the staging directory
+pub fn write(staging: &Path, output: &Path, format: ArchiveFormat) -> io::Result<PathBuf> {
+    let archive_path = PathBuf::from(format!("{}.{}", output.to_string_lossy().trim_end_matches(['/', '\\']), format.extension()));
+    let root = entry_root(output);
+    match format {
+        ArchiveFormat::TarZst => {
+            let encoder = zstd::Encoder::new(File::create(&archive_path)?, 0)?;
+            let mut builder = tar::Builder::new(encoder);
+            builder.append_dir_all(&root, staging)?;
+            builder.into_inner()?.finish()?;
+        }
+        ArchiveFormat::Zip => {
+            let mut zip = ZipWriter::new(File::create(&archive_path)?);
+            let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+            // Sorted so archives of the same context are identical
+            for entry in WalkBuilder::new(staging).standard_filters(false).sort_by_file_name(|a, b| a.cmp(b)).build() {
+                let entry = entry.map_err(io::Error::other)?;
+                let Ok(relative) = entry.path().strip_prefix(staging) else {
+                    continue;
+                };
+                if relative.as_os_str().is_empty() || !entry.path().is_file() {
+                    continue;
+                }
+                let name = root.join(relative).to_string_lossy().replace('\\', "/");
+                zip.start_file(name, options).map_err(io::Error::other)?;
+                io::copy(&mut File::open(entry.path())?, &mut zip)?;
+            }
+            zip.finish().map_err(io::Error::other)?;
+        }
+    }
+
+    fs::remove_dir_all(staging.parent().unwrap_or(staging))?;
+    Ok(archive_path)
+}
+
+/// Top-level directory inside the archive
+fn entry_root(output: &Path) -> PathBuf {
+    output.file_name().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("llm-context"))
+}
@@ -0,0 +1,126 @@
+// binary.rs
+use std::path::Path;
+
+use content_inspector::inspect;
+use globset::GlobSet;
+
+use crate::vfs::FileSystem;
+
+/// Bytes looked at to tell binaries from text
+pub const SNIFF_BYTES: usize = 8192;
+
+/// Extensions of common binary formats, even when their first bytes look like text
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "tiff", "ico", "webp",
+    "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx",
+    "zip", "tar", "gz", "rar", "7z",
+    "exe", "dll", "so", "dylib", "bin",
+    "mp3", "mp4", "wav", "avi", "mov",
+    "woff", "woff2", "ttf", "otf", "eot",
+];
+
+/// Classifies files as text or binary from their content, with glob overrides
+pub struct BinaryDetector {
+    force_text: GlobSet,
+    force_binary: GlobSet,
+}
+
+impl BinaryDetector {
+    pub fn new(force_text: GlobSet, force_binary: GlobSet) -> Self {
+        BinaryDetector { force_text, force_binary }
+    }
+
+    pub fn is_binary(&self, fs: &dyn FileSystem, path: &Path, relative_path: &Path) -> bool {
+        if self.force_binary.is_match(relative_path) {
+            return true;
+        }
+        if self.force_text.is_match(relative_path) {
+            return false;
+        }
+
+        match fs.read_head(path, SNIFF_BYTES) {
+            Ok(head) => looks_binary(path, &head),
+            Err(_) => true, // If we can't read, assume binary
+        }
+    }
+}
+
+/// Whether a file is binary from its first `SNIFF_BYTES` bytes and its extension
+pub fn looks_binary(path: &Path, head: &[u8]) -> bool {
+    // Empty files and scripts with a shebang are text whatever their name
+    if head.is_empty() || head.starts_with(b"#!") {
+        return false;
+    }
+
+    // Null bytes, magic numbers and invalid encodings; UTF-16/32 with a BOM count as text
+    if inspect(head).is_binary() {
+        return true;
+    }
+
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    BINARY_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+}
+
+/// A binary file that was left out of the content but is listed in assets.txt
+pub struct Asset {
+    pub path: std::path::PathBuf,
+    pub relative_path: std::path::PathBuf,
+    pub size: u64,
+    pub mime: &'static str,
+}
+
+/// MIME type guess from the file extension
+pub fn guess_mime(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    match extension.to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "tiff" | "tif" => "image/tiff",
+        "ico" => "image/x-icon",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "zip" => "application/zip",
+        "tar" => "application/x-tar",
+        "gz" => "application/gzip",
+        "rar" => "application/vnd.rar",
+        "7z" => "application/x-7z-compressed",
+        "exe" | "dll" => "application/vnd.microsoft.portable-executable",
+        "so" | "dylib" | "bin" => "application/octet-stream",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "avi" => "video/x-msvideo",
+        "mov" => "video/quicktime",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+        "wasm" => "application/wasm",
+        "sqlite" | "db" => "application/vnd.sqlite3",
+        _ => "application/octet-stream",
+    }
+}
+
+/// One line per asset: path, size and type guess
+pub fn inventory(assets: &[Asset]) -> String {
+    let mut out = String::new();
+    for asset in assets {
+        out.push_str(&format!(
+            "{:<60} {:>10}  {}\n",
+            asset.relative_path.display(),
+            crate::tree::format_size(asset.size),
+            asset.mime
+        ));
+    }
+    out
+}
@@ -0,0 +1,52 @@
+// bundles.rs
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+use crate::SourceFile;
+
+/// Small files of one directory, written to a single output instead of one each
+pub struct Bundle {
+    /// `src_locales.small-files.txt`, or `small-files.txt` for the top level
+    pub file_name: String,
+    pub content: String,
+}
+
+/// Bundle the files under `max_size` bytes by directory, wherever a directory
+/// has more than one. Returns the bundles and, per file, whether it's in one.
+pub fn bundle_small(files: &[SourceFile], max_size: u64) -> (Vec<Bundle>, Vec<bool>) {
+    let mut by_dir: BTreeMap<PathBuf, Vec<usize>> = BTreeMap::new();
+    for (i, file) in files.iter().enumerate() {
+        if file.size < max_size {
+            let dir = file.relative_path.parent().unwrap_or(Path::new("")).to_path_buf();
+            by_dir.entry(dir).or_default().push(i);
+        }
+    }
+
+    let mut bundled = vec![false; files.len()];
+    let mut bundles = Vec::new();
+    for (dir, members) in by_dir {
+        if members.len() < 2 {
+            continue;
+        }
+        let file_name = if dir.as_os_str().is_empty() {
+            "small-files.txt".to_string()
+        } else {
+            format!("{}.small-files.txt", paths::safe_file_name(&dir.to_string_lossy()))
+        };
+        // Delimited like the combined text output
+        let mut content = String::new();
+        for i in members {
+            bundled[i] = true;
+            let file = &files[i];
+            let rule = "=".repeat(64);
+            content.push_str(&format!("{}\nFile: {}\n{}\n\n{}", rule, file.relative_path.to_string_lossy().replace('\\', "/"), rule, file.content));
+            if !file.content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push('\n');
+        }
+        bundles.push(Bundle { file_name, content });
+    }
+    (bundles, bundled)
+}
@@ -0,0 +1,137 @@
+// chunk.rs
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::language;
+use crate::skeleton;
+use crate::tokens;
+use crate::SourceFile;
+
+/// A contiguous range of lines from one file
+pub struct Chunk {
+    pub path: PathBuf,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    pub tokens: usize,
+    pub text: String,
+}
+
+/// Split files into chunks of about `size` tokens, breaking only between lines
+/// and, where it doesn't make the chunk less than half full, right before a
+/// definition starts. Consecutive chunks repeat roughly `overlap` tokens.
+pub fn chunk_files(files: &[SourceFile], size: usize, overlap: usize, chars_per_token: f64) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    for file in files {
+        let lines: Vec<&str> = file.content.lines().collect();
+        let line_tokens: Vec<usize> = lines.iter().map(|l| tokens::estimate_tokens(l, chars_per_token)).collect();
+        let breaks = boundaries(file, &lines);
+
+        let mut start = 0;
+        while start < lines.len() {
+            // Always take at least one line so a huge line can't stall the loop
+            let mut end = start + 1;
+            let mut used = line_tokens[start];
+            while end < lines.len() && used + line_tokens[end] <= size {
+                used += line_tokens[end];
+                end += 1;
+            }
+
+            if end < lines.len() {
+                let mut kept = used;
+                for candidate in (start + 1..end).rev() {
+                    kept -= line_tokens[candidate];
+                    if kept < size / 2 {
+                        break;
+                    }
+                    if breaks[candidate] {
+                        end = candidate;
+                        break;
+                    }
+                }
+            }
+
+            chunks.push(Chunk {
+                path: file.relative_path.clone(),
+                start_line: start + 1,
+                end_line: end,
+                tokens: line_tokens[start..end].iter().sum(),
+                text: lines[start..end].join("\n"),
+            });
+            if end == lines.len() {
+                break;
+            }
+            start = overlap_start(&line_tokens, start, end, overlap);
+        }
+    }
+    chunks
+}
+
+/// Lines a chunk can start at without cutting a definition in half: where
+/// top-level items and members of classes/impls start, or else unindented
+/// lines after a blank line
+fn boundaries(file: &SourceFile, lines: &[&str]) -> Vec<bool> {
+    let mut breaks = vec![false; lines.len()];
+    let tree = language::of(file).and_then(|l| skeleton::parse(&file.content, l));
+    match tree {
+        Some(tree) => {
+            let root = tree.root_node();
+            let mut cursor = root.walk();
+            for node in root.named_children(&mut cursor) {
+                mark(&mut breaks, node.start_position().row);
+                let Some(body) = node.child_by_field_name("body") else { continue };
+                let mut body_cursor = body.walk();
+                for member in body.named_children(&mut body_cursor) {
+                    mark(&mut breaks, member.start_position().row);
+                }
+            }
+        }
+        None => {
+            for i in 1..lines.len() {
+                let line = lines[i];
+                if lines[i - 1].trim().is_empty() && !line.trim().is_empty() && !line.starts_with([' ', '\t']) {
+                    breaks[i] = true;
+                }
+            }
+        }
+    }
+    breaks
+}
+
+fn mark(breaks: &mut [bool], row: usize) {
+    if let Some(line) = breaks.get_mut(row) {
+        *line = true;
+    }
+}
+
+/// Where the next chunk starts so it repeats about `overlap` tokens, while still making progress
+fn overlap_start(line_tokens: &[usize], start: usize, end: usize, overlap: usize) -> usize {
+    let mut next = end;
+    let mut repeated = 0;
+    while overlap > 0 && next > start + 1 && repeated + line_tokens[next - 1] <= overlap {
+        repeated += line_tokens[next - 1];
+        next -= 1;
+    }
+    next
+}
+
+/// One JSON record per chunk with its source metadata
+pub fn write_jsonl(path: &Path, chunks: &[Chunk]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for (id, chunk) in chunks.iter().enumerate() {
+        let record = json!({
+            "id": id,
+            "path": chunk.path.to_string_lossy(),
+            "language": language::detect(&chunk.path).map(|l| l.name),
+            "start_line": chunk.start_line,
+            "end_line": chunk.end_line,
+            "tokens": chunk.tokens,
+            "content": chunk.text,
+        });
+        writeln!(out, "{}", record)?;
+    }
+    out.flush()
+}
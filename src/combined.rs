@@ -0,0 +1,508 @@
+// combined.rs
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde_json::json;
+
+use crate::dataset;
+use crate::history::Stability;
+use crate::images::EmbeddedImage;
+use crate::language;
+use crate::obsidian;
+use crate::pipeline;
+use crate::sqlite;
+use crate::SourceFile;
+
+/// Format of the combined output file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Markdown,
+    Json,
+    Xml,
+    Sqlite,
+    Parquet,
+    /// A directory of linked markdown notes rather than a single file
+    Obsidian,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "txt",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Json => "json",
+            OutputFormat::Xml => "xml",
+            OutputFormat::Sqlite => "db",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Obsidian => "md",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Ok(OutputFormat::Text),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "json" => Ok(OutputFormat::Json),
+            "xml" => Ok(OutputFormat::Xml),
+            "sqlite" | "db" => Ok(OutputFormat::Sqlite),
+            "parquet" => Ok(OutputFormat::Parquet),
+            "obsidian" | "logseq" => Ok(OutputFormat::Obsidian),
+            _ => Err(format!("Unknown format '{}' (expected text, markdown, json, xml, sqlite, parquet or obsidian)", s)),
+        }
+    }
+}
+
+/// Text placed before and after the files of the combined output, and the
+/// line that introduces each file in text output
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Framing<'a> {
+    pub header: Option<&'a str>,
+    pub footer: Option<&'a str>,
+    /// With `{path}`, `{language}`, `{tokens}`, `{size}` and `{lines}` placeholders
+    pub delimiter: Option<&'a str>,
+    /// Files are ordered by stability; a cache-boundary marker ends each section
+    pub sections: Option<&'a HashMap<PathBuf, Stability>>,
+    /// API contracts and their kind; they come first, under a heading of their own
+    pub contracts: Option<&'a HashMap<PathBuf, &'static str>>,
+}
+
+impl Framing<'_> {
+    /// The section that ends right before `files[i]`, if any
+    fn boundary_before(&self, files: &[SourceFile], i: usize) -> Option<&'static str> {
+        let sections = self.sections?;
+        let previous = sections.get(&files[i.checked_sub(1)?].relative_path)?;
+        let current = sections.get(&files[i].relative_path)?;
+        (previous != current).then(|| previous.name())
+    }
+
+    /// "Contracts" before the first contract, "Source files" after the last
+    fn heading_before(&self, files: &[SourceFile], i: usize) -> Option<&'static str> {
+        let contracts = self.contracts?;
+        let is_contract = |file: &SourceFile| contracts.contains_key(&file.relative_path);
+        match i.checked_sub(1) {
+            None => is_contract(&files[i]).then_some("Contracts"),
+            Some(previous) => (is_contract(&files[previous]) && !is_contract(&files[i])).then_some("Source files"),
+        }
+    }
+
+    fn contract(&self, file: &SourceFile) -> Option<&'static str> {
+        self.contracts?.get(&file.relative_path).copied()
+    }
+
+    fn stability(&self, file: &SourceFile) -> Option<&'static str> {
+        self.sections?.get(&file.relative_path).map(Stability::name)
+    }
+}
+
+/// How the combined output is split into numbered parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// Whole files, at most this many tokens per part (a single larger file
+    /// still gets its own part)
+    Tokens(usize),
+    /// At most this many characters per part, cutting between lines where
+    /// possible, for pasting into chat UIs
+    Chars(usize),
+}
+
+/// Room left in each character-limited part for its header
+const PART_HEADER_RESERVE: usize = 100;
+
+/// Write the combined output, split into numbered parts if requested.
+/// Embedded images and the header go into the first part, the footer into the last.
+pub fn write_combined_parts(
+    files: &[SourceFile],
+    images: &[EmbeddedImage],
+    framing: Framing,
+    format: OutputFormat,
+    output_dir: &Path,
+    split: Option<Split>,
+    part_headers: bool,
+) -> io::Result<()> {
+    let whole_path = output_dir.join(format!("combined.{}", format.extension()));
+    // A vault has a note per file, there's nothing to split
+    if format == OutputFormat::Obsidian {
+        return write_combined(files, images, framing, format, &whole_path);
+    }
+    let tabular = matches!(format, OutputFormat::Sqlite | OutputFormat::Parquet);
+    if let (Some(Split::Chars(max_chars)), false) = (split, tabular) {
+        let mut out = Vec::new();
+        write_to(files, images, framing, format, &mut out)?;
+        let text = String::from_utf8_lossy(&out);
+        let reserve = if part_headers { PART_HEADER_RESERVE } else { 0 };
+        let parts = split_text(&text, max_chars.saturating_sub(reserve).max(1));
+        if parts.len() <= 1 {
+            return std::fs::write(whole_path, text.as_bytes());
+        }
+        for (i, part) in parts.iter().enumerate() {
+            let header = if part_headers { part_header(i, parts.len()) } else { String::new() };
+            let path = output_dir.join(format!("combined-{}.{}", i + 1, format.extension()));
+            std::fs::write(path, format!("{}{}", header, part))?;
+        }
+        println!("Combined output split into {} parts", parts.len());
+        return Ok(());
+    }
+
+    let parts = match split {
+        Some(Split::Tokens(max)) => split_parts(files, max),
+        _ => vec![files],
+    };
+
+    if parts.len() <= 1 {
+        return write_combined(files, images, framing, format, &whole_path);
+    }
+
+    for (i, part) in parts.iter().enumerate() {
+        let path = output_dir.join(format!("combined-{}.{}", i + 1, format.extension()));
+        let part_framing = Framing {
+            header: framing.header.filter(|_| i == 0),
+            footer: framing.footer.filter(|_| i == parts.len() - 1),
+            ..framing
+        };
+        let part_images = if i == 0 { images } else { &[] };
+        if part_headers && !tabular {
+            let mut out = part_header(i, parts.len()).into_bytes();
+            write_to(part, part_images, part_framing, format, &mut out)?;
+            std::fs::write(path, out)?;
+        } else {
+            write_combined(part, part_images, part_framing, format, &path)?;
+        }
+    }
+    println!("Combined output split into {} parts", parts.len());
+    Ok(())
+}
+
+/// Tells a chat model to wait for the rest of the parts
+fn part_header(i: usize, count: usize) -> String {
+    if i + 1 == count {
+        format!("Part {} of {} \u{2014} all parts are sent, you can respond now.\n\n", i + 1, count)
+    } else {
+        format!("Part {} of {} \u{2014} do not respond until all parts are sent.\n\n", i + 1, count)
+    }
+}
+
+/// Cut text into pieces of at most `max_chars` characters, after the last
+/// newline that fits, or mid-line when a single line is longer than that
+fn split_text(text: &str, max_chars: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let Some((limit, _)) = rest.char_indices().nth(max_chars) else {
+            parts.push(rest);
+            break;
+        };
+        let cut = match rest[..limit].rfind('\n') {
+            Some(newline) => newline + 1,
+            None => limit,
+        };
+        parts.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    parts
+}
+
+fn split_parts(files: &[SourceFile], max_tokens: usize) -> Vec<&[SourceFile]> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut tokens = 0;
+    for (i, file) in files.iter().enumerate() {
+        if i > start && tokens + file.tokens > max_tokens {
+            parts.push(&files[start..i]);
+            start = i;
+            tokens = 0;
+        }
+        tokens += file.tokens;
+    }
+    if start < files.len() {
+        parts.push(&files[start..]);
+    }
+    parts
+}
+
+pub fn write_combined(
+    files: &[SourceFile],
+    images: &[EmbeddedImage],
+    framing: Framing,
+    format: OutputFormat,
+    combined_path: &Path,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Sqlite => return sqlite::write_database(files, &[], combined_path),
+        OutputFormat::Parquet => return dataset::write_parquet(files, None, combined_path),
+        OutputFormat::Obsidian => return obsidian::write_vault(files, &combined_path.with_file_name("vault")),
+        _ => {}
+    }
+
+    let mut combined = File::create(combined_path)?;
+    write_to(files, images, framing, format, &mut combined)
+}
+
+/// The combined output as a string; databases fall back to plain text and
+/// vaults to markdown
+pub fn render(files: &[SourceFile], format: OutputFormat) -> io::Result<String> {
+    let mut out = Vec::new();
+    match format {
+        OutputFormat::Sqlite | OutputFormat::Parquet => write_text(files, Framing::default(), &mut out)?,
+        OutputFormat::Obsidian => write_to(files, &[], Framing::default(), OutputFormat::Markdown, &mut out)?,
+        _ => write_to(files, &[], Framing::default(), format, &mut out)?,
+    }
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+fn write_to(
+    files: &[SourceFile],
+    images: &[EmbeddedImage],
+    framing: Framing,
+    format: OutputFormat,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Json => return write_json(files, images, framing, out),
+        OutputFormat::Xml => return write_xml(files, images, framing, out),
+        _ => {}
+    }
+
+    // Text and Markdown take the header and footer verbatim
+    if let Some(header) = framing.header {
+        writeln!(out, "{}", header.trim_end())?;
+        writeln!(out)?;
+    }
+    match format {
+        OutputFormat::Text => match framing.delimiter {
+            Some(delimiter) => write_delimited(files, delimiter, framing, out)?,
+            None => write_text(files, framing, out)?,
+        },
+        OutputFormat::Markdown => write_markdown(files, framing, out)?,
+        _ => unreachable!("databases and vaults are written separately"),
+    }
+    if let Some(footer) = framing.footer {
+        writeln!(out, "{}", footer.trim_end())?;
+    }
+    Ok(())
+}
+
+fn write_text(files: &[SourceFile], framing: Framing, out: &mut impl Write) -> io::Result<()> {
+    for (i, file) in files.iter().enumerate() {
+        if let Some(section) = framing.boundary_before(files, i) {
+            writeln!(out, "==== CACHE BOUNDARY: {} files end here ====", section)?;
+            writeln!(out)?;
+        }
+        if let Some(heading) = framing.heading_before(files, i) {
+            writeln!(out, "==== {} ====", heading.to_uppercase())?;
+            writeln!(out)?;
+        }
+        pipeline::write_section(out, file)?;
+    }
+    
+    Ok(())
+}
+
+fn write_delimited(files: &[SourceFile], delimiter: &str, framing: Framing, out: &mut impl Write) -> io::Result<()> {
+    // Shells make a literal newline awkward to pass, so accept "\n" too
+    let delimiter = delimiter.replace("\\n", "\n");
+    for (i, file) in files.iter().enumerate() {
+        if let Some(section) = framing.boundary_before(files, i) {
+            writeln!(out, "==== CACHE BOUNDARY: {} files end here ====", section)?;
+            writeln!(out)?;
+        }
+        if let Some(heading) = framing.heading_before(files, i) {
+            writeln!(out, "==== {} ====", heading.to_uppercase())?;
+            writeln!(out)?;
+        }
+        let line = delimiter
+            .replace("{path}", &file.relative_path.to_string_lossy().replace('\\', "/"))
+            .replace("{language}", language::of(file).map_or("text", |l| l.name))
+            .replace("{tokens}", &file.tokens.to_string())
+            .replace("{size}", &file.size.to_string())
+            .replace("{lines}", &file.content.lines().count().to_string());
+        writeln!(out, "{}", line)?;
+        write!(out, "{}", file.content)?;
+        if !file.content.ends_with('\n') {
+            writeln!(out)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn write_markdown(files: &[SourceFile], framing: Framing, out: &mut impl Write) -> io::Result<()> {
+    let mut anchors = Anchors::default();
+    let headings: Vec<(String, String)> = files
+        .iter()
+        .map(|f| {
+            let heading = f.relative_path.to_string_lossy().replace('\\', "/");
+            let anchor = anchors.next(&heading);
+            (heading, anchor)
+        })
+        .collect();
+
+    writeln!(out, "# Table of Contents")?;
+    writeln!(out)?;
+    for (heading, anchor) in &headings {
+        writeln!(out, "- [{}](#{})", heading, anchor)?;
+    }
+    writeln!(out)?;
+
+    for (i, (file, (heading, _))) in files.iter().zip(&headings).enumerate() {
+        if let Some(section) = framing.boundary_before(files, i) {
+            writeln!(out, "<!-- cache-boundary: {} files end here -->", section)?;
+            writeln!(out)?;
+        }
+        if let Some(heading) = framing.heading_before(files, i) {
+            writeln!(out, "# {}", heading)?;
+            writeln!(out)?;
+        }
+        let fence = code_fence(&file.content);
+        writeln!(out, "## {}", heading)?;
+        writeln!(out)?;
+        writeln!(out, "{}{}", fence, language::fence_language(file))?;
+        write!(out, "{}", file.content)?;
+        if !file.content.ends_with('\n') {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", fence)?;
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+fn write_json(files: &[SourceFile], images: &[EmbeddedImage], framing: Framing, out: &mut impl Write) -> io::Result<()> {
+    let files: Vec<_> = files
+        .iter()
+        .map(|f| {
+            let mut file = json!({
+                "path": f.relative_path.to_string_lossy().replace('\\', "/"),
+                "language": language::of(f).map(|l| l.name),
+                "size": f.size,
+                "tokens": f.tokens,
+                "content": f.content,
+            });
+            if let Some(stability) = framing.stability(f) {
+                file["stability"] = json!(stability);
+            }
+            if let Some(contract) = framing.contract(f) {
+                file["contract"] = json!(contract);
+            }
+            file
+        })
+        .collect();
+    let images: Vec<_> = images
+        .iter()
+        .map(|image| {
+            json!({
+                "path": image.relative_path.to_string_lossy().replace('\\', "/"),
+                "mime": image.mime,
+                "size": image.size,
+                "data": image.data,
+            })
+        })
+        .collect();
+
+    let mut document = serde_json::Map::new();
+    if let Some(header) = framing.header {
+        document.insert("header".to_string(), json!(header));
+    }
+    document.insert("files".to_string(), json!(files));
+    document.insert("images".to_string(), json!(images));
+    if let Some(footer) = framing.footer {
+        document.insert("footer".to_string(), json!(footer));
+    }
+    serde_json::to_writer_pretty(&mut *out, &document)?;
+    writeln!(out)
+}
+
+fn write_xml(files: &[SourceFile], images: &[EmbeddedImage], framing: Framing, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(out, "<context>")?;
+    if let Some(header) = framing.header {
+        writeln!(out, "  <header><![CDATA[{}]]></header>", cdata_escape(header))?;
+    }
+    for (i, file) in files.iter().enumerate() {
+        if let Some(section) = framing.boundary_before(files, i) {
+            writeln!(out, "  <!-- cache-boundary: {} files end here -->", section)?;
+        }
+        let path = file.relative_path.to_string_lossy().replace('\\', "/");
+        let language = language::of(file).map(|l| l.name).unwrap_or("");
+        let contract = framing.contract(file).map(|kind| format!(" contract=\"{}\"", xml_escape(kind))).unwrap_or_default();
+        writeln!(out, "  <file path=\"{}\" language=\"{}\" tokens=\"{}\"{}><![CDATA[{}]]></file>",
+            xml_escape(&path), xml_escape(language), file.tokens, contract, cdata_escape(&file.content))?;
+    }
+    for image in images {
+        let path = image.relative_path.to_string_lossy().replace('\\', "/");
+        writeln!(out, "  <image path=\"{}\" mime=\"{}\" size=\"{}\">{}</image>",
+            xml_escape(&path), image.mime, image.size, image.data)?;
+    }
+    if let Some(footer) = framing.footer {
+        writeln!(out, "  <footer><![CDATA[{}]]></footer>", cdata_escape(footer))?;
+    }
+    writeln!(out, "</context>")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// "]]>" can't appear inside CDATA, so split it across two sections
+fn cdata_escape(s: &str) -> String {
+    s.replace("]]>", "]]]]><![CDATA[>")
+}
+
+/// Use a fence longer than any backtick run inside the content
+pub fn code_fence(content: &str) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in content.chars() {
+        if c == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// GitHub-style heading anchors, with numeric suffixes for duplicates
+#[derive(Default)]
+struct Anchors {
+    seen: HashMap<String, usize>,
+}
+
+impl Anchors {
+    fn next(&mut self, heading: &str) -> String {
+        let slug: String = heading
+            .to_lowercase()
+            .chars()
+            .filter_map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    Some(c)
+                } else if c == ' ' {
+                    Some('-')
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let count = self.seen.entry(slug.clone()).or_insert(0);
+        let anchor = if *count == 0 {
+            slug
+        } else {
+            format!("{}-{}", slug, count)
+        };
+        *count += 1;
+        anchor
+    }
+}
@@ -0,0 +1,63 @@
+// contracts.rs
+//! API contracts (OpenAPI and Swagger specs, GraphQL schemas, protobuf and
+//! Thrift definitions, JSON and Avro schemas) describe a whole system's
+//! interfaces in a few files, so they're placed first in a section of their own.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::SourceFile;
+
+/// Contract formats recognised by extension alone
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("proto", "Protobuf"),
+    ("graphql", "GraphQL"),
+    ("graphqls", "GraphQL"),
+    ("gql", "GraphQL"),
+    ("thrift", "Thrift"),
+    ("avsc", "Avro schema"),
+    ("smithy", "Smithy"),
+];
+
+/// How far into a YAML or JSON file the spec's top-level version key is looked for
+const SNIFF_BYTES: usize = 4096;
+
+/// The kind of contract of each file that is one
+pub fn detect(files: &[SourceFile]) -> HashMap<PathBuf, &'static str> {
+    // A top-level `openapi: 3.0.0` or `"swagger": "2.0"`
+    let spec_key = Regex::new(r#"(?m)^\s{0,2}"?(openapi|swagger|asyncapi)"?\s*:"#).expect("invalid spec pattern");
+    files
+        .iter()
+        .filter_map(|file| Some((file.relative_path.clone(), kind(&file.relative_path, &file.content, &spec_key)?)))
+        .collect()
+}
+
+fn kind(path: &Path, content: &str, spec_key: &Regex) -> Option<&'static str> {
+    let extension = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    if let Some((_, kind)) = EXTENSIONS.iter().find(|(ext, _)| *ext == extension) {
+        return Some(kind);
+    }
+    if !matches!(extension.as_str(), "yaml" | "yml" | "json") {
+        return None;
+    }
+
+    let end = (0..=SNIFF_BYTES.min(content.len())).rev().find(|&i| content.is_char_boundary(i)).unwrap_or(0);
+    let start = &content[..end];
+    if let Some(found) = spec_key.captures(start) {
+        return Some(match &found[1] {
+            "openapi" => "OpenAPI",
+            "swagger" => "Swagger",
+            _ => "AsyncAPI",
+        });
+    }
+    // Config files point "$schema" at schemastore; schemas themselves at json-schema.org
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    let is_schema = name.ends_with(".schema.json") || (start.contains("\"$schema\"") && start.contains("json-schema.org"));
+    (extension == "json" && is_schema).then_some("JSON Schema")
+}
+
+/// Move the contracts to the front, keeping the order within both groups
+pub fn place_first(files: &mut [SourceFile], contracts: &HashMap<PathBuf, &'static str>) {
+    files.sort_by_key(|file| !contracts.contains_key(&file.relative_path));
+}
@@ -0,0 +1,194 @@
+// dataset.rs
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use serde_json::json;
+
+use crate::language;
+use crate::license;
+use crate::skeleton;
+use crate::SourceFile;
+
+/// One row per file; string columns load as `string` in Hugging Face datasets
+const SCHEMA: &str = "
+message file {
+    REQUIRED BYTE_ARRAY path (UTF8);
+    OPTIONAL BYTE_ARRAY language (UTF8);
+    REQUIRED BYTE_ARRAY content (UTF8);
+    OPTIONAL BYTE_ARRAY license (UTF8);
+    REQUIRED INT64 size;
+    REQUIRED INT64 tokens;
+}
+";
+
+/// Files per row group
+const ROW_GROUP_SIZE: usize = 1000;
+
+/// Write files as a Parquet table. The license column is the file's own
+/// SPDX header, falling back to `project_license`.
+pub fn write_parquet(files: &[SourceFile], project_license: Option<&str>, path: &Path) -> io::Result<()> {
+    write(files, project_license, path).map_err(|e| io::Error::other(format!("Error writing {}: {}", path.display(), e)))
+}
+
+fn write(files: &[SourceFile], project_license: Option<&str>, path: &Path) -> Result<(), ParquetError> {
+    let schema = Arc::new(parse_message_type(SCHEMA)?);
+    let properties = Arc::new(WriterProperties::builder().set_compression(Compression::SNAPPY).build());
+    let mut writer = SerializedFileWriter::new(File::create(path)?, schema, properties)?;
+    let spdx = license::spdx_pattern();
+
+    for group in files.chunks(ROW_GROUP_SIZE) {
+        let paths: Vec<ByteArray> = group.iter().map(|f| ByteArray::from(f.relative_path.to_string_lossy().as_ref())).collect();
+        let languages: Vec<Option<&str>> = group.iter().map(|f| language::of(f).map(|l| l.name)).collect();
+        let contents: Vec<ByteArray> = group.iter().map(|f| ByteArray::from(f.content.as_str())).collect();
+        let licenses: Vec<Option<String>> = group
+            .iter()
+            .map(|f| license::file_license(&f.content, &spdx).or_else(|| project_license.map(str::to_string)))
+            .collect();
+        let sizes: Vec<i64> = group.iter().map(|f| f.size as i64).collect();
+        let tokens: Vec<i64> = group.iter().map(|f| f.tokens as i64).collect();
+
+        let mut row_group = writer.next_row_group()?;
+        let mut index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            match index {
+                0 => write_required(column.typed::<ByteArrayType>(), &paths)?,
+                1 => write_optional(column.typed::<ByteArrayType>(), &languages)?,
+                2 => write_required(column.typed::<ByteArrayType>(), &contents)?,
+                3 => write_optional(column.typed::<ByteArrayType>(), &licenses)?,
+                4 => column.typed::<Int64Type>().write_batch(&sizes, None, None).map(|_| ())?,
+                _ => column.typed::<Int64Type>().write_batch(&tokens, None, None).map(|_| ())?,
+            }
+            column.close()?;
+            index += 1;
+        }
+        row_group.close()?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+fn write_required(
+    column: &mut parquet::column::writer::ColumnWriterImpl<ByteArrayType>,
+    values: &[ByteArray],
+) -> Result<(), ParquetError> {
+    column.write_batch(values, None, None).map(|_| ())
+}
+
+/// Nulls are encoded with a definition level of 0 and no value
+fn write_optional<S: AsRef<str>>(
+    column: &mut parquet::column::writer::ColumnWriterImpl<ByteArrayType>,
+    values: &[Option<S>],
+) -> Result<(), ParquetError> {
+    let present: Vec<ByteArray> = values.iter().flatten().map(|v| ByteArray::from(v.as_ref())).collect();
+    let levels: Vec<i16> = values.iter().map(|v| v.is_some() as i16).collect();
+    column.write_batch(&present, Some(&levels), None).map(|_| ())
+}
+
+/// Shape of fine-tuning records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordStyle {
+    /// `{"prompt": ..., "completion": ...}`
+    Completion,
+    /// `{"messages": [{"role": "user", ...}, {"role": "assistant", ...}]}`
+    Chat,
+}
+
+impl FromStr for RecordStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "completion" => Ok(RecordStyle::Completion),
+            "chat" => Ok(RecordStyle::Chat),
+            _ => Err(format!("Unknown record style '{}' (expected completion or chat)", s)),
+        }
+    }
+}
+
+/// Records derived from the files: path => content ("file") and
+/// signature => body ("function")
+pub struct DatasetOptions<'a> {
+    pub style: RecordStyle,
+    pub tasks: &'a str,
+    /// Fraction of files whose records are held out for validation
+    pub val_split: f64,
+    pub seed: u64,
+}
+
+/// Write dataset-train.jsonl and dataset-val.jsonl, returning the record counts
+pub fn write_records(files: &[SourceFile], options: &DatasetOptions, output_dir: &Path) -> io::Result<(usize, usize)> {
+    let tasks: Vec<&str> = options.tasks.split(',').map(str::trim).filter(|t| !t.is_empty()).collect();
+    if let Some(unknown) = tasks.iter().find(|t| !matches!(**t, "file" | "function")) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unknown dataset task '{}' (expected file or function)", unknown),
+        ));
+    }
+
+    let mut train = BufWriter::new(File::create(output_dir.join("dataset-train.jsonl"))?);
+    let mut val = BufWriter::new(File::create(output_dir.join("dataset-val.jsonl"))?);
+    let mut counts = (0, 0);
+    for file in files {
+        let path = file.relative_path.to_string_lossy();
+        let language = language::of(file);
+        let mut records = Vec::new();
+
+        if tasks.contains(&"file") {
+            records.push((format!("Write the contents of `{}`.", path), file.content.clone()));
+        }
+        if tasks.contains(&"function") {
+            if let Some(language) = language {
+                for (signature, body) in skeleton::functions(&file.content, language) {
+                    let prompt = format!(
+                        "Complete the body of this {} function from `{}`:\n\n{}",
+                        language.name, path, signature.trim_end()
+                    );
+                    records.push((prompt, body));
+                }
+            }
+        }
+
+        // Split by file so a file's functions never leak between train and validation
+        let held_out = split_bucket(&path, options.seed) < options.val_split;
+        for (prompt, completion) in records {
+            let record = match options.style {
+                RecordStyle::Completion => json!({ "prompt": prompt, "completion": completion }),
+                RecordStyle::Chat => json!({
+                    "messages": [
+                        { "role": "user", "content": prompt },
+                        { "role": "assistant", "content": completion },
+                    ]
+                }),
+            };
+            if held_out {
+                writeln!(val, "{}", record)?;
+                counts.1 += 1;
+            } else {
+                writeln!(train, "{}", record)?;
+                counts.0 += 1;
+            }
+        }
+    }
+    train.flush()?;
+    val.flush()?;
+    Ok(counts)
+}
+
+/// Deterministic position of a path in [0, 1) for the given seed
+fn split_bucket(path: &str, seed: u64) -> f64 {
+    let mut hash: u64 = 0xcbf29ce484222325 ^ seed;
+    for byte in path.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash % 10_000) as f64 / 10_000.0
+}
@@ -0,0 +1,41 @@
+// dedup.rs
+use std::collections::HashMap;
+
+use crate::embeddings::fnv1a;
+use crate::tokens;
+use crate::tree::FileTree;
+use crate::SourceFile;
+
+/// Replace the content of every file identical to an earlier one with a note
+/// naming it, so copies in monorepos cost a line instead of their full size.
+/// Returns how many files were replaced and the tokens saved.
+pub fn dedup(files: &mut [SourceFile], tree: &mut FileTree, chars_per_token: f64) -> (usize, usize) {
+    // Hash to the indices of distinct contents with that hash
+    let mut first_seen: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut replaced = 0;
+    let mut saved = 0;
+    for i in 0..files.len() {
+        if files[i].content.trim().is_empty() {
+            continue;
+        }
+        let candidates = first_seen.entry(fnv1a(&files[i].content)).or_default();
+        let Some(&original) = candidates.iter().find(|&&j| files[j].content == files[i].content) else {
+            candidates.push(i);
+            continue;
+        };
+
+        let note = format!("(identical to {})\n", files[original].relative_path.to_string_lossy().replace('\\', "/"));
+        // Tiny files are cheaper to repeat than to reference
+        if note.len() >= files[i].content.len() {
+            continue;
+        }
+        let file = &mut files[i];
+        let before = file.tokens;
+        file.content = note;
+        file.tokens = tokens::estimate_tokens(&file.content, chars_per_token);
+        tree.update_tokens(&file.relative_path, before, file.tokens);
+        replaced += 1;
+        saved += before.saturating_sub(file.tokens);
+    }
+    (replaced, saved)
+}
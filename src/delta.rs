@@ -0,0 +1,83 @@
+// delta.rs
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::embeddings::fnv1a;
+use crate::SourceFile;
+
+/// Content hashes from the last run into this output directory
+const SNAPSHOT_FILE: &str = ".snapshot.json";
+
+/// Content hash of every file, keyed by path
+pub type Snapshot = HashMap<PathBuf, u64>;
+
+pub fn snapshot(files: &[SourceFile]) -> Snapshot {
+    files.iter().map(|f| (f.relative_path.clone(), fnv1a(&f.content))).collect()
+}
+
+/// The snapshot saved by the last run, if there was one
+pub fn load(output_dir: &Path) -> io::Result<Option<Snapshot>> {
+    let path = output_dir.join(SNAPSHOT_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let hashes: BTreeMap<String, Value> = serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Couldn't read {}: {}", path.display(), e)))?;
+    Ok(Some(
+        hashes
+            .into_iter()
+            .filter_map(|(path, hash)| Some((PathBuf::from(path), u64::from_str_radix(hash.as_str()?, 16).ok()?)))
+            .collect(),
+    ))
+}
+
+pub fn save(output_dir: &Path, snapshot: &Snapshot) -> io::Result<()> {
+    // Sorted so unchanged runs leave the file the same
+    let hashes: BTreeMap<String, String> = snapshot
+        .iter()
+        .map(|(path, hash)| (path.to_string_lossy().replace('\\', "/"), format!("{:016x}", hash)))
+        .collect();
+    fs::write(output_dir.join(SNAPSHOT_FILE), serde_json::to_string_pretty(&hashes)?)
+}
+
+/// How a file differs from the previous snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl Change {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::Added => "new",
+            Change::Modified => "changed",
+            Change::Deleted => "deleted",
+        }
+    }
+}
+
+/// Every path that differs between the two snapshots
+pub fn changes(previous: &Snapshot, current: &Snapshot) -> BTreeMap<PathBuf, Change> {
+    let mut changes = BTreeMap::new();
+    for (path, hash) in current {
+        match previous.get(path) {
+            None => {
+                changes.insert(path.clone(), Change::Added);
+            }
+            Some(old) if old != hash => {
+                changes.insert(path.clone(), Change::Modified);
+            }
+            Some(_) => {}
+        }
+    }
+    for path in previous.keys().filter(|path| !current.contains_key(*path)) {
+        changes.insert(path.clone(), Change::Deleted);
+    }
+    changes
+}
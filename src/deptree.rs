@@ -0,0 +1,91 @@
+// deptree.rs
+//! `--dependency-tree`: the resolved dependencies as the ecosystem's own tool
+//! reports them (`cargo tree`, `npm ls`, ...), which manifests alone don't show.
+use std::io::Read;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::profiles;
+use crate::roots::Root;
+
+/// The command run for each detected project type
+const TOOLS: &[(&str, &str, &[&str])] = &[
+    ("rust", "cargo", &["tree", "--edges", "normal", "--depth", "2"]),
+    ("node", "npm", &["ls", "--depth=1"]),
+    ("python", "pipdeptree", &["--warn", "silence"]),
+    ("go", "go", &["list", "-m", "all"]),
+];
+
+/// Each tool's output under the command that produced it, for every root.
+/// A tool that's missing, fails or runs past `timeout` gets a note instead.
+pub fn capture(roots: &[Root], timeout: Duration) -> String {
+    let mut out = String::new();
+    for root in roots {
+        for project_type in profiles::detect(std::slice::from_ref(root)) {
+            let Some((_, program, args)) = TOOLS.iter().find(|(name, _, _)| *name == project_type) else { continue };
+            out.push_str(&format!("$ {} {}  (in {})\n", program, args.join(" "), root.dir.display()));
+            match run(&root.dir, program, args, timeout) {
+                Ok(output) => out.push_str(output.trim_end()),
+                Err(note) => out.push_str(&format!("({})", note)),
+            }
+            out.push_str("\n\n");
+        }
+    }
+    if out.is_empty() {
+        out.push_str("No Rust, Node, Python or Go project found to list dependencies for.\n");
+    }
+    out
+}
+
+fn run(dir: &Path, program: &str, args: &[&str], timeout: Duration) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("couldn't run {}: {}", program, e))?;
+
+    // Read while waiting, a full pipe would block the tool
+    let stdout = read_in_background(&mut child, true);
+    let stderr = read_in_background(&mut child, false);
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("{} timed out after {}s", program, timeout.as_secs()));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    // npm ls exits non-zero for problems it still prints the tree for
+    if !status.success() && stdout.trim().is_empty() {
+        let stderr = stderr.join().unwrap_or_default();
+        let reason = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("no output").trim().to_string();
+        return Err(format!("{} failed ({}): {}", program, status, reason));
+    }
+    Ok(stdout)
+}
+
+fn read_in_background(child: &mut Child, stdout: bool) -> thread::JoinHandle<String> {
+    let mut pipe: Option<Box<dyn Read + Send>> = if stdout {
+        child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>)
+    } else {
+        child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>)
+    };
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(pipe) = pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
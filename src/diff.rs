@@ -0,0 +1,172 @@
+// diff.rs
+//! Compare two generated contexts, so a model that has seen the old one can
+//! be sent only what changed.
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::combined::{self, Framing, OutputFormat};
+use crate::tokens;
+use crate::SourceFile;
+
+/// How each file differs between the old and the new context
+pub struct ContextDiff {
+    pub added: Vec<SourceFile>,
+    pub removed: Vec<SourceFile>,
+    /// Old and new version
+    pub changed: Vec<(SourceFile, SourceFile)>,
+    pub unchanged: usize,
+}
+
+impl ContextDiff {
+    pub fn between(old: Vec<SourceFile>, new: Vec<SourceFile>) -> Self {
+        let mut old: HashMap<PathBuf, SourceFile> = old.into_iter().map(|f| (f.relative_path.clone(), f)).collect();
+        let mut diff = ContextDiff { added: Vec::new(), removed: Vec::new(), changed: Vec::new(), unchanged: 0 };
+        for file in new {
+            match old.remove(&file.relative_path) {
+                Some(previous) if previous.content == file.content => diff.unchanged += 1,
+                Some(previous) => diff.changed.push((previous, file)),
+                None => diff.added.push(file),
+            }
+        }
+        diff.removed = old.into_values().collect();
+        diff.removed.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        diff
+    }
+
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        report.push_str(&format!("Added ({}):\n", self.added.len()));
+        for file in &self.added {
+            report.push_str(&format!("  + {} (~{} tokens)\n", file.relative_path.display(), file.tokens));
+        }
+        report.push_str(&format!("Removed ({}):\n", self.removed.len()));
+        for file in &self.removed {
+            report.push_str(&format!("  - {}\n", file.relative_path.display()));
+        }
+        report.push_str(&format!("Changed ({}):\n", self.changed.len()));
+        for (old, new) in &self.changed {
+            report.push_str(&format!("  ~ {} (~{} tokens, was ~{})\n", new.relative_path.display(), new.tokens, old.tokens));
+        }
+        report.push_str(&format!("Unchanged: {}\n", self.unchanged));
+        report
+    }
+}
+
+/// The files of a generated output directory, read back from its
+/// combined.json, combined.txt or combined.md, along with that file's format
+pub fn load(output_dir: &Path) -> io::Result<(Vec<SourceFile>, OutputFormat)> {
+    for format in [OutputFormat::Json, OutputFormat::Text, OutputFormat::Markdown] {
+        let path = output_dir.join(format!("combined.{}", format.extension()));
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        let files = match format {
+            OutputFormat::Json => parse_json(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Couldn't read {}: {}", path.display(), e)))?,
+            OutputFormat::Markdown => parse_markdown(&content),
+            _ => parse_text(&content),
+        };
+        return Ok((files, format));
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("No combined.json, combined.txt or combined.md in {} (generate it with --combined)", output_dir.display()),
+    ))
+}
+
+/// Write the added and changed files as `combined.<ext>`, with the report in `changes.txt`
+pub fn write_bundle(diff: &ContextDiff, format: OutputFormat, bundle_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(bundle_dir)?;
+    let files: Vec<SourceFile> = diff.added.iter().chain(diff.changed.iter().map(|(_, new)| new)).cloned().collect();
+    combined::write_combined(&files, &[], Framing::default(), format, &bundle_dir.join(format!("combined.{}", format.extension())))?;
+    fs::write(bundle_dir.join("changes.txt"), diff.report())
+}
+
+fn parse_json(content: &str) -> Result<Vec<SourceFile>, serde_json::Error> {
+    let document: Value = serde_json::from_str(content)?;
+    let files = document["files"].as_array().map(Vec::as_slice).unwrap_or_default();
+    Ok(files
+        .iter()
+        .filter_map(|file| {
+            let content = file["content"].as_str()?;
+            Some(loaded(file["path"].as_str()?, content, file["tokens"].as_u64().map(|t| t as usize)))
+        })
+        .collect())
+}
+
+/// Files in the text format: a `File: path` header between separator lines,
+/// a blank line, the content and another blank line
+fn parse_text(content: &str) -> Vec<SourceFile> {
+    let separator = "=".repeat(64);
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let is_header = |i: usize| {
+        i + 2 < lines.len()
+            && lines[i].trim_end() == separator
+            && lines[i + 1].starts_with("File: ")
+            && lines[i + 2].trim_end() == separator
+    };
+
+    let mut files = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !is_header(i) {
+            i += 1;
+            continue;
+        }
+        let path = lines[i + 1]["File: ".len()..].trim_end();
+        let start = (i + 4).min(lines.len());
+        let mut end = start;
+        while end < lines.len() && !is_header(end) && !lines[end].starts_with("==== CACHE BOUNDARY:") {
+            end += 1;
+        }
+        let body = lines[start..end].concat();
+        // The writer adds a blank line after every file
+        let body = body.strip_suffix('\n').unwrap_or(&body);
+        files.push(loaded(path, body, None));
+        i = end;
+    }
+    files
+}
+
+/// Files in the markdown format: a `## path` heading and a fenced code block
+fn parse_markdown(content: &str) -> Vec<SourceFile> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut files = Vec::new();
+    let mut i = 0;
+    while i + 2 < lines.len() {
+        let Some(path) = lines[i].strip_prefix("## ") else {
+            i += 1;
+            continue;
+        };
+        let opening = lines[i + 2].trim_end();
+        let fence: String = opening.chars().take_while(|&c| c == '`').collect();
+        if fence.len() < 3 {
+            i += 1;
+            continue;
+        }
+        let start = i + 3;
+        let mut end = start;
+        while end < lines.len() && lines[end].trim_end() != fence {
+            end += 1;
+        }
+        files.push(loaded(path.trim_end(), &lines[start..end].concat(), None));
+        i = end + 1;
+    }
+    files
+}
+
+fn loaded(path: &str, content: &str, tokens: Option<usize>) -> SourceFile {
+    SourceFile {
+        relative_path: PathBuf::from(path),
+        content: content.to_string(),
+        size: content.len() as u64,
+        modified: None,
+        tokens: tokens.unwrap_or_else(|| tokens::estimate_tokens(content, tokens::DEFAULT_CHARS_PER_TOKEN)),
+        redactions: Vec::new(),
+    }
+}
@@ -0,0 +1,56 @@
+// documents.rs
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use regex::Regex;
+
+/// Whether text can be extracted from this file with `--extract-documents`
+pub fn is_document(path: &Path) -> bool {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    matches!(extension.to_lowercase().as_str(), "pdf" | "docx")
+}
+
+/// Extract the plain text of a PDF or DOCX document
+pub fn extract_text(path: &Path) -> Result<String, String> {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    match extension.to_lowercase().as_str() {
+        "pdf" => pdf_extract::extract_text(path).map_err(|e| e.to_string()),
+        "docx" => extract_docx(path),
+        _ => Err(format!("Unsupported document type: {}", path.display())),
+    }
+}
+
+fn extract_docx(path: &Path) -> Result<String, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|e| e.to_string())?
+        .read_to_string(&mut xml)
+        .map_err(|e| e.to_string())?;
+
+    // Text runs, paragraph ends, tabs and line breaks are all we need
+    let pattern = Regex::new(r"<w:t(?:\s[^>]*)?>([^<]*)</w:t>|</w:p>|<w:tab/>|<w:br/>")
+        .expect("invalid docx pattern");
+    let mut text = String::new();
+    for caps in pattern.captures_iter(&xml) {
+        match caps.get(1) {
+            Some(run) => text.push_str(&unescape_xml(run.as_str())),
+            None => match &caps[0] {
+                "<w:tab/>" => text.push('\t'),
+                _ => text.push('\n'),
+            },
+        }
+    }
+    Ok(text)
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
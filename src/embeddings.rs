@@ -0,0 +1,214 @@
+// embeddings.rs
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde_json::{json, Value};
+
+use crate::chunk::Chunk;
+
+/// Where embeddings come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    /// Hashed bag of identifiers, computed locally without a model
+    Local,
+    /// OpenAI-compatible `/embeddings` endpoint
+    OpenAi,
+    /// Ollama's `/api/embed` endpoint
+    Ollama,
+}
+
+impl FromStr for Provider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "local" => Ok(Provider::Local),
+            "openai" => Ok(Provider::OpenAi),
+            "ollama" => Ok(Provider::Ollama),
+            _ => Err(format!("Unknown embedding provider '{}' (expected local, openai or ollama)", s)),
+        }
+    }
+}
+
+/// Dimensions of the local hashed embeddings
+const LOCAL_DIMENSIONS: usize = 1024;
+
+/// Words too common to say anything about relevance
+const STOP_WORDS: &[&str] = &[
+    "the", "an", "is", "are", "was", "be", "do", "does", "did", "why", "how", "what", "when",
+    "where", "which", "who", "on", "in", "of", "to", "for", "and", "or", "with", "it", "this", "that",
+];
+
+/// Texts sent per API request
+const BATCH_SIZE: usize = 64;
+
+pub struct Embedder {
+    provider: Provider,
+    model: String,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl Embedder {
+    /// `model` and `url` default per provider. The key comes from the variable named
+    /// by `api_key_env`, or from OPENAI_API_KEY for OpenAI's own API only, so a custom
+    /// `url` never gets the OpenAI key unless asked to
+    pub fn new(provider: Provider, model: Option<&str>, url: Option<&str>, api_key_env: Option<&str>) -> io::Result<Self> {
+        let (default_model, default_url) = match provider {
+            Provider::Local => ("hashed-identifiers", ""),
+            Provider::OpenAi => ("text-embedding-3-small", "https://api.openai.com/v1"),
+            Provider::Ollama => ("nomic-embed-text", "http://localhost:11434"),
+        };
+        let api_key = match api_key_env {
+            Some(var) => Some(env::var(var).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("{} (from --embedding-api-key-env) is not set", var))
+            })?),
+            None if provider == Provider::OpenAi && url.is_none() => Some(env::var("OPENAI_API_KEY").map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "OPENAI_API_KEY must be set for --embedding-provider openai")
+            })?),
+            None => None,
+        };
+        Ok(Embedder {
+            provider,
+            model: model.unwrap_or(default_model).to_string(),
+            url: url.unwrap_or(default_url).trim_end_matches('/').to_string(),
+            api_key,
+        })
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn embed(&self, texts: &[&str]) -> io::Result<Vec<Vec<f32>>> {
+        if self.provider == Provider::Local {
+            return Ok(texts.iter().map(|text| local_embedding(text)).collect());
+        }
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            vectors.extend(self.request(batch)?);
+        }
+        Ok(vectors)
+    }
+
+    fn request(&self, batch: &[&str]) -> io::Result<Vec<Vec<f32>>> {
+        let (endpoint, body) = match self.provider {
+            Provider::OpenAi => (format!("{}/embeddings", self.url), json!({ "model": self.model, "input": batch })),
+            Provider::Ollama => (format!("{}/api/embed", self.url), json!({ "model": self.model, "input": batch })),
+            Provider::Local => unreachable!("local embeddings don't use the network"),
+        };
+        let mut request = ureq::post(&endpoint);
+        if let Some(key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", key));
+        }
+        let response: Value = request
+            .send_json(body)
+            .map_err(|e| io::Error::other(format!("Embedding request to {} failed: {}", endpoint, e)))?
+            .into_json()?;
+
+        // OpenAI: {"data": [{"embedding": [...]}]}, Ollama: {"embeddings": [[...]]}
+        let vectors: Option<Vec<Vec<f32>>> = match self.provider {
+            Provider::OpenAi => response["data"]
+                .as_array()
+                .map(|data| data.iter().map(|d| to_vector(&d["embedding"])).collect()),
+            _ => response["embeddings"].as_array().map(|data| data.iter().map(to_vector).collect()),
+        };
+        match vectors {
+            Some(vectors) if vectors.len() == batch.len() => Ok(vectors),
+            _ => Err(io::Error::other(format!("Unexpected embedding response from {}", endpoint))),
+        }
+    }
+}
+
+fn to_vector(value: &Value) -> Vec<f32> {
+    value
+        .as_array()
+        .map(|items| items.iter().filter_map(Value::as_f64).map(|x| x as f32).collect())
+        .unwrap_or_default()
+}
+
+/// Feature-hashed, sublinear term frequencies of lowercased identifier parts,
+/// so `PaymentProcessor` and `payment_processor` land on the same terms
+fn local_embedding(text: &str) -> Vec<f32> {
+    let mut vector = vec![0f32; LOCAL_DIMENSIONS];
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        for term in split_identifier(word) {
+            if term.len() < 2 || STOP_WORDS.contains(&term.as_str()) {
+                continue;
+            }
+            let hash = fnv1a(&term);
+            vector[(hash % LOCAL_DIMENSIONS as u64) as usize] += 1.0;
+        }
+    }
+    for x in vector.iter_mut() {
+        if *x > 0.0 {
+            *x = 1.0 + x.ln();
+        }
+    }
+    normalize(&mut vector);
+    vector
+}
+
+/// Split camelCase and digits apart, lowercasing every part
+fn split_identifier(word: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in word.chars() {
+        if c.is_uppercase() && previous_lower && !current.is_empty() {
+            parts.push(std::mem::take(&mut current));
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+pub fn fnv1a(s: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in s.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+/// One JSON object per chunk: path, line range, model and vector
+pub fn write_jsonl(path: &Path, chunks: &[Chunk], vectors: &[Vec<f32>], model: &str) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for (chunk, vector) in chunks.iter().zip(vectors) {
+        let record = json!({
+            "path": chunk.path.to_string_lossy(),
+            "start_line": chunk.start_line,
+            "end_line": chunk.end_line,
+            "model": model,
+            "embedding": vector,
+        });
+        writeln!(out, "{}", record)?;
+    }
+    out.flush()
+}
+
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
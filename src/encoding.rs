@@ -0,0 +1,29 @@
+// encoding.rs
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Decode file contents to UTF-8, stripping any BOM. Files that aren't valid
+/// UTF-8 are transcoded from the detected encoding (Latin-1, Shift-JIS, UTF-16, ...).
+/// Returns the text and the name of the source encoding.
+pub fn decode(bytes: &[u8]) -> (String, &'static str) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return (text.into_owned(), encoding.name());
+    }
+
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return (text.to_string(), UTF_8.name());
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), encoding.name())
+}
+
+/// Whether `\n` bytes are line ends, as in UTF-8, Latin-1 and Shift-JIS but
+/// not UTF-16 (which only comes with a BOM here)
+pub fn ascii_compatible(bytes: &[u8]) -> bool {
+    !matches!(Encoding::for_bom(bytes), Some((encoding, _)) if encoding == UTF_16LE || encoding == UTF_16BE)
+}
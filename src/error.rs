@@ -0,0 +1,74 @@
+// error.rs
+//! What can go wrong in a run. Failures confined to one file (it couldn't be
+//! read, converted or written) are collected rather than ending the run, and
+//! reported together in errors.txt at the end; `pipeline::generate` returns
+//! them alongside the context.
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+#[derive(Error)]
+pub enum ContextGenError {
+    /// The walker couldn't read a directory or entry
+    #[cfg(feature = "native")]
+    #[error("{source}")]
+    Walk { root: PathBuf, source: ignore::Error },
+    /// A file couldn't be opened or read
+    #[error("couldn't read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    /// A file couldn't be turned into text (e.g. a PDF without a text layer)
+    #[error("couldn't convert {} to text: {reason}", path.display())]
+    Encoding { path: PathBuf, reason: String },
+    /// A --preprocess command failed on a file
+    #[error("preprocessor `{command}` failed on {}: {reason}", path.display())]
+    Preprocess { path: PathBuf, command: String, reason: String },
+    /// `git lfs smudge` couldn't produce the object behind an LFS pointer
+    #[error("couldn't fetch the LFS object for {}: {reason}", path.display())]
+    LfsFetch { path: PathBuf, reason: String },
+    /// An output file couldn't be written
+    #[error("couldn't write {}: {source}", path.display())]
+    Output { path: PathBuf, source: io::Error },
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+// Returned from main, whose error is printed with Debug
+impl fmt::Debug for ContextGenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl ContextGenError {
+    /// The file the failure is about, for the variants that have one
+    pub fn path_mut(&mut self) -> Option<&mut PathBuf> {
+        match self {
+            ContextGenError::Read { path, .. }
+            | ContextGenError::Encoding { path, .. }
+            | ContextGenError::Preprocess { path, .. }
+            | ContextGenError::LfsFetch { path, .. }
+            | ContextGenError::Output { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+/// For the modules that report io::Result
+impl From<ContextGenError> for io::Error {
+    fn from(error: ContextGenError) -> Self {
+        match error {
+            ContextGenError::Io(e) => e,
+            ContextGenError::InvalidInput(message) => io::Error::new(io::ErrorKind::InvalidInput, message),
+            other => io::Error::other(other.to_string()),
+        }
+    }
+}
+
+/// errors.txt: one line per failure
+pub fn report(errors: &[ContextGenError]) -> String {
+    errors.iter().map(|e| format!("{}\n", e)).collect()
+}
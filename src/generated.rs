@@ -0,0 +1,48 @@
+// generated.rs
+
+/// Markers that code generators put at the top of their output
+const GENERATED_MARKERS: &[&str] = &["@generated", "do not edit", "auto-generated", "autogenerated"];
+
+const COMMENT_PREFIXES: &[&str] = &["//", "#", "/*", "*", "<!--", "--", ";"];
+
+/// Reason a file looks minified or machine-generated, if it does
+pub fn detect(file_name: &str, content: &str) -> Option<&'static str> {
+    let lower_name = file_name.to_lowercase();
+    if lower_name.ends_with(".map") && content.contains("\"mappings\"") {
+        return Some("sourcemap");
+    }
+    if lower_name.contains(".min.") {
+        return Some("minified");
+    }
+
+    // Only comments in the first few lines count, not code that mentions the markers
+    let generated = content
+        .lines()
+        .take(5)
+        .map(|line| line.trim_start().to_lowercase())
+        .filter(|line| COMMENT_PREFIXES.iter().any(|prefix| line.starts_with(prefix)))
+        .any(|line| GENERATED_MARKERS.iter().any(|marker| line.contains(marker)));
+    if generated {
+        return Some("generated");
+    }
+
+    if looks_minified(content) {
+        return Some("minified");
+    }
+    None
+}
+
+/// Very long lines with little whitespace, as produced by bundlers and minifiers
+fn looks_minified(content: &str) -> bool {
+    if content.len() < 2000 {
+        return false;
+    }
+
+    let lines = content.lines().count().max(1);
+    let longest = content.lines().map(str::len).max().unwrap_or(0);
+    let average = content.len() / lines;
+    let whitespace = content.bytes().filter(u8::is_ascii_whitespace).count();
+    let whitespace_ratio = whitespace as f64 / content.len() as f64;
+
+    average > 300 || (longest > 1000 && whitespace_ratio < 0.1)
+}
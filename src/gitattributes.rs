@@ -0,0 +1,87 @@
+// gitattributes.rs
+use std::fs;
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+/// Linguist attributes that mark files as not worth including
+const LINGUIST_ATTRIBUTES: &[&str] = &["linguist-generated", "linguist-vendored"];
+
+struct Rule {
+    matcher: GlobMatcher,
+    attribute: &'static str,
+    set: bool,
+}
+
+/// `linguist-generated` and `linguist-vendored` markers from the root .gitattributes
+#[derive(Default)]
+pub struct GitAttributes {
+    rules: Vec<Rule>,
+}
+
+impl GitAttributes {
+    /// Load `<dir>/.gitattributes`; a missing or unreadable file has no rules
+    pub fn load(dir: &Path) -> Self {
+        match fs::read_to_string(dir.join(".gitattributes")) {
+            Ok(content) => Self::parse(&content),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let Some(pattern) = fields.next() else { continue };
+            let Some(matcher) = compile_pattern(pattern) else { continue };
+
+            for field in fields {
+                // `attr`, `attr=true` and `attr=<anything>` set it; `-attr`, `!attr` and `attr=false` unset it
+                let (name, set) = match field.strip_prefix(['-', '!']) {
+                    Some(name) => (name, false),
+                    None => match field.split_once('=') {
+                        Some((name, value)) => (name, value != "false"),
+                        None => (field, true),
+                    },
+                };
+                if let Some(attribute) = LINGUIST_ATTRIBUTES.iter().find(|a| **a == name) {
+                    rules.push(Rule { matcher: matcher.clone(), attribute, set });
+                }
+            }
+        }
+        GitAttributes { rules }
+    }
+
+    /// The linguist attribute set on this path, if any. Later lines win, as in git.
+    pub fn linguist_reason(&self, relative_path: &Path) -> Option<&'static str> {
+        LINGUIST_ATTRIBUTES.iter().copied().find(|attribute| {
+            self.rules
+                .iter()
+                .rev()
+                .find(|rule| rule.attribute == *attribute && rule.matcher.is_match(relative_path))
+                .is_some_and(|rule| rule.set)
+        })
+    }
+}
+
+/// Patterns without a slash match at any depth; others are relative to the root
+fn compile_pattern(pattern: &str) -> Option<GlobMatcher> {
+    // Directory patterns never match files in .gitattributes
+    if pattern.ends_with('/') {
+        return None;
+    }
+    let glob = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{}", pattern),
+    };
+    GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .ok()
+        .map(|g| g.compile_matcher())
+}
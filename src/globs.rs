@@ -0,0 +1,11 @@
+// globs.rs
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Build a glob set from a comma-separated list of patterns
+pub fn build_glob_set(patterns: &str) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build()
+}
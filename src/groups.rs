@@ -0,0 +1,65 @@
+// groups.rs
+//! `--group-by dir`: one combined output per directory, for giving a model one
+//! subsystem at a time.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::tree::FileTree;
+use crate::SourceFile;
+
+/// How files are grouped into separate combined outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Dir,
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dir" | "directory" => Ok(GroupBy::Dir),
+            _ => Err(format!("Unknown grouping '{}' (expected dir)", s)),
+        }
+    }
+}
+
+/// Files grouped by their directory `depth` levels down (src/api/users.rs is
+/// in src at depth 1 and src/api at depth 2). Files less deep than that belong
+/// to the directory they're in, top-level files to ".".
+pub fn by_dir(files: &[SourceFile], depth: usize) -> BTreeMap<PathBuf, Vec<SourceFile>> {
+    let mut groups: BTreeMap<PathBuf, Vec<SourceFile>> = BTreeMap::new();
+    for file in files {
+        let parent = file.relative_path.parent().unwrap_or(Path::new(""));
+        let dir: PathBuf = parent.components().take(depth.max(1)).collect();
+        let dir = if dir.as_os_str().is_empty() { PathBuf::from(".") } else { dir };
+        groups.entry(dir).or_default().push(file.clone());
+    }
+    groups
+}
+
+/// Put before a group's files: its name, token count and its own file tree
+pub fn header(dir: &Path, files: &[SourceFile]) -> String {
+    let mut tree = FileTree::new(true);
+    for file in files {
+        tree.add_file(&file.relative_path, file.size, file.tokens);
+    }
+    format!(
+        "Directory: {} ({} files, ~{} tokens)\n\n{}\n",
+        dir.display(),
+        files.len(),
+        tree.total_tokens(),
+        tree.render()
+    )
+}
+
+pub fn summary(groups: &BTreeMap<PathBuf, Vec<SourceFile>>) -> String {
+    let mut out = format!("Directories ({}):\n", groups.len());
+    for (dir, files) in groups {
+        let tokens: usize = files.iter().map(|f| f.tokens).sum();
+        let _ = writeln!(out, "  {} - {} files, ~{} tokens", dir.display(), files.len(), tokens);
+    }
+    out
+}
@@ -0,0 +1,413 @@
+// history.rs
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+
+use crate::paths;
+use crate::roots::Root;
+use crate::SourceFile;
+
+/// Recent commits looked at to estimate how often files change
+const HISTORY_DEPTH: &str = "200";
+
+/// Longer author names are cut in `--blame lines` prefixes
+const MAX_AUTHOR_WIDTH: usize = 20;
+
+/// How likely a file is to change before the next run; the combined output
+/// is ordered by this so prompt caches can reuse the longest possible prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stability {
+    /// At most one change in recent history (e.g. just the commit that added it)
+    Stable,
+    /// A few recent changes
+    Occasional,
+    /// Changed often, or has uncommitted changes
+    Volatile,
+}
+
+impl Stability {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stability::Stable => "stable",
+            Stability::Occasional => "occasional",
+            Stability::Volatile => "volatile",
+        }
+    }
+
+    fn from_changes(changes: usize) -> Self {
+        match changes {
+            0..=1 => Stability::Stable,
+            2..=5 => Stability::Occasional,
+            _ => Stability::Volatile,
+        }
+    }
+}
+
+/// Stability of each file from how many of the last commits touched it, or
+/// from modification times for roots that aren't in a git repository
+pub fn classify(roots: &[Root], files: &[SourceFile]) -> HashMap<PathBuf, Stability> {
+    let mut changes = HashMap::new();
+    let mut uncommitted = HashSet::new();
+    let mut without_history = Vec::new();
+    for root in roots {
+        match change_counts(&root.dir) {
+            Some(counts) => {
+                changes.extend(counts.into_iter().map(|(path, count)| (root.prefix.join(path), count)));
+                uncommitted.extend(uncommitted_files(&root.dir).into_iter().map(|path| root.prefix.join(path)));
+            }
+            None => {
+                eprintln!("No git history found in {}, estimating file stability from modification times", root.dir.display());
+                without_history.push(&root.prefix);
+            }
+        }
+    }
+
+    files
+        .iter()
+        .map(|f| {
+            let stability = if without_history.iter().any(|prefix| f.relative_path.starts_with(prefix)) {
+                stability_from_mtime(f.modified)
+            } else if uncommitted.contains(&f.relative_path) {
+                Stability::Volatile
+            } else {
+                Stability::from_changes(changes.get(&f.relative_path).copied().unwrap_or(0))
+            };
+            (f.relative_path.clone(), stability)
+        })
+        .collect()
+}
+
+/// A point in time given as an age (`14d`, `2w`, `12h`) or a date (`2024-05-01`, UTC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Since(pub SystemTime);
+
+impl FromStr for Since {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid time '{}' (expected an age like 14d, 2w or 12h, or a date like 2024-05-01)", s);
+        let s = s.trim();
+        if let Some((year, rest)) = s.split_once('-') {
+            let mut parts = rest.splitn(2, '-');
+            let (Some(month), Some(day)) = (parts.next(), parts.next()) else { return Err(invalid()) };
+            let (year, month, day) = (year.parse().map_err(|_| invalid())?, month.parse().map_err(|_| invalid())?, day.parse().map_err(|_| invalid())?);
+            if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+                return Err(invalid());
+            }
+            let days = days_from_civil(year, month, day);
+            let secs = u64::try_from(days * 86_400).map_err(|_| invalid())?;
+            return Ok(Since(UNIX_EPOCH + Duration::from_secs(secs)));
+        }
+        let unit = s.chars().last().ok_or_else(invalid)?;
+        let amount: u64 = s[..s.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+        let hours = match unit {
+            'h' => amount,
+            'd' => amount * 24,
+            'w' => amount * 24 * 7,
+            _ => return Err(invalid()),
+        };
+        Ok(Since(SystemTime::now() - Duration::from_secs(hours * 3600)))
+    }
+}
+
+/// The date `days` after 1970-01-01, the inverse of `days_from_civil`
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Files changed since `since`: committed to since then or with uncommitted
+/// changes, or modified since then for roots that aren't in a git repository
+/// (where a fresh checkout would make every file look new)
+pub fn changed_since(roots: &[Root], files: &[SourceFile], since: Since) -> HashSet<PathBuf> {
+    let timestamp = since.0.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let mut changed = HashSet::new();
+    let mut without_history = Vec::new();
+    for root in roots {
+        let after = format!("--since=@{}", timestamp);
+        match git(&root.dir, &["log", &after, "--format=", "--name-only", "--relative"]) {
+            Some(log) => {
+                let committed = log.lines().filter(|l| !l.is_empty()).map(|l| paths::normalize(Path::new(l)));
+                changed.extend(committed.chain(uncommitted_files(&root.dir)).map(|path| root.prefix.join(path)));
+            }
+            None => without_history.push(&root.prefix),
+        }
+    }
+    for file in files {
+        let by_mtime = without_history.iter().any(|prefix| file.relative_path.starts_with(prefix));
+        if by_mtime && file.modified.is_some_and(|m| m >= since.0) {
+            changed.insert(file.relative_path.clone());
+        }
+    }
+    changed
+}
+
+/// Files with commits by `author` (matched against "Name <email>" the way
+/// `git log --author` does) among the last commits, or since `since`. With a
+/// `min_share`, only those where blame credits the author with at least that
+/// percentage of the lines.
+pub fn authored_by(roots: &[Root], files: &[SourceFile], author: &str, since: Option<Since>, min_share: Option<f64>) -> Result<HashSet<PathBuf>, String> {
+    let pattern = Regex::new(author).map_err(|e| format!("Invalid --author pattern: {}", e))?;
+    let author_arg = format!("--author={}", author);
+    let since_arg = since.map(|since| format!("--since=@{}", since.0.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)));
+    let mut authored = HashSet::new();
+    for root in roots {
+        let mut args = vec!["log", &author_arg, "--format=", "--name-only", "--relative"];
+        match &since_arg {
+            Some(since_arg) => args.push(since_arg),
+            None => args.extend(["-n", HISTORY_DEPTH]),
+        }
+        let Some(log) = git(&root.dir, &args) else {
+            eprintln!("No git history found in {}, --author leaves out all of its files", root.dir.display());
+            continue;
+        };
+        let touched: HashSet<PathBuf> = log.lines().filter(|l| !l.is_empty()).map(|l| root.prefix.join(paths::normalize(Path::new(l)))).collect();
+        for file in files.iter().filter(|file| touched.contains(&file.relative_path)) {
+            if let Some(min) = min_share {
+                let in_root = file.relative_path.strip_prefix(&root.prefix).unwrap_or(&file.relative_path);
+                if blame_share(&root.dir, in_root, &pattern).unwrap_or(0.0) < min {
+                    continue;
+                }
+            }
+            authored.insert(file.relative_path.clone());
+        }
+    }
+    Ok(authored)
+}
+
+/// Percentage of the file's lines whose author matches
+fn blame_share(dir: &Path, path: &Path, author: &Regex) -> Option<f64> {
+    let lines = blame(&dir.join(path))?;
+    let matching = lines.iter().filter(|line| author.is_match(&format!("{} {}", line.author, line.mail))).count();
+    (!lines.is_empty()).then(|| matching as f64 * 100.0 / lines.len() as f64)
+}
+
+/// `--blame`: annotate files with who last changed them and when
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blame {
+    /// One comment line on top with the file's latest change
+    Header,
+    /// Every line prefixed with its last commit, date and author
+    Lines,
+}
+
+impl FromStr for Blame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "header" => Ok(Blame::Header),
+            "lines" => Ok(Blame::Lines),
+            _ => Err(format!("Unknown blame mode '{}' (expected header or lines)", s)),
+        }
+    }
+}
+
+/// The last change to one line of a file
+#[derive(Debug, Default)]
+pub struct BlameLine {
+    pub commit: String,
+    pub author: String,
+    pub mail: String,
+    pub time: i64,
+    pub summary: String,
+    pub content: String,
+}
+
+impl BlameLine {
+    fn is_uncommitted(&self) -> bool {
+        self.commit.bytes().all(|b| b == b'0')
+    }
+
+    fn short_commit(&self) -> &str {
+        &self.commit[..self.commit.len().min(8)]
+    }
+}
+
+/// `git blame` of the file at `path`, one entry per line; None outside git or
+/// for files git doesn't track
+pub fn blame(path: &Path) -> Option<Vec<BlameLine>> {
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name()?.to_string_lossy();
+    let porcelain = git(dir, &["blame", "--line-porcelain", "--", &name])?;
+    let mut lines = Vec::new();
+    let mut current = BlameLine::default();
+    for line in porcelain.lines() {
+        if let Some(content) = line.strip_prefix('\t') {
+            current.content = content.to_string();
+            lines.push(std::mem::take(&mut current));
+        } else if let Some(author) = line.strip_prefix("author ") {
+            current.author = author.to_string();
+        } else if let Some(mail) = line.strip_prefix("author-mail ") {
+            current.mail = mail.to_string();
+        } else if let Some(time) = line.strip_prefix("author-time ") {
+            current.time = time.parse().unwrap_or(0);
+        } else if let Some(summary) = line.strip_prefix("summary ") {
+            current.summary = summary.to_string();
+        } else if current.commit.is_empty() {
+            current.commit = line.split(' ').next().unwrap_or_default().to_string();
+        }
+    }
+    Some(lines)
+}
+
+/// Blame annotations on `content`, which may be only part of the file or have
+/// gone through transforms: its lines are matched to the file's in order, and
+/// lines that aren't in the file (notes, stripped whitespace) stay unannotated
+pub fn annotate(content: &str, blame: &[BlameLine], mode: Blame, comment: Option<&str>) -> String {
+    match mode {
+        Blame::Header => {
+            let (uncommitted, committed): (Vec<&BlameLine>, Vec<&BlameLine>) = blame.iter().partition(|line| line.is_uncommitted());
+            let Some(latest) = committed.into_iter().max_by_key(|line| line.time) else { return content.to_string() };
+            let mut header = format!("Last changed {} by {} in {}: {}", date(latest.time), latest.author, latest.short_commit(), latest.summary);
+            if !uncommitted.is_empty() {
+                header.push_str(" (plus uncommitted changes)");
+            }
+            match comment {
+                Some(comment) => format!("{} {}\n{}", comment, header, content),
+                None => format!("{}\n\n{}", header, content),
+            }
+        }
+        Blame::Lines => {
+            let width = blame.iter().map(|line| line.author.chars().count().min(MAX_AUTHOR_WIDTH)).max().unwrap_or(0);
+            let mut next = 0;
+            let mut matched = false;
+            let mut out = String::with_capacity(content.len() * 2);
+            for line in content.split_inclusive('\n') {
+                let text = line.trim_end_matches(['\n', '\r']);
+                let found = blame[next..].iter().position(|b| b.content.trim_end_matches('\r').trim() == text.trim()).map(|i| next + i);
+                match found {
+                    Some(i) => {
+                        let b = &blame[i];
+                        let author: String = b.author.chars().take(MAX_AUTHOR_WIDTH).collect();
+                        out.push_str(&format!("{} {} {:<width$} | ", b.short_commit(), date(b.time), author, width = width));
+                        next = i + 1;
+                        matched = true;
+                    }
+                    None => out.push_str(&format!("{:<width$} | ", "", width = width + 20)),
+                }
+                out.push_str(line);
+            }
+            if matched { out } else { content.to_string() }
+        }
+    }
+}
+
+/// YYYY-MM-DD of a Unix timestamp
+fn date(time: i64) -> String {
+    let (year, month, day) = civil_from_days(time.div_euclid(86_400));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// history.md for `--with-log`: the last `count` commit subjects of each root,
+/// or with `per_file` the last `count` commits for each included file that was
+/// touched in the recent history
+pub fn render_log(roots: &[Root], files: &[SourceFile], count: usize, per_file: bool) -> String {
+    let mut out = String::from("# Recent history\n");
+    let count_arg = count.to_string();
+    let depth = if per_file { count.max(HISTORY_DEPTH.parse().unwrap_or(0)).to_string() } else { count_arg.clone() };
+    for root in roots {
+        if roots.len() > 1 {
+            out.push_str(&format!("\n## {}\n", root.prefix.display()));
+        }
+        // Commits start with a NUL so their subject lines can't be mistaken for file names
+        let Some(log) = git(&root.dir, &["log", "-n", &depth, "--format=%x00%h\t%as\t%an\t%s", "--name-only", "--relative"]) else {
+            out.push_str("\nNo git history found.\n");
+            continue;
+        };
+        let mut commits = Vec::new();
+        for entry in log.split('\0').filter(|e| !e.trim().is_empty()) {
+            let mut lines = entry.lines();
+            let mut fields = lines.next().unwrap_or_default().splitn(4, '\t');
+            let (hash, date, author, subject) = (fields.next().unwrap_or_default(), fields.next().unwrap_or_default(), fields.next().unwrap_or_default(), fields.next().unwrap_or_default());
+            let line = format!("- {} `{}` {}: {}\n", date, hash, author, subject);
+            let touched: Vec<PathBuf> = lines.filter(|l| !l.is_empty()).map(|l| root.prefix.join(paths::normalize(Path::new(l)))).collect();
+            commits.push((line, touched));
+        }
+        if !per_file {
+            out.push('\n');
+            commits.iter().for_each(|(line, _)| out.push_str(line));
+            continue;
+        }
+        for file in files.iter().filter(|f| f.relative_path.starts_with(&root.prefix)) {
+            let mut touching = commits.iter().filter(|(_, touched)| touched.contains(&file.relative_path)).take(count).peekable();
+            if touching.peek().is_none() {
+                continue;
+            }
+            let level = if roots.len() > 1 { "###" } else { "##" };
+            out.push_str(&format!("\n{} {}\n\n", level, file.relative_path.to_string_lossy().replace('\\', "/")));
+            touching.for_each(|(line, _)| out.push_str(line));
+        }
+    }
+    out
+}
+
+/// Commits touching each file among the most recent ones, across roots;
+/// files in roots outside git count none
+pub fn churn(roots: &[Root]) -> HashMap<PathBuf, usize> {
+    let mut churn = HashMap::new();
+    for root in roots {
+        if let Some(counts) = change_counts(&root.dir) {
+            churn.extend(counts.into_iter().map(|(path, count)| (root.prefix.join(path), count)));
+        }
+    }
+    churn
+}
+
+fn change_counts(dir: &Path) -> Option<HashMap<PathBuf, usize>> {
+    let log = git(dir, &["log", "-n", HISTORY_DEPTH, "--format=", "--name-only", "--relative"])?;
+    let mut counts = HashMap::new();
+    for line in log.lines().filter(|l| !l.is_empty()) {
+        *counts.entry(paths::normalize(Path::new(line))).or_insert(0) += 1;
+    }
+    Some(counts)
+}
+
+/// Modified and untracked files, which are about to change anyway
+fn uncommitted_files(dir: &Path) -> Vec<PathBuf> {
+    let modified = git(dir, &["diff", "--name-only", "--relative", "HEAD"]).unwrap_or_default();
+    let untracked = git(dir, &["ls-files", "--others", "--exclude-standard"]).unwrap_or_default();
+    modified.lines().chain(untracked.lines()).filter(|l| !l.is_empty()).map(|l| paths::normalize(Path::new(l))).collect()
+}
+
+/// Output of a git command run in `dir`, None if git failed or isn't installed
+pub fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    // Without quotepath=off git prints non-ASCII names as quoted octal escapes
+    let output = Command::new("git").arg("-C").arg(dir).args(["-c", "core.quotepath=off"]).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn stability_from_mtime(modified: Option<SystemTime>) -> Stability {
+    let age = modified.and_then(|m| SystemTime::now().duration_since(m).ok()).unwrap_or(Duration::MAX);
+    let days = age.as_secs() / 86_400;
+    match days {
+        0..=6 => Stability::Volatile,
+        7..=29 => Stability::Occasional,
+        _ => Stability::Stable,
+    }
+}
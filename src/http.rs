@@ -0,0 +1,141 @@
+// http.rs
+//! HTTP API over the same fresh-per-request context as the MCP server:
+//!
+//! - `GET /tree`
+//! - `GET /context?budget=100000&include=src/**&format=markdown`
+//! - `GET /file?path=src/main.rs`
+//! - `GET /search?pattern=fn%20main&context=2`
+use std::collections::HashMap;
+use std::io;
+
+use regex::Regex;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::combined::{self, OutputFormat};
+use crate::globs;
+use crate::mcp::{self, Snapshot};
+use crate::tokens::BudgetBy;
+
+/// Lines of context around each search match unless the query asks otherwise
+const SEARCH_CONTEXT: usize = 2;
+
+pub fn serve(address: &str, snapshot: impl Fn() -> io::Result<Snapshot>, format: OutputFormat, budget_by: BudgetBy) -> io::Result<()> {
+    let server = Server::http(address)
+        .map_err(|e| io::Error::other(format!("Couldn't listen on {}: {}", address, e)))?;
+    println!("Serving context on http://{}", address);
+    for request in server.incoming_requests() {
+        let (status, content_type, body) = match respond(&request, &snapshot, format, budget_by) {
+            Ok((content_type, body)) => (200, content_type, body),
+            Err((status, message)) => (status, "text/plain", message),
+        };
+        let header = Header::from_bytes("Content-Type", format!("{}; charset=utf-8", content_type))
+            .expect("valid content type header");
+        let response = Response::from_string(body).with_status_code(status).with_header(header);
+        if let Err(e) = request.respond(response) {
+            eprintln!("Error sending response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Content type and body, or status code and error message
+fn respond(
+    request: &Request,
+    snapshot: &impl Fn() -> io::Result<Snapshot>,
+    format: OutputFormat,
+    budget_by: BudgetBy,
+) -> Result<(&'static str, String), (u16, String)> {
+    if *request.method() != Method::Get {
+        return Err((405, "Only GET is supported".to_string()));
+    }
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let params = parse_query(query);
+    let internal = |e: io::Error| (500, e.to_string());
+
+    match path {
+        "/tree" => snapshot().map(|(_, tree)| ("text/plain", tree.render())).map_err(internal),
+        "/file" => {
+            let path = params.get("path").ok_or((400, "Missing 'path' parameter".to_string()))?;
+            let (files, _) = snapshot().map_err(internal)?;
+            let file = mcp::find_file(&files, path).map_err(|e| (404, e.to_string()))?;
+            Ok(("text/plain", file.content.clone()))
+        }
+        "/search" => {
+            let pattern = params.get("pattern").ok_or((400, "Missing 'pattern' parameter".to_string()))?;
+            let pattern = Regex::new(pattern).map_err(|e| (400, e.to_string()))?;
+            let context = number(&params, "context")?.unwrap_or(SEARCH_CONTEXT);
+            let (files, _) = snapshot().map_err(internal)?;
+            Ok(("text/plain", mcp::search(&files, &pattern, context)))
+        }
+        "/context" => {
+            let format = match params.get("format") {
+                Some(name) => name.parse::<OutputFormat>().map_err(|e| (400, e))?,
+                None => format,
+            };
+            let budget = number(&params, "budget")?;
+            let (mut files, mut tree) = snapshot().map_err(internal)?;
+            if let Some(include) = params.get("include") {
+                let include = globs::build_glob_set(include).map_err(|e| (400, e.to_string()))?;
+                files.retain(|f| include.is_match(&f.relative_path));
+            }
+            if let Some(budget) = budget {
+                crate::apply_token_budget(&mut files, &mut tree, budget, budget_by);
+            }
+            let body = combined::render(&files, format).map_err(internal)?;
+            Ok((content_type(format), body))
+        }
+        _ => Err((404, "Not found (endpoints: /tree, /context, /file, /search)".to_string())),
+    }
+}
+
+fn content_type(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Markdown | OutputFormat::Obsidian => "text/markdown",
+        OutputFormat::Json => "application/json",
+        OutputFormat::Xml => "application/xml",
+        _ => "text/plain",
+    }
+}
+
+fn number(params: &HashMap<String, String>, name: &str) -> Result<Option<usize>, (u16, String)> {
+    params
+        .get(name)
+        .map(|value| value.parse().map_err(|_| (400, format!("'{}' must be a number", name))))
+        .transpose()
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` as a space
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,78 @@
+// images.rs
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use base64::Engine;
+use globset::GlobSet;
+
+use crate::binary::Asset;
+
+/// How embedded images are encoded in JSON/XML output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Plain base64 data
+    Base64,
+    /// `data:<mime>;base64,...` URI
+    DataUri,
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "base64" => Ok(ImageFormat::Base64),
+            "data-uri" | "datauri" => Ok(ImageFormat::DataUri),
+            _ => Err(format!("Unknown image format '{}' (expected base64 or data-uri)", s)),
+        }
+    }
+}
+
+/// An image embedded in the combined output for multimodal models
+pub struct EmbeddedImage {
+    pub relative_path: PathBuf,
+    pub mime: &'static str,
+    pub size: u64,
+    pub data: String,
+}
+
+/// Encode the image assets matching `include`, skipping any larger than `max_size` bytes
+pub fn embed_images(
+    assets: &[Asset],
+    include: &GlobSet,
+    max_size: u64,
+    format: ImageFormat,
+) -> Vec<EmbeddedImage> {
+    let mut images = Vec::new();
+    for asset in assets {
+        if !asset.mime.starts_with("image/") || !include.is_match(&asset.relative_path) {
+            continue;
+        }
+        if asset.size > max_size {
+            println!("Image {} is larger than {} bytes, not embedded", asset.relative_path.display(), max_size);
+            continue;
+        }
+
+        let bytes = match fs::read(&asset.path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("Error reading image {}: {}", asset.path.display(), e);
+                continue;
+            }
+        };
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        let data = match format {
+            ImageFormat::Base64 => encoded,
+            ImageFormat::DataUri => format!("data:{};base64,{}", asset.mime, encoded),
+        };
+
+        images.push(EmbeddedImage {
+            relative_path: asset.relative_path.clone(),
+            mime: asset.mime,
+            size: asset.size,
+            data,
+        });
+    }
+    images
+}
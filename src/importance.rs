@@ -0,0 +1,137 @@
+// importance.rs
+//! How useful each file is for understanding the project, as a weighted sum of
+//! heuristics: path conventions (READMEs, manifests, entry points, src/ over
+//! tests/), how many files import it, how central it is in the import graph,
+//! how often it changed recently and its size. `--sort importance` orders by
+//! it, so the token budget keeps the highest scores, and `--importance-report`
+//! writes importance.txt with each score's make-up.
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use crate::history;
+use crate::imports::{self, ImportResolver};
+use crate::roots::Root;
+use crate::sort;
+use crate::SourceFile;
+
+/// Multipliers for each heuristic, from `--importance-weight NAME=VALUE`
+/// or an `[importance-weight]` table in the config file
+#[derive(Debug, Clone, Copy)]
+pub struct Weights {
+    pub path: f64,
+    /// Per file importing this one
+    pub references: f64,
+    /// Per unit of PageRank in the import graph, where the average file has 1
+    pub centrality: f64,
+    /// Per recent commit touching the file
+    pub churn: f64,
+    /// Per 1000 tokens, normally negative so big files need to earn their place
+    pub size: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights { path: 1.0, references: 10.0, centrality: 10.0, churn: 2.0, size: -1.0 }
+    }
+}
+
+impl Weights {
+    pub fn parse(entries: &[String]) -> Result<Self, String> {
+        let mut weights = Weights::default();
+        for entry in entries {
+            let invalid = || format!("Invalid importance weight '{}' (expected path, references, centrality, churn or size=NUMBER)", entry);
+            let (name, value) = entry.split_once('=').ok_or_else(invalid)?;
+            let value: f64 = value.trim().parse().map_err(|_| invalid())?;
+            match name.trim() {
+                "path" => weights.path = value,
+                "references" => weights.references = value,
+                "centrality" => weights.centrality = value,
+                "churn" => weights.churn = value,
+                "size" => weights.size = value,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(weights)
+    }
+}
+
+/// A file's heuristics and their weighted total
+#[derive(Debug, Clone, Copy)]
+pub struct Score {
+    pub path: i32,
+    pub references: usize,
+    pub centrality: f64,
+    pub churn: usize,
+    pub tokens: usize,
+    pub total: f64,
+}
+
+pub type Scores = HashMap<PathBuf, Score>;
+
+pub fn score(roots: &[Root], files: &[SourceFile], weights: &Weights) -> Scores {
+    let mut references: HashMap<PathBuf, usize> = HashMap::new();
+    let mut centrality = HashMap::new();
+    if weights.references != 0.0 || weights.centrality != 0.0 {
+        let graph = ImportResolver::new().graph(files);
+        centrality = imports::centrality(&graph);
+        for imported in graph.into_values() {
+            for path in imported {
+                *references.entry(path).or_insert(0) += 1;
+            }
+        }
+    }
+    let churn = if weights.churn != 0.0 { history::churn(roots) } else { HashMap::new() };
+
+    files
+        .iter()
+        .map(|file| {
+            let path = sort::importance_score(&file.relative_path);
+            let references = references.get(&file.relative_path).copied().unwrap_or(0);
+            let centrality = centrality.get(&file.relative_path).copied().unwrap_or(0.0);
+            let churn = churn.get(&file.relative_path).copied().unwrap_or(0);
+            let total = weights.path * f64::from(path)
+                + weights.references * references as f64
+                + weights.centrality * centrality
+                + weights.churn * churn as f64
+                + weights.size * file.tokens as f64 / 1000.0;
+            (file.relative_path.clone(), Score { path, references, centrality, churn, tokens: file.tokens, total })
+        })
+        .collect()
+}
+
+/// importance.txt: every scored file, highest first, and whether it made it
+/// into the output
+pub fn render(scores: &Scores, kept: &[SourceFile], weights: &Weights) -> String {
+    let kept: HashSet<&PathBuf> = kept.iter().map(|f| &f.relative_path).collect();
+    let mut ranked: Vec<(&PathBuf, &Score)> = scores.iter().collect();
+    ranked.sort_by(|a, b| b.1.total.total_cmp(&a.1.total).then_with(|| a.0.cmp(b.0)));
+
+    let mut out = format!(
+        "Importance of {} files, highest first\nScore: the sum of path x {}, references x {}, centrality x {}, churn x {} and tokens/1000 x {}\n\n",
+        ranked.len(),
+        weights.path,
+        weights.references,
+        weights.centrality,
+        weights.churn,
+        weights.size
+    );
+    let _ = writeln!(out, "{:>5}  {:>8}  {:>5}  {:>10}  {:>10}  {:>5}  {:>8}  {:<8}  File", "Rank", "Score", "Path", "References", "Centrality", "Churn", "Tokens", "Status");
+    for (rank, (path, score)) in ranked.iter().enumerate() {
+        let status = if kept.contains(path) { "kept" } else { "left out" };
+        let _ = writeln!(
+            out,
+            "{:>5}  {:>8.1}  {:>5}  {:>10}  {:>10.2}  {:>5}  {:>8}  {:<8}  {}",
+            rank + 1,
+            score.total,
+            score.path,
+            score.references,
+            score.centrality,
+            score.churn,
+            score.tokens,
+            status,
+            path.to_string_lossy().replace('\\', "/")
+        );
+    }
+    out
+}
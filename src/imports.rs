@@ -0,0 +1,328 @@
+// imports.rs
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::SourceFile;
+
+/// Formats the import graph can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "import-graph.dot",
+            GraphFormat::Mermaid => "import-graph.mmd",
+        }
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(format!("Unknown graph format '{}' (expected dot or mermaid)", s)),
+        }
+    }
+}
+
+/// Local files imported by each file, keyed by relative path
+pub type ImportGraph = BTreeMap<PathBuf, BTreeSet<PathBuf>>;
+
+const JS_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts", "vue", "svelte"];
+const JS_RESOLVE_SUFFIXES: &[&str] = &[
+    "", ".ts", ".tsx", ".js", ".jsx", ".mjs", ".cjs", ".vue", ".svelte",
+    "/index.ts", "/index.tsx", "/index.js", "/index.jsx",
+];
+
+/// Finds import statements and resolves them to files in the project
+pub struct ImportResolver {
+    js: Regex,
+    python_from: Regex,
+    python_import: Regex,
+    rust_mod: Regex,
+    rust_use: Regex,
+    c_include: Regex,
+}
+
+impl ImportResolver {
+    pub fn new() -> Self {
+        ImportResolver {
+            js: Regex::new(
+                r#"(?m)(?:\bfrom\s+|^\s*import\s+|\brequire\s*\(\s*|\bimport\s*\(\s*)["']([^"'\n]+)["']"#,
+            )
+            .expect("invalid js import pattern"),
+            python_from: Regex::new(r"(?m)^\s*from\s+(\.*[\w.]*)\s+import\s+([\w, ]+)")
+                .expect("invalid python import pattern"),
+            python_import: Regex::new(r"(?m)^\s*import\s+([\w.]+(?:\s*,\s*[\w.]+)*)")
+                .expect("invalid python import pattern"),
+            rust_mod: Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+([A-Za-z_][A-Za-z0-9_]*)\s*;")
+                .expect("invalid rust mod pattern"),
+            rust_use: Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?use\s+((?:crate|super|self)(?:::[A-Za-z_][A-Za-z0-9_]*)+)")
+                .expect("invalid rust use pattern"),
+            c_include: Regex::new(r#"(?m)^\s*#\s*include\s+"([^"]+)""#).expect("invalid include pattern"),
+        }
+    }
+
+    /// Resolve the local imports of every file; imports of packages and
+    /// files outside `files` are ignored
+    pub fn graph(&self, files: &[SourceFile]) -> ImportGraph {
+        let known: HashSet<&Path> = files.iter().map(|f| f.relative_path.as_path()).collect();
+        files
+            .iter()
+            .map(|file| {
+                let imports = self.imports(&file.relative_path, &file.content, &known);
+                (file.relative_path.clone(), imports)
+            })
+            .collect()
+    }
+
+    fn imports(&self, path: &Path, content: &str, known: &HashSet<&Path>) -> BTreeSet<PathBuf> {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let mut candidates: Vec<PathBuf> = Vec::new();
+
+        match extension.as_str() {
+            ext if JS_EXTENSIONS.contains(&ext) => {
+                for caps in self.js.captures_iter(content) {
+                    let specifier = &caps[1];
+                    if specifier.starts_with('.') {
+                        let base = dir.join(specifier);
+                        candidates.extend(JS_RESOLVE_SUFFIXES.iter().map(|s| with_suffix(&base, s)));
+                    }
+                }
+            }
+            "py" | "pyi" => {
+                for caps in self.python_from.captures_iter(content) {
+                    let module = &caps[1];
+                    let names: Vec<&str> = caps[2].split(',').map(str::trim).collect();
+                    candidates.extend(python_candidates(dir, module, &names));
+                }
+                for caps in self.python_import.captures_iter(content) {
+                    for module in caps[1].split(',').map(str::trim) {
+                        candidates.extend(python_candidates(dir, module, &[]));
+                    }
+                }
+            }
+            "rs" => {
+                let module_dir = rust_module_dir(path);
+                for caps in self.rust_mod.captures_iter(content) {
+                    candidates.push(module_dir.join(format!("{}.rs", &caps[1])));
+                    candidates.push(module_dir.join(&caps[1]).join("mod.rs"));
+                }
+                let crate_root = rust_crate_root(path, known);
+                for caps in self.rust_use.captures_iter(content) {
+                    let mut segments: Vec<&str> = caps[1].split("::").collect();
+                    let mut base = match segments.remove(0) {
+                        "crate" => crate_root.clone(),
+                        "self" => module_dir.clone(),
+                        _ => module_dir.parent().map(Path::to_path_buf).unwrap_or_default(),
+                    };
+                    // The longest module path that exists is the imported module
+                    let mut found = None;
+                    for segment in segments {
+                        base = base.join(segment);
+                        for candidate in [base.with_extension("rs"), base.join("mod.rs")] {
+                            if known.contains(normalize(&candidate).as_path()) {
+                                found = Some(candidate);
+                            }
+                        }
+                    }
+                    candidates.extend(found);
+                }
+            }
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" => {
+                for caps in self.c_include.captures_iter(content) {
+                    candidates.push(dir.join(&caps[1]));
+                    candidates.push(PathBuf::from(&caps[1]));
+                }
+            }
+            _ => {}
+        }
+
+        let mut imports = BTreeSet::new();
+        for candidate in candidates {
+            let candidate = normalize(&candidate);
+            if candidate != path && known.contains(candidate.as_path()) {
+                imports.insert(candidate);
+            }
+        }
+        imports
+    }
+}
+
+fn with_suffix(base: &Path, suffix: &str) -> PathBuf {
+    let mut path = base.as_os_str().to_os_string();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// `from .a import b` may import the module `a/b.py` or a name from `a.py`
+fn python_candidates(dir: &Path, module: &str, names: &[&str]) -> Vec<PathBuf> {
+    let dots = module.chars().take_while(|c| *c == '.').count();
+    let rest = module[dots..].replace('.', "/");
+
+    let mut bases = Vec::new();
+    if dots > 0 {
+        let mut base = dir.to_path_buf();
+        for _ in 1..dots {
+            base = base.parent().map(Path::to_path_buf).unwrap_or_default();
+        }
+        bases.push(base);
+    } else {
+        // Absolute imports are relative to the project root or a src/ layout
+        bases.push(PathBuf::new());
+        bases.push(PathBuf::from("src"));
+        bases.push(dir.to_path_buf());
+    }
+
+    let mut candidates = Vec::new();
+    for base in bases {
+        let module_path = if rest.is_empty() { base } else { base.join(&rest) };
+        candidates.push(module_path.with_extension("py"));
+        candidates.push(module_path.join("__init__.py"));
+        for name in names {
+            candidates.push(module_path.join(format!("{}.py", name)));
+        }
+    }
+    candidates
+}
+
+/// Directory holding the submodules of a Rust file
+fn rust_module_dir(path: &Path) -> PathBuf {
+    let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    match path.file_name().and_then(|n| n.to_str()) {
+        Some("main.rs") | Some("lib.rs") | Some("mod.rs") => dir,
+        _ => dir.join(path.file_stem().unwrap_or_default()),
+    }
+}
+
+/// Directory of the nearest lib.rs or main.rs above the file
+fn rust_crate_root(path: &Path, known: &HashSet<&Path>) -> PathBuf {
+    for dir in path.ancestors().skip(1) {
+        if known.contains(dir.join("lib.rs").as_path()) || known.contains(dir.join("main.rs").as_path()) {
+            return dir.to_path_buf();
+        }
+    }
+    path.parent().unwrap_or(Path::new("")).to_path_buf()
+}
+
+/// The entries plus every file they import, transitively up to `depth` hops
+pub fn closure(graph: &ImportGraph, entries: &[PathBuf], depth: Option<usize>) -> HashSet<PathBuf> {
+    let mut included: HashSet<PathBuf> = entries.iter().cloned().collect();
+    let mut frontier: Vec<PathBuf> = entries.to_vec();
+    let mut hops = 0;
+    while !frontier.is_empty() && depth.is_none_or(|max| hops < max) {
+        let mut next = Vec::new();
+        for file in &frontier {
+            for import in graph.get(file).into_iter().flatten() {
+                if included.insert(import.clone()) {
+                    next.push(import.clone());
+                }
+            }
+        }
+        frontier = next;
+        hops += 1;
+    }
+    included
+}
+
+/// Damping factor of PageRank: how often the random reader follows an import
+/// rather than jumping to any file
+const DAMPING: f64 = 0.85;
+
+/// PageRank over the import graph, scaled so the average file scores 1: a file
+/// imported by files that are themselves imported a lot scores high, a leaf
+/// utility imported once scores low
+pub fn centrality(graph: &ImportGraph) -> HashMap<PathBuf, f64> {
+    let nodes: Vec<&PathBuf> = graph.keys().collect();
+    let count = nodes.len();
+    if count == 0 {
+        return HashMap::new();
+    }
+    let index: HashMap<&PathBuf, usize> = nodes.iter().enumerate().map(|(i, path)| (*path, i)).collect();
+    let edges: Vec<Vec<usize>> = nodes.iter().map(|path| graph[*path].iter().filter_map(|import| index.get(import).copied()).collect()).collect();
+
+    let mut rank = vec![1.0 / count as f64; count];
+    for _ in 0..100 {
+        // Files importing nothing hand their rank to every file alike
+        let dangling: f64 = edges.iter().zip(&rank).filter(|(out, _)| out.is_empty()).map(|(_, r)| r).sum();
+        let base = (1.0 - DAMPING) / count as f64 + DAMPING * dangling / count as f64;
+        let mut next = vec![base; count];
+        for (from, out) in edges.iter().enumerate() {
+            for &to in out {
+                next[to] += DAMPING * rank[from] / out.len() as f64;
+            }
+        }
+        let change: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+        rank = next;
+        if change < 1e-9 {
+            break;
+        }
+    }
+    nodes.into_iter().zip(rank).map(|(path, r)| (path.clone(), r * count as f64)).collect()
+}
+
+/// Resolve `.` and `..` without touching the filesystem
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+pub fn render(graph: &ImportGraph, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => render_dot(graph),
+        GraphFormat::Mermaid => render_mermaid(graph),
+    }
+}
+
+fn render_dot(graph: &ImportGraph) -> String {
+    let mut out = String::from("digraph imports {\n    rankdir=LR;\n    node [shape=box];\n");
+    for (file, imports) in graph {
+        let _ = writeln!(out, "    \"{}\";", escape(file));
+        for import in imports {
+            let _ = writeln!(out, "    \"{}\" -> \"{}\";", escape(file), escape(import));
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_mermaid(graph: &ImportGraph) -> String {
+    let ids: BTreeMap<&PathBuf, usize> = graph.keys().enumerate().map(|(id, path)| (path, id)).collect();
+    let mut out = String::from("flowchart LR\n");
+    for (path, id) in &ids {
+        let _ = writeln!(out, "    n{}[\"{}\"]", id, escape(path));
+    }
+    for (file, imports) in graph {
+        for import in imports {
+            if let Some(target) = ids.get(import) {
+                let _ = writeln!(out, "    n{} --> n{}", ids[file], target);
+            }
+        }
+    }
+    out
+}
+
+fn escape(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/").replace('"', "'")
+}
@@ -0,0 +1,188 @@
+// language.rs
+use std::path::Path;
+use std::sync::OnceLock;
+
+use globset::{Glob, GlobMatcher};
+
+use crate::SourceFile;
+
+/// A programming or markup language recognised by file name or extension
+pub struct Language {
+    pub name: &'static str,
+    /// Language name used for markdown code fences
+    pub fence: &'static str,
+    pub extensions: &'static [&'static str],
+    pub file_names: &'static [&'static str],
+    pub line_comments: &'static [&'static str],
+    pub block_comments: &'static [(&'static str, &'static str)],
+}
+
+const C_LINE: &[&str] = &["//"];
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+const HASH_LINE: &[&str] = &["#"];
+const NONE_LINE: &[&str] = &[];
+const NONE_BLOCK: &[(&str, &str)] = &[];
+const XML_BLOCK: &[(&str, &str)] = &[("<!--", "-->")];
+
+const fn lang(
+    name: &'static str,
+    fence: &'static str,
+    extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comments: &'static [(&'static str, &'static str)],
+) -> Language {
+    Language { name, fence, extensions, file_names: &[], line_comments, block_comments }
+}
+
+pub static LANGUAGES: &[Language] = &[
+    lang("Rust", "rust", &["rs"], C_LINE, C_BLOCK),
+    lang("Python", "python", &["py", "pyi"], HASH_LINE, &[("\"\"\"", "\"\"\"")]),
+    lang("JavaScript", "javascript", &["js", "mjs", "cjs"], C_LINE, C_BLOCK),
+    lang("JSX", "jsx", &["jsx"], C_LINE, C_BLOCK),
+    lang("TypeScript", "typescript", &["ts", "mts", "cts"], C_LINE, C_BLOCK),
+    lang("TSX", "tsx", &["tsx"], C_LINE, C_BLOCK),
+    lang("Go", "go", &["go"], C_LINE, C_BLOCK),
+    lang("Java", "java", &["java"], C_LINE, C_BLOCK),
+    lang("Kotlin", "kotlin", &["kt", "kts"], C_LINE, C_BLOCK),
+    lang("C", "c", &["c", "h"], C_LINE, C_BLOCK),
+    lang("C++", "cpp", &["cpp", "cc", "cxx", "hpp", "hh"], C_LINE, C_BLOCK),
+    lang("C#", "csharp", &["cs"], C_LINE, C_BLOCK),
+    lang("Ruby", "ruby", &["rb"], HASH_LINE, &[("=begin", "=end")]),
+    lang("PHP", "php", &["php"], &["//", "#"], C_BLOCK),
+    lang("Swift", "swift", &["swift"], C_LINE, C_BLOCK),
+    lang("Scala", "scala", &["scala"], C_LINE, C_BLOCK),
+    lang("Shell", "bash", &["sh", "bash", "zsh"], HASH_LINE, NONE_BLOCK),
+    lang("PowerShell", "powershell", &["ps1"], HASH_LINE, &[("<#", "#>")]),
+    lang("SQL", "sql", &["sql"], &["--"], C_BLOCK),
+    lang("HTML", "html", &["html", "htm"], NONE_LINE, XML_BLOCK),
+    lang("CSS", "css", &["css"], NONE_LINE, C_BLOCK),
+    lang("SCSS", "scss", &["scss"], C_LINE, C_BLOCK),
+    lang("Vue", "vue", &["vue"], C_LINE, &[("/*", "*/"), ("<!--", "-->")]),
+    lang("Svelte", "svelte", &["svelte"], C_LINE, &[("/*", "*/"), ("<!--", "-->")]),
+    lang("JSON", "json", &["json"], NONE_LINE, NONE_BLOCK),
+    lang("YAML", "yaml", &["yaml", "yml"], HASH_LINE, NONE_BLOCK),
+    lang("TOML", "toml", &["toml"], HASH_LINE, NONE_BLOCK),
+    lang("XML", "xml", &["xml"], NONE_LINE, XML_BLOCK),
+    lang("Markdown", "markdown", &["md", "markdown"], NONE_LINE, XML_BLOCK),
+    lang("Lua", "lua", &["lua"], &["--"], &[("--[[", "]]")]),
+    lang("Dart", "dart", &["dart"], C_LINE, C_BLOCK),
+    lang("Elixir", "elixir", &["ex", "exs"], HASH_LINE, NONE_BLOCK),
+    lang("Haskell", "haskell", &["hs"], &["--"], &[("{-", "-}")]),
+    lang("Protocol Buffers", "protobuf", &["proto"], C_LINE, C_BLOCK),
+    lang("GraphQL", "graphql", &["graphql", "gql"], HASH_LINE, NONE_BLOCK),
+    Language {
+        name: "Dockerfile",
+        fence: "dockerfile",
+        extensions: &["dockerfile"],
+        file_names: &["Dockerfile"],
+        line_comments: HASH_LINE,
+        block_comments: NONE_BLOCK,
+    },
+    Language {
+        name: "Makefile",
+        fence: "makefile",
+        extensions: &["mk"],
+        file_names: &["Makefile", "makefile", "GNUmakefile"],
+        line_comments: HASH_LINE,
+        block_comments: NONE_BLOCK,
+    },
+];
+
+/// What a `--language` mapping matches: a glob on the file name (or on the
+/// path when it has a slash), or an interpreter in the `#!` line
+enum Pattern {
+    Name(GlobMatcher),
+    Path(GlobMatcher),
+    Shebang(String),
+}
+
+/// Mappings from `--language` or the config's `[language]` table, checked
+/// before the built-in ones; set once at startup
+static MAPPINGS: OnceLock<Vec<(Pattern, &'static Language)>> = OnceLock::new();
+
+/// Parse mappings as `PATTERN=LANGUAGE`, e.g. `*.gotmpl=go-template`,
+/// `Justfile=makefile` or `#!deno=typescript`. A known language (by name or
+/// fence) keeps its comment syntax, any other name is used as is for fences
+/// and stats.
+pub fn set_mappings(rules: &[String]) -> Result<(), String> {
+    let mappings = rules
+        .iter()
+        .map(|rule| {
+            let (pattern, name) = rule.split_once('=').ok_or_else(|| format!("Invalid language mapping '{}' (expected PATTERN=LANGUAGE)", rule))?;
+            let (pattern, name) = (pattern.trim(), name.trim());
+            let pattern = match pattern.strip_prefix("#!") {
+                Some(interpreter) => Pattern::Shebang(interpreter.trim().to_string()),
+                None => {
+                    let glob = Glob::new(pattern).map_err(|e| format!("Invalid language mapping glob '{}': {}", pattern, e))?.compile_matcher();
+                    if pattern.contains('/') { Pattern::Path(glob) } else { Pattern::Name(glob) }
+                }
+            };
+            Ok((pattern, by_name(name).unwrap_or_else(|| custom(name))))
+        })
+        .collect::<Result<_, String>>()?;
+    let _ = MAPPINGS.set(mappings);
+    Ok(())
+}
+
+fn by_name(name: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|l| l.name.eq_ignore_ascii_case(name) || l.fence.eq_ignore_ascii_case(name))
+}
+
+/// A language only known by its name; leaked, there's one per mapping for the whole run
+fn custom(name: &str) -> &'static Language {
+    let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+    Box::leak(Box::new(Language { name, fence: name, extensions: &[], file_names: &[], line_comments: NONE_LINE, block_comments: NONE_BLOCK }))
+}
+
+fn mapped(path: &Path) -> Option<&'static Language> {
+    let file_name = path.file_name()?;
+    MAPPINGS.get()?.iter().find_map(|(pattern, language)| {
+        let matches = match pattern {
+            Pattern::Name(glob) => glob.is_match(file_name),
+            Pattern::Path(glob) => glob.is_match(path),
+            Pattern::Shebang(_) => false,
+        };
+        matches.then_some(*language)
+    })
+}
+
+/// A `#!` mapping matching the interpreter on the first line, e.g. `deno` in
+/// `#!/usr/bin/env -S deno run`
+fn mapped_shebang(content: &str) -> Option<&'static Language> {
+    let shebang = content.lines().next()?.strip_prefix("#!")?;
+    let words: Vec<&str> = shebang.split_whitespace().map(|word| word.rsplit('/').next().unwrap_or(word)).collect();
+    MAPPINGS.get()?.iter().find_map(|(pattern, language)| match pattern {
+        Pattern::Shebang(interpreter) if words.contains(&interpreter.as_str()) => Some(*language),
+        _ => None,
+    })
+}
+
+/// The language of a loaded file, which can also come from its `#!` line
+pub fn of(file: &SourceFile) -> Option<&'static Language> {
+    detect_source(&file.relative_path, &file.content)
+}
+
+/// Like `detect`, with `content` for `#!` mappings
+pub fn detect_source(path: &Path, content: &str) -> Option<&'static Language> {
+    mapped(path).or_else(|| mapped_shebang(content)).or_else(|| builtin(path))
+}
+
+/// Detect the language of a file from its name, then its extension
+pub fn detect(path: &Path) -> Option<&'static Language> {
+    mapped(path).or_else(|| builtin(path))
+}
+
+fn builtin(path: &Path) -> Option<&'static Language> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if let Some(language) = LANGUAGES.iter().find(|l| l.file_names.contains(&file_name)) {
+        return Some(language);
+    }
+
+    let extension = path.extension().and_then(|ext| ext.to_str())?.to_lowercase();
+    LANGUAGES.iter().find(|l| l.extensions.contains(&extension.as_str()))
+}
+
+/// Map a file to the language name used for markdown code fences
+pub fn fence_language(file: &SourceFile) -> &'static str {
+    of(file).map(|l| l.fence).unwrap_or("")
+}
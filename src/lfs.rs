@@ -0,0 +1,60 @@
+// lfs.rs
+//! Git LFS pointers: the small text files checked out in place of large
+//! objects that haven't been downloaded. They're skipped with the object's
+//! size, or with `--lfs-fetch` replaced by the object when it's small enough.
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::vfs::FileSystem;
+
+const SPEC_PREFIX: &[u8] = b"version https://git-lfs.github.com/spec/";
+
+/// Pointer files are a few lines; anything bigger is the real file
+const MAX_POINTER_BYTES: u64 = 1024;
+
+/// What a pointer says about the object it stands for
+pub struct Pointer {
+    /// The pointer file itself, which `git lfs smudge` reads
+    pub text: Vec<u8>,
+    /// Size of the object in bytes
+    pub size: u64,
+}
+
+/// The pointer at `path`, `len` bytes long, if that's what the file is
+pub fn read_pointer(fs: &dyn FileSystem, path: &Path, len: u64) -> Option<Pointer> {
+    if len > MAX_POINTER_BYTES {
+        return None;
+    }
+    let text = fs.read(path).ok()?.to_vec();
+    if !text.starts_with(SPEC_PREFIX) {
+        return None;
+    }
+    let size = String::from_utf8_lossy(&text).lines().find_map(|line| line.strip_prefix("size ")?.trim().parse().ok())?;
+    Some(Pointer { text, size })
+}
+
+/// Download the object (or take it from the local LFS store) with `git lfs smudge`
+pub fn fetch(path: &Path, pointer: &Pointer) -> Result<Vec<u8>, String> {
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["lfs", "smudge", "--"])
+        .arg(path.file_name().unwrap_or_default())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("couldn't run git lfs: {}", e))?;
+    // The pointer is tiny, it fits in the pipe before smudge writes anything
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&pointer.text).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("git lfs smudge failed").trim().to_string());
+    }
+    Ok(output.stdout)
+}
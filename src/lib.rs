@@ -0,0 +1,35 @@
+// lib.rs
+//! The core of llm-context-gen: reading files through a `FileSystem`,
+//! recognizing binaries, decoding, redacting secrets, counting tokens and
+//! building the tree, then rendering the plain text context. With
+//! `--no-default-features` it builds for wasm32-unknown-unknown, so a browser
+//! front end can generate context from a dropped folder or a fetched tarball
+//! held in a `vfs::Memory`. The command line (src/main.rs) adds everything
+//! else on top and needs the default `native` feature.
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+pub mod binary;
+pub mod encoding;
+pub mod error;
+pub mod generated;
+pub mod language;
+pub mod pipeline;
+pub mod secrets;
+#[cfg(feature = "tree-sitter")]
+pub mod skeleton;
+pub mod tokens;
+pub mod tree;
+pub mod vfs;
+
+/// A source file that passed all filters and was loaded for output
+#[derive(Clone)]
+pub struct SourceFile {
+    pub relative_path: PathBuf,
+    pub content: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub tokens: usize,
+    /// Kinds of secrets that were redacted from the content
+    pub redactions: Vec<&'static str>,
+}
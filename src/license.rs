@@ -0,0 +1,70 @@
+// license.rs
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+/// Files whose content identifies the project's license
+const LICENSE_FILES: &[&str] = &["LICENSE", "LICENSE.md", "LICENSE.txt", "LICENCE", "COPYING", "COPYING.md"];
+
+/// SPDX identifier of the project at `dir`: from the Cargo.toml or package.json
+/// `license` field, else recognised from the text of its LICENSE file
+pub fn detect(dir: &Path) -> Option<String> {
+    let manifest_license = fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|s| s.parse::<toml::Value>().ok())
+        .and_then(|m| m.get("package")?.get("license")?.as_str().map(str::to_string))
+        .or_else(|| {
+            let manifest = fs::read_to_string(dir.join("package.json")).ok()?;
+            let manifest: serde_json::Value = serde_json::from_str(&manifest).ok()?;
+            manifest.get("license")?.as_str().map(str::to_string)
+        });
+    if manifest_license.is_some() {
+        return manifest_license;
+    }
+
+    LICENSE_FILES
+        .iter()
+        .find_map(|name| fs::read_to_string(dir.join(name)).ok())
+        .and_then(|text| identify(&text).map(str::to_string))
+}
+
+/// SPDX identifier declared in a file's header, if any
+pub fn file_license(content: &str, pattern: &Regex) -> Option<String> {
+    let header: String = content.lines().take(20).collect::<Vec<_>>().join("\n");
+    pattern
+        .captures(&header)
+        .map(|caps| caps[1].trim().trim_end_matches("*/").trim().to_string())
+}
+
+pub fn spdx_pattern() -> Regex {
+    Regex::new(r"SPDX-License-Identifier:\s*([^\n]+)").expect("invalid SPDX pattern")
+}
+
+/// Recognise common license texts
+fn identify(text: &str) -> Option<&'static str> {
+    let text = text.to_lowercase();
+    let has = |needle: &str| text.contains(needle);
+
+    if has("mit license") || has("permission is hereby granted, free of charge") {
+        Some("MIT")
+    } else if has("apache license") && has("version 2.0") {
+        Some("Apache-2.0")
+    } else if has("gnu affero general public license") {
+        Some("AGPL-3.0")
+    } else if has("gnu lesser general public license") {
+        Some(if has("version 2.1") { "LGPL-2.1" } else { "LGPL-3.0" })
+    } else if has("gnu general public license") {
+        Some(if has("version 2") && !has("version 3") { "GPL-2.0" } else { "GPL-3.0" })
+    } else if has("mozilla public license") && has("2.0") {
+        Some("MPL-2.0")
+    } else if has("isc license") {
+        Some("ISC")
+    } else if has("this is free and unencumbered software") {
+        Some("Unlicense")
+    } else if has("redistribution and use in source and binary forms") {
+        Some(if has("neither the name") { "BSD-3-Clause" } else { "BSD-2-Clause" })
+    } else {
+        None
+    }
+}
@@ -0,0 +1,147 @@
+// llm.rs
+use std::env;
+use std::io;
+use std::str::FromStr;
+
+use serde_json::{json, Value};
+
+use crate::tokens;
+
+/// Chat model API used by `ask`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatProvider {
+    /// OpenAI-compatible `/chat/completions` endpoint
+    OpenAi,
+    /// Anthropic's `/v1/messages` endpoint
+    Anthropic,
+    /// A local Ollama server's `/api/chat` endpoint, no key needed
+    Ollama,
+}
+
+impl FromStr for ChatProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "openai" => Ok(ChatProvider::OpenAi),
+            "anthropic" => Ok(ChatProvider::Anthropic),
+            "ollama" => Ok(ChatProvider::Ollama),
+            _ => Err(format!("Unknown provider '{}' (expected openai, anthropic or ollama)", s)),
+        }
+    }
+}
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Instructions sent with every question
+pub const SYSTEM_PROMPT: &str = "You are a senior engineer answering questions about a codebase. \
+Answer from the files provided, cite file paths for the code you refer to, and say so when the \
+files don't contain the answer.";
+
+/// The user message: the generated context followed by the question
+pub fn prompt(context: &str, question: &str) -> String {
+    format!("<context>\n{}\n</context>\n\nQuestion: {}", context.trim_end(), question)
+}
+
+pub struct ChatClient {
+    provider: ChatProvider,
+    model: String,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl ChatClient {
+    /// `model` and `url` default per provider; keys are read from
+    /// OPENAI_API_KEY and ANTHROPIC_API_KEY, Ollama needs none
+    pub fn new(provider: ChatProvider, model: Option<&str>, url: Option<&str>) -> io::Result<Self> {
+        let (default_model, default_url, key_var) = match provider {
+            ChatProvider::OpenAi => ("gpt-4o", "https://api.openai.com/v1", Some("OPENAI_API_KEY")),
+            ChatProvider::Anthropic => ("claude-3-5-sonnet-latest", "https://api.anthropic.com", Some("ANTHROPIC_API_KEY")),
+            ChatProvider::Ollama => ("qwen2.5-coder", "http://localhost:11434", None),
+        };
+        let api_key = key_var.and_then(|var| env::var(var).ok());
+        if let (Some(key_var), None, None) = (key_var, &api_key, url) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} must be set to ask with this provider", key_var),
+            ));
+        }
+        Ok(ChatClient {
+            provider,
+            model: model.unwrap_or(default_model).to_string(),
+            url: url.unwrap_or(default_url).trim_end_matches('/').to_string(),
+            api_key,
+        })
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// The model's reply to a single user message
+    pub fn complete(&self, system: &str, message: &str, max_tokens: usize) -> io::Result<String> {
+        let (endpoint, body) = match self.provider {
+            ChatProvider::OpenAi => (
+                format!("{}/chat/completions", self.url),
+                json!({
+                    "model": self.model,
+                    "max_tokens": max_tokens,
+                    "messages": [
+                        { "role": "system", "content": system },
+                        { "role": "user", "content": message },
+                    ],
+                }),
+            ),
+            ChatProvider::Anthropic => (
+                format!("{}/v1/messages", self.url),
+                json!({
+                    "model": self.model,
+                    "max_tokens": max_tokens,
+                    "system": system,
+                    "messages": [{ "role": "user", "content": message }],
+                }),
+            ),
+            ChatProvider::Ollama => (
+                format!("{}/api/chat", self.url),
+                json!({
+                    "model": self.model,
+                    "stream": false,
+                    // Ollama silently truncates prompts beyond its default 2048-token window
+                    "options": {
+                        "num_predict": max_tokens,
+                        "num_ctx": tokens::estimate_tokens(system, tokens::DEFAULT_CHARS_PER_TOKEN)
+                            + tokens::estimate_tokens(message, tokens::DEFAULT_CHARS_PER_TOKEN)
+                            + max_tokens,
+                    },
+                    "messages": [
+                        { "role": "system", "content": system },
+                        { "role": "user", "content": message },
+                    ],
+                }),
+            ),
+        };
+        let mut request = ureq::post(&endpoint);
+        if let Some(key) = &self.api_key {
+            request = match self.provider {
+                ChatProvider::OpenAi => request.set("Authorization", &format!("Bearer {}", key)),
+                ChatProvider::Anthropic => request.set("x-api-key", key).set("anthropic-version", ANTHROPIC_VERSION),
+                ChatProvider::Ollama => request,
+            };
+        }
+        let response: Value = request
+            .send_json(body)
+            .map_err(|e| io::Error::other(format!("Request to {} failed: {}", endpoint, e)))?
+            .into_json()?;
+
+        // OpenAI: {"choices": [{"message": {"content": ...}}]}, Anthropic: {"content": [{"type": "text", "text": ...}]},
+        // Ollama: {"message": {"content": ...}}
+        let answer = match self.provider {
+            ChatProvider::OpenAi => response["choices"][0]["message"]["content"].as_str().map(str::to_string),
+            ChatProvider::Anthropic => response["content"].as_array().map(|blocks| {
+                blocks.iter().filter_map(|b| b["text"].as_str()).collect::<Vec<_>>().join("")
+            }),
+            ChatProvider::Ollama => response["message"]["content"].as_str().map(str::to_string),
+        };
+        answer.ok_or_else(|| io::Error::other(format!("Unexpected response from {}", endpoint)))
+    }
+}
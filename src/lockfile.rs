@@ -0,0 +1,188 @@
+// lockfile.rs
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+const LOCKFILES: &[&str] = &["Cargo.lock", "package-lock.json", "npm-shrinkwrap.json", "yarn.lock"];
+
+pub fn is_lockfile(file_name: &str) -> bool {
+    LOCKFILES.contains(&file_name)
+}
+
+/// Replace a lock file with its direct dependencies and a transitive count.
+/// `path` is used to find the sibling package.json for yarn.lock.
+pub fn summarize(file_name: &str, content: &str, path: &Path) -> Option<String> {
+    let (direct, total) = match file_name {
+        "Cargo.lock" => summarize_cargo(content),
+        "package-lock.json" | "npm-shrinkwrap.json" => summarize_npm(content)?,
+        "yarn.lock" => summarize_yarn(content, path),
+        _ => return None,
+    };
+
+    let mut out = format!("# {} (summarized)\n\n", file_name);
+    out.push_str(&format!("Locked packages: {}\n", total));
+    out.push_str(&format!("Direct dependencies ({}):\n", direct.len()));
+    for (name, version) in direct {
+        out.push_str(&format!("  {} {}\n", name, version));
+    }
+    Some(out)
+}
+
+struct CargoPackage {
+    name: String,
+    version: String,
+    is_local: bool,
+    dependencies: Vec<String>,
+}
+
+/// Cargo.lock: direct dependencies are those of the local (source-less) packages
+fn summarize_cargo(content: &str) -> (BTreeMap<String, String>, usize) {
+    let mut packages: Vec<CargoPackage> = Vec::new();
+    let mut in_dependencies = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line == "[[package]]" {
+            packages.push(CargoPackage {
+                name: String::new(),
+                version: String::new(),
+                is_local: true,
+                dependencies: Vec::new(),
+            });
+            in_dependencies = false;
+            continue;
+        }
+        let Some(package) = packages.last_mut() else { continue };
+
+        if in_dependencies {
+            if line.starts_with(']') {
+                in_dependencies = false;
+            } else {
+                let dependency = line.trim_matches(|c| c == '"' || c == ',');
+                package.dependencies.push(dependency.to_string());
+            }
+        } else if let Some(value) = line.strip_prefix("name = ") {
+            package.name = value.trim_matches('"').to_string();
+        } else if let Some(value) = line.strip_prefix("version = ") {
+            package.version = value.trim_matches('"').to_string();
+        } else if line.starts_with("source = ") {
+            package.is_local = false;
+        } else if line.starts_with("dependencies = [") {
+            in_dependencies = !line.ends_with(']');
+        }
+    }
+
+    let versions: BTreeMap<&str, &str> = packages
+        .iter()
+        .map(|p| (p.name.as_str(), p.version.as_str()))
+        .collect();
+    let mut direct = BTreeMap::new();
+    for package in packages.iter().filter(|p| p.is_local) {
+        for dependency in &package.dependencies {
+            // Entries are "name", "name version" or "name version (source)"
+            let mut parts = dependency.split_whitespace();
+            let name = parts.next().unwrap_or_default();
+            if packages.iter().any(|p| p.is_local && p.name == name) {
+                continue;
+            }
+            let version = parts
+                .next()
+                .or_else(|| versions.get(name).copied())
+                .unwrap_or("?");
+            direct.insert(name.to_string(), version.to_string());
+        }
+    }
+
+    let total = packages.iter().filter(|p| !p.is_local).count();
+    (direct, total)
+}
+
+/// package-lock.json v2/v3 have a "packages" map keyed by install path;
+/// v1 only has the nested "dependencies" map
+fn summarize_npm(content: &str) -> Option<(BTreeMap<String, String>, usize)> {
+    let lock: Value = serde_json::from_str(content).ok()?;
+    let mut direct = BTreeMap::new();
+
+    if let Some(packages) = lock.get("packages").and_then(Value::as_object) {
+        let root = packages.get("");
+        for section in ["dependencies", "devDependencies", "optionalDependencies"] {
+            let Some(deps) = root.and_then(|r| r.get(section)).and_then(Value::as_object) else {
+                continue;
+            };
+            for name in deps.keys() {
+                let version = packages
+                    .get(&format!("node_modules/{}", name))
+                    .and_then(|p| p.get("version"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("?");
+                direct.insert(name.clone(), version.to_string());
+            }
+        }
+        let total = packages.keys().filter(|k| !k.is_empty()).count();
+        return Some((direct, total));
+    }
+
+    let deps = lock.get("dependencies").and_then(Value::as_object)?;
+    for (name, dep) in deps {
+        let version = dep.get("version").and_then(Value::as_str).unwrap_or("?");
+        direct.insert(name.clone(), version.to_string());
+    }
+    Some((direct, count_npm_v1(deps)))
+}
+
+fn count_npm_v1(deps: &serde_json::Map<String, Value>) -> usize {
+    deps.values()
+        .map(|dep| {
+            1 + dep
+                .get("dependencies")
+                .and_then(Value::as_object)
+                .map(count_npm_v1)
+                .unwrap_or(0)
+        })
+        .sum()
+}
+
+/// yarn.lock has no notion of direct dependencies, so they come from package.json
+fn summarize_yarn(content: &str, path: &Path) -> (BTreeMap<String, String>, usize) {
+    // Map every "name@range" specifier to its resolved version
+    let mut resolved: BTreeMap<String, String> = BTreeMap::new();
+    let mut specifiers: Vec<String> = Vec::new();
+    let mut total = 0;
+    for line in content.lines() {
+        if !line.starts_with(' ') && line.ends_with(':') && !line.starts_with('#') {
+            specifiers = line
+                .trim_end_matches(':')
+                .split(", ")
+                .map(|s| s.trim_matches('"').to_string())
+                .collect();
+            total += 1;
+        } else if let Some(version) = line.trim().strip_prefix("version") {
+            // Classic yarn writes `version "1.2.3"`, yarn berry `version: 1.2.3`
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            for specifier in specifiers.drain(..) {
+                resolved.insert(specifier.replace("@npm:", "@"), version.to_string());
+            }
+        }
+    }
+
+    let mut direct = BTreeMap::new();
+    let manifest = path
+        .parent()
+        .map(|dir| dir.join("package.json"))
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str::<Value>(&s).ok());
+    if let Some(manifest) = manifest {
+        for section in ["dependencies", "devDependencies", "optionalDependencies"] {
+            let Some(deps) = manifest.get(section).and_then(Value::as_object) else { continue };
+            for (name, range) in deps {
+                let specifier = format!("{}@{}", name, range.as_str().unwrap_or(""));
+                let version = resolved.get(&specifier).map(String::as_str).unwrap_or("?");
+                direct.insert(name.clone(), version.to_string());
+            }
+        }
+    }
+
+    (direct, total)
+}
@@ -1,85 +1,1435 @@
 // main.rs
 use std::fs::{self, File, OpenOptions};
-use std::io::{self, Read, Write};
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::env;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use regex::Regex;
 use structopt::StructOpt;
 use ignore::WalkBuilder;
 
+// The core, shared with the wasm32 build
+use llm_context_gen::{binary, encoding, error, generated, language, pipeline, secrets, skeleton, tokens, tree, vfs, SourceFile};
+
+mod anonymize;
+mod apidocs;
+mod append;
+mod archive;
+mod bundles;
+mod chunk;
+mod combined;
+mod contracts;
+mod dataset;
+mod dedup;
+mod delta;
+mod deptree;
+mod diff;
+mod documents;
+mod embeddings;
+mod gitattributes;
+mod globs;
+mod groups;
+mod history;
+mod http;
+mod images;
+mod imports;
+mod importance;
+mod lfs;
+mod license;
+mod manifests;
+mod manpage;
+mod llm;
+mod lockfile;
+mod mcp;
+mod models;
+mod notebook;
+mod obsidian;
+mod overview;
+mod paths;
+mod picker;
+mod preprocess;
+mod profiles;
+mod query;
+mod release;
+mod roots;
+mod sample;
+mod selfupdate;
+mod settings;
+mod sort;
+mod sqlite;
+mod stats;
+mod submodules;
+mod symbols;
+mod template;
+mod testfiles;
+mod timings;
+mod todos;
+mod transform;
+mod vendored;
+mod workspace;
+
+use anonymize::Anonymizer;
+use archive::ArchiveFormat;
+use binary::{Asset, BinaryDetector};
+use combined::{Framing, OutputFormat, Split};
+use dataset::{DatasetOptions, RecordStyle};
+use embeddings::{Embedder, Provider};
+use error::ContextGenError;
+use gitattributes::GitAttributes;
+use groups::GroupBy;
+use history::{Blame, Since, Stability};
+use images::ImageFormat;
+use imports::{GraphFormat, ImportResolver};
+use llm::{ChatClient, ChatProvider};
+use models::ModelInfo;
+use preprocess::Preprocessors;
+use profiles::Profile;
+use roots::{Listed, Root};
+use secrets::SecretScanner;
+use skeleton::Mode;
+use sort::SortOrder;
+use template::TemplateData;
+use testfiles::TestFilter;
+use timings::Timings;
+use transform::WhitespaceOptions;
+use tokens::{BudgetBy, OverLimit};
+use tree::{DiagramFormat, FileTree};
+use vfs::FileSystem;
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "llm-context-gen", about = "Generate text files for LLM context from source code")]
 struct Opt {
-    /// The directory to process
-    #[structopt(short, long, default_value = ".")]
-    dir: String,
+    /// The directory to process; repeat it or separate with commas to merge several
+    /// roots into one context (e.g. "services/*,libs/shared"), each under its own prefix
+    #[structopt(short, long, default_value = ".", number_of_values = 1)]
+    dir: Vec<String>,
+
+    /// Process exactly these files instead of walking --dir, each optionally with line
+    /// ranges to keep (e.g. src/parser.rs:120-340 or src/lib.rs:1-20,80-95)
+    files: Vec<String>,
+
+    /// Process exactly the files listed in this file, one per line, or on stdin with "-"
+    /// (e.g. `git diff --name-only | llm-context-gen --files-from -`); ignore rules don't
+    /// apply, and lines may carry ranges like FILES
+    #[structopt(long)]
+    files_from: Option<String>,
+
+    /// Pick the files to include in a terminal tree view with live token totals and
+    /// fuzzy search, starting from everything the other options select
+    #[structopt(long)]
+    interactive: bool,
+
+    /// Don't ask before processing a very large number of files or tokens
+    #[structopt(short, long)]
+    yes: bool,
+
+    /// Follow symlinked directories and files; cycles and files reached twice are skipped
+    #[structopt(long)]
+    follow_symlinks: bool,
 
     /// The output directory
     #[structopt(short, long, default_value = "llm-context")]
     output: String,
 
+    /// Add this run's files to those from earlier --append runs into the same output
+    /// directory instead of starting over; files seen again replace their earlier version.
+    /// Paths start with the root's name (e.g. services/api/src/main.rs) so roots don't collide
+    #[structopt(long)]
+    append: bool,
+
+    /// Only include files that changed since the last run into the same output directory,
+    /// with new, changed and deleted files marked in the tree
+    #[structopt(long)]
+    delta: bool,
+
+    /// Write the outputs as a single <output>.tar.zst or <output>.zip instead of a directory
+    #[structopt(long)]
+    archive: Option<ArchiveFormat>,
+
     /// Additional directories to ignore (comma-separated)
     #[structopt(short, long, default_value = "")]
     ignore: String,
+
+    /// Also apply these ecosystems' ignore files, like .gitignore (comma-separated:
+    /// docker, npm, eslint, prettier, vercel, gcloud, helm)
+    #[structopt(long, default_value = "")]
+    ignore_file_types: String,
     
     /// Maximum number of files to process
     #[structopt(short, long, default_value = "2000")]
     max_files: usize,
     
-    /// Maximum file size to process in bytes
-    #[structopt(short, long, default_value = "500000")]
-    max_size: u64,
+    /// Maximum file size to process in bytes
+    #[structopt(long, default_value = "500000")]
+    max_size: u64,
+    
+    /// Write files smaller than this many bytes into one <dir>.small-files.txt per
+    /// directory instead of a .txt each
+    #[structopt(long)]
+    merge_small: Option<u64>,
+    
+    /// Maximum directory depth
+    #[structopt(long, default_value = "8")]
+    max_depth: usize,
+
+    /// Files read and processed at once (default: one per CPU, at most 8); 1 or 2
+    /// is kinder to NFS and SMB mounts
+    #[structopt(long)]
+    io_threads: Option<usize>,
+
+    /// Make at most this many file requests a second (a file's metadata, its first
+    /// bytes to tell binaries apart and its content are one each), for network shares
+    /// and cloud-synced folders (Dropbox, OneDrive) that fetch every file read
+    #[structopt(long)]
+    io_throttle: Option<f64>,
+
+    /// Skip files whose path relative to --dir is longer than this many characters
+    #[structopt(long, default_value = "200")]
+    max_path_length: usize,
+
+    /// Skip directories nested deeper than this many levels below --dir
+    #[structopt(long, default_value = "20")]
+    max_nesting: usize,
+
+    /// In file-tree.txt, fold directories below this depth into one line with
+    /// their file count
+    #[structopt(long)]
+    tree_depth: Option<usize>,
+
+    /// Also write all files into a single combined file
+    #[structopt(long)]
+    combined: bool,
+
+    /// Format of the combined file (text, markdown, json, xml, sqlite, parquet), or
+    /// obsidian for a vault/ of linked markdown notes, one per file
+    #[structopt(long, default_value = "text")]
+    format: OutputFormat,
+
+    /// Order of files in the combined output (path, size, mtime, tokens, importance)
+    #[structopt(long, default_value = "path")]
+    sort: SortOrder,
+
+    /// Weight of an importance heuristic as NAME=VALUE: path (conventions like README,
+    /// manifests, entry points), references (per importing file), centrality (PageRank
+    /// in the import graph, 1 for the average file), churn (per recent commit) or size
+    /// (per 1000 tokens); repeatable, or an [importance-weight] table
+    #[structopt(long, number_of_values = 1)]
+    importance_weight: Vec<String>,
+
+    /// Also write importance.txt ranking files by importance score, with what makes
+    /// up each score and whether the file was kept
+    #[structopt(long)]
+    importance_report: bool,
+
+    /// Files to place first in the combined output, in order (comma-separated globs;
+    /// default: the preset's, e.g. README.md,Cargo.toml,package.json,pyproject.toml,go.mod)
+    #[structopt(long)]
+    priority: Option<String>,
+
+    /// Ecosystem defaults for ignored directories, priority files and excluded extensions
+    /// (rust, node, python, go, java, default, or a TOML file; comma-separated to combine).
+    /// Detected from Cargo.toml, package.json, pyproject.toml, go.mod or pom.xml when not given
+    #[structopt(long)]
+    preset: Option<String>,
+
+    /// Only write file-tree.txt, annotated with sizes and token estimates
+    #[structopt(long)]
+    tree_only: bool,
+
+    /// Also export the directory structure as a diagram (mermaid)
+    #[structopt(long)]
+    diagram: Option<DiagramFormat>,
+
+    /// Collapse directories below this depth in the diagram
+    #[structopt(long)]
+    diagram_depth: Option<usize>,
+
+    /// Also write stats.txt with per-language line counts and per-extension token usage
+    #[structopt(long)]
+    stats: bool,
+
+    /// Also write timings.txt with the time spent walking, reading, tokenizing and
+    /// writing, and the slowest files and directories
+    #[structopt(long)]
+    timings: bool,
+
+    /// Also write symbols.txt and symbols.json listing every function, type and constant
+    #[structopt(long)]
+    symbols: bool,
+
+    /// Also write overview.md: project type, frameworks, entry points, what each
+    /// top-level directory holds and dependency counts, worked out from the files
+    #[structopt(long)]
+    overview: bool,
+
+    /// Also write dependencies.md listing the direct dependencies, versions, features
+    /// and scripts of every Cargo.toml, package.json, pyproject.toml and go.mod
+    #[structopt(long)]
+    dependencies: bool,
+
+    /// Also write dependency-tree.txt with the output of `cargo tree`, `npm ls`,
+    /// `pipdeptree` or `go list -m all` for each detected project type
+    #[structopt(long)]
+    dependency_tree: bool,
+
+    /// Seconds each --dependency-tree command may run before it's stopped
+    #[structopt(long, default_value = "30")]
+    dependency_tree_timeout: u64,
+
+    /// Also write api.md, a reference of the public Rust items with their signatures
+    /// and doc comments (doc comments are gone with --strip-comments)
+    #[structopt(long)]
+    with_docs: bool,
+
+    /// Also write todos.txt listing every TODO, FIXME, HACK and XXX comment with its location
+    #[structopt(long)]
+    todos: bool,
+
+    /// Also export the graph of local imports between files (dot, mermaid)
+    #[structopt(long)]
+    import_graph: Option<GraphFormat>,
+
+    /// Only include these files (comma-separated, relative to --dir)
+    #[structopt(long, default_value = "")]
+    entry: String,
+
+    /// With --entry, also include every local file the entries import, transitively
+    #[structopt(long)]
+    follow_imports: bool,
+
+    /// With --follow-imports, how many levels of imports to follow
+    #[structopt(long)]
+    depth: Option<usize>,
+
+    /// Only include the content of files changed within this time (e.g. 14d, 2w, 12h)
+    /// or since a date (e.g. 2024-05-01), by git history where there is one; older
+    /// files are only listed in the tree
+    #[structopt(long)]
+    modified_since: Option<Since>,
+
+    /// Only include files with commits by this author, a pattern matched against
+    /// "Name <email>" like `git log --author` (e.g. "alice@"), looking at the last 200
+    /// commits or those since --modified-since
+    #[structopt(long)]
+    author: Option<String>,
+
+    /// With --author, only files where git blame credits the author with at least
+    /// this percentage of the lines
+    #[structopt(long)]
+    author_share: Option<f64>,
+
+    /// Annotate content with git blame: "header" adds a comment line with the file's
+    /// latest commit, author and date, "lines" prefixes every line with its own
+    #[structopt(long)]
+    blame: Option<Blame>,
+
+    /// Also write history.md with the subjects of the last N commits, for temporal
+    /// context on what changed recently
+    #[structopt(long)]
+    with_log: Option<usize>,
+
+    /// With --with-log, list the last N commits of each included file instead
+    #[structopt(long)]
+    log_per_file: bool,
+
+    /// Only include these workspace members (comma-separated Cargo or npm package names)
+    #[structopt(long, default_value = "")]
+    package: String,
+
+    /// With --package, also include the workspace members it depends on
+    #[structopt(long)]
+    with_path_deps: bool,
+
+    /// With --combined, also write a combined file per Cargo, pnpm, yarn or npm
+    /// workspace member under packages/, plus a summary of the workspace
+    #[structopt(long)]
+    per_package: bool,
+
+    /// Also write a combined file per directory under groups/, each with its own
+    /// file tree and token count (dir)
+    #[structopt(long)]
+    group_by: Option<GroupBy>,
+
+    /// With --group-by dir, how many levels down directories are grouped
+    #[structopt(long, default_value = "1")]
+    group_depth: usize,
+
+    /// Only include files whose content matches this regex (e.g. "PaymentProcessor|stripe")
+    #[structopt(long)]
+    grep: Option<String>,
+
+    /// With --grep, include only the matching lines plus this many lines of context
+    #[structopt(long)]
+    grep_context: Option<usize>,
+
+    /// Source of embeddings for `query` and --emit-embeddings (local, openai, ollama);
+    /// local needs no model or network
+    #[structopt(long, default_value = "local")]
+    embedding_provider: Provider,
+
+    /// Embedding model name (defaults to text-embedding-3-small for openai, nomic-embed-text for ollama)
+    #[structopt(long)]
+    embedding_model: Option<String>,
+
+    /// Base URL of the embedding API, for OpenAI-compatible servers or a remote Ollama
+    #[structopt(long)]
+    embedding_url: Option<String>,
+
+    /// Environment variable holding the key for --embedding-url; OPENAI_API_KEY is only
+    /// sent to OpenAI's own API
+    #[structopt(long)]
+    embedding_api_key_env: Option<String>,
+
+    /// Write per-chunk embeddings (path, line range, vector) to this JSONL file
+    #[structopt(long)]
+    emit_embeddings: Option<PathBuf>,
+
+    /// Also write chunks.jsonl with chunks of about this many tokens for RAG ingestion;
+    /// also sets the chunk size for --emit-embeddings and adds chunks to --format sqlite
+    #[structopt(long)]
+    chunk_size: Option<usize>,
+
+    /// Tokens repeated between consecutive chunks
+    #[structopt(long, default_value = "0")]
+    chunk_overlap: usize,
+
+    /// Print the estimated input cost for these models (comma-separated, e.g. gpt-4o,claude-sonnet)
+    #[structopt(long, default_value = "")]
+    estimate_cost: String,
+
+    /// Override model input prices in USD per million tokens (e.g. "gpt-4o=2.5,my-model=1.0")
+    #[structopt(long, default_value = "")]
+    pricing: String,
+
+    /// Model preset selecting tokenizer approximation, token budget and split size
+    /// (e.g. claude-3-7, gpt-4o, gemini-1.5-pro)
+    #[structopt(long)]
+    model: Option<String>,
+
+    /// Maximum total tokens to include; files that don't fit are listed as skipped
+    #[structopt(long)]
+    token_budget: Option<usize>,
+
+    /// Token budget for the files under a directory, as DIR=TOKENS (e.g. "src/=60000"),
+    /// so each area gets its share; a file counts against the most specific one it's
+    /// in. Repeatable, or `budgets = { "src/" = 60000, "docs/" = 15000 }` in the config
+    #[structopt(long, number_of_values = 1)]
+    budgets: Vec<String>,
+
+    /// Which files the token budget keeps: the first in output order, or the most
+    /// central in the import graph by PageRank (order, centrality)
+    #[structopt(long, default_value = "order")]
+    budget_by: BudgetBy,
+
+    /// Largest number of tokens a single file may have, whatever its size in bytes
+    /// (minified and CJK-heavy files are dense); see --over-max-tokens
+    #[structopt(long)]
+    max_tokens_per_file: Option<usize>,
+
+    /// What to do with a file over --max-tokens-per-file (skip, truncate); truncating
+    /// elides function bodies first in languages --mode skeleton supports
+    #[structopt(long, default_value = "skip")]
+    over_max_tokens: OverLimit,
+
+    /// Split the combined output into parts of at most this many tokens
+    #[structopt(long)]
+    split_tokens: Option<usize>,
+
+    /// Split the combined output into parts of at most this many characters, for
+    /// pasting into chat UIs; takes precedence over --split-tokens
+    #[structopt(long)]
+    split_chars: Option<usize>,
+
+    /// Start each part with "Part i of N" and ask the model to wait for all parts
+    #[structopt(long)]
+    part_headers: bool,
+
+    /// Don't redact detected secrets (API keys, private keys, tokens, connection strings)
+    #[structopt(long)]
+    no_redact: bool,
+
+    /// Fail instead of redacting when secrets are detected
+    #[structopt(long)]
+    fail_on_secrets: bool,
+
+    /// Replacement for values in .env files
+    #[structopt(long, default_value = "***")]
+    env_mask: String,
+
+    /// Include .env values unmasked
+    #[structopt(long)]
+    keep_env_values: bool,
+
+    /// Consistently rename project identifiers, string literals and domain terms,
+    /// in contents and in paths
+    #[structopt(long)]
+    anonymize: bool,
+
+    /// Additional domain terms to anonymize (comma-separated)
+    #[structopt(long, default_value = "")]
+    anonymize_terms: String,
+
+    /// Mapping file from a previous run to reuse replacements from
+    #[structopt(long)]
+    anonymize_map: Option<PathBuf>,
+
+    /// Remove line and block comments from recognised languages
+    #[structopt(long)]
+    strip_comments: bool,
+
+    /// Remove copyright/license banners at the top of files
+    #[structopt(long)]
+    strip_license_headers: bool,
+
+    /// Remove trailing whitespace from every line
+    #[structopt(long)]
+    trim_trailing_whitespace: bool,
+
+    /// Convert tabs to this many spaces
+    #[structopt(long)]
+    tabs_to_spaces: Option<usize>,
+
+    /// Collapse runs of blank lines into a single blank line
+    #[structopt(long)]
+    squeeze_blank_lines: bool,
+
+    /// Convert CRLF and CR line endings to LF
+    #[structopt(long)]
+    normalize_line_endings: bool,
+
+    /// Always treat files matching these globs as text (comma-separated)
+    #[structopt(long, default_value = "")]
+    force_text: String,
+
+    /// Always treat files matching these globs as binary (comma-separated)
+    #[structopt(long, default_value = "")]
+    force_binary: String,
+
+    /// Embed images matching these globs in JSON/XML output (comma-separated)
+    #[structopt(long, default_value = "")]
+    include_images: String,
+
+    /// Encoding of embedded images (base64, data-uri)
+    #[structopt(long, default_value = "base64")]
+    image_format: ImageFormat,
+
+    /// Largest image to embed in bytes
+    #[structopt(long, default_value = "1000000")]
+    max_image_size: u64,
+
+    /// Extract the text of PDF and DOCX documents instead of skipping them as binary
+    #[structopt(long)]
+    extract_documents: bool,
+
+    /// Run files matching a glob through a command and include its output instead, as
+    /// GLOB=COMMAND with {file} for the file's path (without it the content goes to
+    /// stdin), e.g. "*.ipynb=jupyter nbconvert --to script {file} --stdout"; repeatable,
+    /// or a [preprocess] table in .llm-context-gen.toml
+    #[structopt(long, number_of_values = 1)]
+    preprocess: Vec<String>,
+
+    /// Run the [preprocess] commands of the .llm-context-gen.toml in the working
+    /// directory, which a cloned repository could otherwise use to run anything
+    #[structopt(long)]
+    allow_preprocess: bool,
+
+    /// Use the embedding options (--emit-embeddings, --embedding-provider, --embedding-url,
+    /// --embedding-api-key-env) of the .llm-context-gen.toml in the working directory, which a cloned repository
+    /// could otherwise use to send its files, and your API key, to any server
+    #[structopt(long)]
+    allow_network: bool,
+
+    /// Map files to a language for fences, stats and comment stripping, as
+    /// PATTERN=LANGUAGE where the pattern is a file name glob or #!interpreter, e.g.
+    /// "*.gotmpl=go-template" or "#!deno=typescript"; repeatable, or a [language] table
+    #[structopt(long, number_of_values = 1)]
+    language: Vec<String>,
+
+    /// Include Jupyter notebooks as raw JSON instead of just their code and markdown cells
+    #[structopt(long)]
+    raw_notebooks: bool,
+
+    /// Include lock files verbatim instead of summarizing their dependencies
+    #[structopt(long)]
+    raw_lockfiles: bool,
+
+    /// Include every copy of files with identical content instead of referring
+    /// later copies to the first one
+    #[structopt(long)]
+    keep_duplicates: bool,
+
+    /// Include minified, generated and sourcemap files, and files marked
+    /// linguist-generated or linguist-vendored in .gitattributes
+    #[structopt(long)]
+    include_generated: bool,
+
+    /// Test files by each ecosystem's convention (*_test.go, *.spec.ts, tests/, __tests__/):
+    /// include, exclude, or only
+    #[structopt(long, default_value = "include")]
+    tests: TestFilter,
+
+    /// Include snapshot files (__snapshots__/, *.snap) and fixtures over 10 KB in
+    /// fixtures/ and testdata/, which are skipped by default
+    #[structopt(long)]
+    include_fixtures: bool,
+
+    /// Include vendored dependencies (vendor/, third_party/, crates from cargo vendor,
+    /// copies of npm packages), which are otherwise summarized as one tree entry
+    #[structopt(long)]
+    include_vendored: bool,
+
+    /// Walk into git submodules (respecting their own .gitignore files), which are
+    /// otherwise summarized as one tree entry
+    #[structopt(long)]
+    submodules: bool,
+
+    /// Replace Git LFS pointers to objects of at most this many bytes with the object,
+    /// fetched with `git lfs smudge`; other pointers are skipped with the object's size
+    #[structopt(long)]
+    lfs_fetch: Option<u64>,
+
+    /// Only include the header plus the first and last N rows of CSV, TSV and JSONL files
+    #[structopt(long)]
+    sample_data: Option<usize>,
+
+    /// Content to emit per file (full, skeleton); skeleton keeps only imports,
+    /// type definitions and function signatures for supported languages
+    #[structopt(long, default_value = "full")]
+    mode: Mode,
+
+    /// Tera template shaping the output into a full prompt, with variables such as
+    /// {{ file_tree }}, {{ files }} and {{ stats }}; review.md.tera is written to review.md.
+    /// With ask, it replaces the built-in prompt and can use {{ question }}
+    #[structopt(long)]
+    template: Option<PathBuf>,
+
+    /// Text file placed before the files in the combined output, e.g. standing instructions
+    #[structopt(long)]
+    header_file: Option<PathBuf>,
+
+    /// Text file placed after the files in the combined output, e.g. the task
+    #[structopt(long)]
+    footer_file: Option<PathBuf>,
+
+    /// Line introducing each file in text output, with {path}, {language}, {tokens},
+    /// {size} and {lines} placeholders, e.g. "==== FILE: {path} ({tokens} tokens) ===="
+    #[structopt(long)]
+    delimiter: Option<String>,
+
+    /// Order files from rarely to frequently changed (by recent git history) with
+    /// cache-boundary markers between the sections, so prompt caching reuses the
+    /// longest prefix between runs; replaces --sort and --priority
+    #[structopt(long)]
+    cache_friendly: bool,
+
+    /// Don't give API contracts (OpenAPI, GraphQL, protobuf, JSON Schema, ...) a section
+    /// of their own at the start of the combined output
+    #[structopt(long)]
+    no_contracts: bool,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Include only the files most relevant to a question, ranked by embedding similarity
+    Query {
+        /// The question or topic, e.g. "why does checkout fail on retries?"
+        query: String,
+
+        /// Number of most relevant files to include
+        #[structopt(long, default_value = "20")]
+        top_k: usize,
+
+        /// Embedding provider for ranking, overriding --embedding-provider (local, openai, ollama)
+        #[structopt(long)]
+        provider: Option<Provider>,
+
+        /// Embedding model, overriding --embedding-model
+        #[structopt(long)]
+        model: Option<String>,
+    },
+    /// Write fine-tuning records (dataset-train.jsonl, dataset-val.jsonl) instead of context files
+    Dataset {
+        /// Record format (chat, completion)
+        #[structopt(long, default_value = "chat")]
+        style: RecordStyle,
+
+        /// Kinds of records: file path => content, function signature => body (comma-separated)
+        #[structopt(long, default_value = "file,function")]
+        tasks: String,
+
+        /// Fraction of files whose records go to the validation set
+        #[structopt(long, default_value = "0.1")]
+        val_split: f64,
+
+        /// Seed for the deterministic train/validation split
+        #[structopt(long, default_value = "0")]
+        seed: u64,
+    },
+    /// Send the context and a question to an LLM and print the answer
+    Ask {
+        /// The question, e.g. "where is auth handled?"
+        question: String,
+
+        /// Chat API to call (openai, anthropic, ollama)
+        #[structopt(long, default_value = "openai")]
+        provider: ChatProvider,
+
+        /// Model name (default: gpt-4o for openai, claude-3-5-sonnet-latest for anthropic, qwen2.5-coder for ollama)
+        #[structopt(long)]
+        model: Option<String>,
+
+        /// Base URL of the API, e.g. for an OpenAI-compatible gateway
+        #[structopt(long)]
+        url: Option<String>,
+
+        /// Maximum length of the answer in tokens
+        #[structopt(long, default_value = "1024")]
+        max_output_tokens: usize,
+    },
+    /// Serve the context to AI tools instead of writing files, regenerated on every request
+    Serve {
+        /// Speak the Model Context Protocol on stdin/stdout
+        #[structopt(long)]
+        mcp: bool,
+
+        /// Serve an HTTP API (/tree, /context, /file, /search) on this address, e.g. 127.0.0.1:8080
+        #[structopt(long)]
+        http: Option<String>,
+    },
+    /// Report which files were added, removed or changed between two generated outputs
+    Diff {
+        /// The earlier output directory (with a combined.json, combined.txt or combined.md)
+        old: PathBuf,
+
+        /// The later output directory
+        new: PathBuf,
+
+        /// Also write just the added and changed files, and the report, to this directory
+        #[structopt(long)]
+        bundle: Option<PathBuf>,
+    },
+    /// Bundle the changelog entries, commit messages and diffs between two refs
+    /// into combined.<format>, for writing release notes
+    Release {
+        /// The refs, e.g. v1.4.0..v1.5.0 (the end defaults to HEAD)
+        range: String,
+
+        /// Include the changed files' contents at the later ref instead of their diffs
+        #[structopt(long)]
+        contents: bool,
+    },
+    /// Print a shell completion script (bash, zsh, fish, powershell, elvish)
+    Completions {
+        #[structopt(possible_values = &structopt::clap::Shell::variants(), case_insensitive = true)]
+        shell: structopt::clap::Shell,
+    },
+    /// Print the man page, e.g. `llm-context-gen man > llm-context-gen.1`
+    Man,
+    /// Replace this binary with the latest GitHub release, verified against its SHA-256 checksum
+    SelfUpdate {
+        /// Only report whether a newer version is available
+        #[structopt(long)]
+        check: bool,
+    },
+}
+
+/// Settings applied to every file as it is loaded
+struct ProcessConfig {
+    /// Where file contents are read from
+    fs: Box<dyn FileSystem>,
+    io_threads: usize,
+    chars_per_token: f64,
+    secrets: Option<SecretScanner>,
+    env_mask: Option<String>,
+    strip_comments: bool,
+    strip_license_headers: bool,
+    whitespace: WhitespaceOptions,
+    binary: BinaryDetector,
+    extract_documents: bool,
+    preprocessors: Preprocessors,
+    lfs_fetch: Option<u64>,
+    flatten_notebooks: bool,
+    summarize_lockfiles: bool,
+    max_size: u64,
+    max_tokens_per_file: Option<(usize, OverLimit)>,
+    blame: Option<Blame>,
+    max_path_length: usize,
+    include_generated: bool,
+    /// Per root prefix; empty when --include-generated is given
+    gitattributes: Vec<(PathBuf, GitAttributes)>,
+    sample_data: Option<usize>,
+    mode: Mode,
+    tests: TestFilter,
+    /// Extensions the preset excludes
+    profile: Profile,
+    include_fixtures: bool,
+    /// Progress messages on stdout; off when stdout carries a protocol
+    verbose: bool,
+    timings: Timings,
+}
+
+fn main() -> Result<(), ContextGenError> {
+    let started = Instant::now();
+    // Parsed once to see which options the command line gives, then again with the rest
+    // filled in from LLM_CONTEXT_GEN_* variables and .llm-context-gen.toml
+    let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let given = Opt::clap().get_matches_from(&args);
+    let opt = Opt::from_iter(settings::fill_in(args, |name| given.occurrences_of(name.replace('_', "-")) > 0)?);
+    
+    match &opt.command {
+        Some(Command::Completions { shell }) => {
+            Opt::clap().gen_completions_to(env!("CARGO_PKG_NAME"), *shell, &mut io::stdout());
+            return Ok(());
+        }
+        Some(Command::Man) => {
+            print!("{}", manpage::render(Opt::clap())?);
+            return Ok(());
+        }
+        Some(Command::SelfUpdate { check }) => {
+            selfupdate::run(*check)?;
+            return Ok(());
+        }
+        _ => {}
+    }
+    
+    language::set_mappings(&opt.language).map_err(ContextGenError::InvalidInput)?;
+    
+    // Works on earlier outputs only, nothing is walked
+    if let Some(Command::Diff { old, new, bundle }) = &opt.command {
+        let (old_files, _) = diff::load(old)?;
+        let (new_files, format) = diff::load(new)?;
+        let changes = diff::ContextDiff::between(old_files, new_files);
+        print!("{}", changes.report());
+        if let Some(bundle) = bundle {
+            diff::write_bundle(&changes, format, bundle)?;
+            println!("Changed files written to: {}", bundle.display());
+        }
+        return Ok(());
+    }
+    
+    // Comes from the repository's history rather than its working tree
+    if let Some(Command::Release { range, contents }) = &opt.command {
+        let dir = opt.dir.first().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+        let secrets = (!opt.no_redact || opt.fail_on_secrets).then(SecretScanner::new);
+        let mut release = release::collect(&dir, range, *contents, opt.max_size, secrets.as_ref()).map_err(ContextGenError::InvalidInput)?;
+        let output_dir = Path::new(&opt.output);
+        if let Some(anonymizer) = guard_content(&opt, &mut release.files)? {
+            anonymizer.rename_files(&mut release.files);
+            write_anonymize_map(&opt, &anonymizer)?;
+        }
+        fs::create_dir_all(output_dir)?;
+        let combined_path = output_dir.join(format!("combined.{}", opt.format.extension()));
+        combined::write_combined(&release.files, &[], Framing::default(), opt.format, &combined_path)?;
+        let tokens: usize = release.files.iter().map(|f| f.tokens).sum();
+        println!("Release context written to: {} ({} commits, {} files changed, ~{} tokens)", combined_path.display(), release.commits, release.changed, tokens);
+        return Ok(());
+    }
+    
+    let force_text = globs::build_glob_set(&opt.force_text)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let force_binary = globs::build_glob_set(&opt.force_binary)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let include_images = globs::build_glob_set(&opt.include_images)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let weights = importance::Weights::parse(&opt.importance_weight).map_err(ContextGenError::InvalidInput)?;
+    let budgets = tokens::parse_directory_budgets(&opt.budgets).map_err(ContextGenError::InvalidInput)?;
+    let pricing = models::pricing_table(&opt.pricing)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    
+    // Model presets provide defaults that explicit flags override
+    let preset: Option<&ModelInfo> = match &opt.model {
+        Some(name) => Some(models::find(name).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown model preset '{}'", name))
+        })?),
+        None => None,
+    };
+    let chars_per_token = preset.map(|m| m.chars_per_token).unwrap_or(tokens::DEFAULT_CHARS_PER_TOKEN);
+    let token_budget = opt.token_budget.or(preset.map(|m| m.token_budget()));
+    let split_tokens = opt.split_tokens.or(preset.map(|m| m.token_budget()));
+    let split = opt.split_chars.map(Split::Chars).or(split_tokens.map(Split::Tokens));
+    let header = opt.header_file.as_ref().map(fs::read_to_string).transpose()?;
+    let footer = opt.footer_file.as_ref().map(fs::read_to_string).transpose()?;
+    let mut framing = Framing {
+        header: header.as_deref(),
+        footer: footer.as_deref(),
+        delimiter: opt.delimiter.as_deref(),
+        sections: None,
+        contracts: None,
+    };
+    if opt.delimiter.is_some() && opt.format != OutputFormat::Text {
+        eprintln!("--delimiter only applies to --format text");
+    }
+    if opt.allow_preprocess && opt.preprocess.is_empty() {
+        eprintln!("--allow-preprocess: no [preprocess] commands in the config to allow");
+    }
+    if opt.allow_network && opt.emit_embeddings.is_none() && opt.embedding_url.is_none() && opt.embedding_api_key_env.is_none()
+        && opt.embedding_provider == Provider::Local
+    {
+        eprintln!("--allow-network: no embedding options in the config to allow");
+    }
+    
+    let roots = roots::expand(&opt.dir, opt.append).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if roots.len() > 1 && (!opt.package.is_empty() || opt.per_package) {
+        return Err(ContextGenError::InvalidInput("--package and --per-package work on a single --dir".to_string()));
+    }
+    
+    // Marker files pick the presets unless they're given
+    let detected_presets = profiles::detect(&roots);
+    let profile = match &opt.preset {
+        Some(presets) => Profile::load(presets)?,
+        None if detected_presets.is_empty() => Profile::default_profile(),
+        None => Profile::load(&detected_presets.join(","))?,
+    };
+    let priority = globs::build_glob_set(opt.priority.as_deref().unwrap_or(&profile.priority.join(",")))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // Read once up front, servers reuse the list for every request
+    let mut listed = opt.files_from.as_deref().map(roots::read_file_list).transpose()?;
+    for entry in &opt.files {
+        let entry = roots::parse_listed(entry).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        listed.get_or_insert_with(Vec::new).push(entry);
+    }
+    
+    let io_threads = opt.io_threads.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get().min(8))).max(1);
+    let config = ProcessConfig {
+        fs: match opt.io_throttle {
+            Some(per_second) if per_second > 0.0 => Box::new(vfs::Throttled::new(vfs::Disk, per_second)),
+            _ => Box::new(vfs::Disk),
+        },
+        io_threads,
+        chars_per_token,
+        secrets: if opt.no_redact && !opt.fail_on_secrets { None } else { Some(SecretScanner::new()) },
+        env_mask: if opt.keep_env_values { None } else { Some(opt.env_mask.clone()) },
+        strip_comments: opt.strip_comments,
+        strip_license_headers: opt.strip_license_headers,
+        whitespace: WhitespaceOptions {
+            trim_trailing: opt.trim_trailing_whitespace,
+            tab_width: opt.tabs_to_spaces.filter(|&width| width > 0),
+            squeeze_blank_lines: opt.squeeze_blank_lines,
+            normalize_line_endings: opt.normalize_line_endings,
+        },
+        binary: BinaryDetector::new(force_text, force_binary),
+        extract_documents: opt.extract_documents,
+        preprocessors: Preprocessors::new(&opt.preprocess).map_err(ContextGenError::InvalidInput)?,
+        lfs_fetch: opt.lfs_fetch,
+        flatten_notebooks: !opt.raw_notebooks,
+        summarize_lockfiles: !opt.raw_lockfiles,
+        max_size: opt.max_size,
+        max_tokens_per_file: opt.max_tokens_per_file.map(|max| (max, opt.over_max_tokens)),
+        blame: opt.blame,
+        max_path_length: opt.max_path_length,
+        include_generated: opt.include_generated,
+        sample_data: opt.sample_data,
+        mode: opt.mode,
+        tests: opt.tests,
+        profile: profile.clone(),
+        include_fixtures: opt.include_fixtures,
+        verbose: true,
+        timings: Timings::new(opt.timings, io_threads),
+        gitattributes: if opt.include_generated {
+            Vec::new()
+        } else {
+            roots.iter().map(|root| (root.prefix.clone(), GitAttributes::load(&root.dir))).collect()
+        },
+    };
+    
+    let output_dir = Path::new(&opt.output);
+    let default_ignores = ignored_dirs(&opt, &profile);
+    
+    // Modes that answer from the context rather than writing output files
+    if matches!(opt.command, Some(Command::Serve { .. }) | Some(Command::Ask { .. })) {
+        // stdout carries the protocol or the answer, and per-request progress is just noise
+        let config = ProcessConfig { verbose: false, ..config };
+        let snapshot = || -> io::Result<mcp::Snapshot> {
+            let Collected { mut files, mut tree, errors, .. } = collect_files(&opt, &roots, listed.as_deref(), &config, &default_ignores, output_dir)?;
+            for error in &errors {
+                eprintln!("Error: {}", error);
+            }
+            // Replacements come from a hash of the name, so every snapshot maps alike
+            let anonymizer = guard_content(&opt, &mut files)?;
+            select_files(&opt, &roots, &mut files, &mut tree, chars_per_token)?;
+            if let Some(anonymizer) = &anonymizer {
+                anonymizer.rename_files(&mut files);
+                tree.rename(&|name| anonymizer.anonymize_name(name));
+            }
+            let scores = (opt.sort == SortOrder::Importance).then(|| importance::score(&roots, &files, &weights));
+            sort::sort_files(&mut files, opt.sort, scores.as_ref());
+            sort::prioritize(&mut files, &priority);
+            Ok((files, tree))
+        };
+        match &opt.command {
+            Some(Command::Serve { mcp, http }) => {
+                if *mcp == http.is_some() {
+                    return Err(ContextGenError::InvalidInput("serve requires either --mcp or --http <address>".to_string()));
+                }
+                if *mcp && opt.files_from.as_deref() == Some("-") {
+                    return Err(ContextGenError::InvalidInput("serve --mcp reads requests from stdin, use --files-from <file>".to_string()));
+                }
+                match http {
+                    Some(address) => http::serve(address, snapshot, opt.format, opt.budget_by)?,
+                    None => mcp::serve(snapshot, opt.format, opt.budget_by)?,
+                }
+                return Ok(());
+            }
+            Some(Command::Ask { question, provider, model, url, max_output_tokens }) => {
+                let client = ChatClient::new(*provider, model.as_deref(), url.as_deref())?;
+                let (mut files, mut tree) = snapshot()?;
+                let _ = apply_directory_budgets(&mut files, &mut tree, &budgets, opt.budget_by);
+                if let Some(budget) = token_budget {
+                    let _ = apply_token_budget(&mut files, &mut tree, budget, opt.budget_by);
+                }
+                let context = combined::render(&files, opt.format)?;
+                let message = match &opt.template {
+                    Some(template) => template::render(template, &TemplateData {
+                        files: &files,
+                        file_tree: &tree.render(),
+                        combined: &context,
+                        question: Some(question),
+                    })?,
+                    None => llm::prompt(&context, question),
+                };
+                eprintln!("Asking {} about {} files (~{} tokens)", client.model(), files.len(), tree.total_tokens());
+                let answer = client.complete(llm::SYSTEM_PROMPT, &message, *max_output_tokens)?;
+                println!("{}", answer.trim_end());
+                return Ok(());
+            }
+            _ => unreachable!("only serve and ask are handled here"),
+        }
+    }
+    
+    // Outputs are written through the extended-length form where Windows needs it;
+    // the walk below still compares against the directory as given
+    if (opt.append || opt.delta) && opt.archive.is_some() {
+        return Err(ContextGenError::InvalidInput("--append and --delta keep state in the output directory and can't be used with --archive".to_string()));
+    }
+    if listed.is_none() && !opt.yes && !confirm_large_run(&opt, &roots, &default_ignores, output_dir, chars_per_token)? {
+        println!("Cancelled, no context generated");
+        return Ok(());
+    }
+    let staging = opt.archive.map(|_| archive::staging_dir(output_dir));
+    let output_dir = &paths::long_path(staging.as_deref().unwrap_or(output_dir));
+    
+    // Create output directory
+    fs::create_dir_all(output_dir)?;
+    
+    // Create file-tree.txt
+    let file_tree_path = output_dir.join("file-tree.txt");
+    let mut file_tree = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(file_tree_path)?;
+    
+    for root in &roots {
+        println!("Processing directory: {}", root.dir.display());
+    }
+    println!("Ignoring directories: {:?}", default_ignores);
+    println!("Maximum files: {}", opt.max_files);
+    println!("Maximum file size: {} bytes", opt.max_size);
+    println!("Maximum depth: {}", opt.max_depth);
+    if let Some(model) = preset {
+        println!("Model preset: {} (~{} chars/token)", model.name, model.chars_per_token);
+    }
+    match &opt.preset {
+        Some(_) => println!("Preset: {}", profile.names.join(", ")),
+        None if detected_presets.is_empty() => println!("No project type detected, using the default preset"),
+        None => println!("Detected project type: {}", detected_presets.join(", ")),
+    }
+    if let Some(budget) = token_budget {
+        println!("Token budget: {}", budget);
+    }
+    
+    let Collected { mut files, mut assets, mut tree, file_count, limit_reached, mut errors } =
+        collect_files(&opt, &roots, listed.as_deref(), &config, &default_ignores, Path::new(&opt.output))?;
+    let selecting = Instant::now();
+    
+    // Report secrets before anything is written
+    let anonymizer = guard_content(&opt, &mut files)?;
+    
+    select_files(&opt, &roots, &mut files, &mut tree, chars_per_token)?;
+    
+    if opt.interactive {
+        let Some(picked) = picker::pick(&files)? else {
+            println!("Cancelled, no context generated");
+            return Ok(());
+        };
+        let mut picked = picked.into_iter();
+        files.retain(|file| {
+            let keep = picked.next().unwrap_or(true);
+            if !keep {
+                tree.exclude_file(&file.relative_path, file.size, file.tokens, "not picked");
+            }
+            keep
+        });
+    }
+    
+    // Paths are anonymized like the contents once nothing looks files up by their real path
+    if let Some(anonymizer) = &anonymizer {
+        anonymizer.rename_files(&mut files);
+        tree.rename(&|name| anonymizer.anonymize_name(name));
+        for asset in &mut assets {
+            asset.relative_path = anonymizer.anonymize_path(&asset.relative_path);
+        }
+        for path in errors.iter_mut().filter_map(ContextGenError::path_mut) {
+            *path = anonymizer.anonymize_path(path);
+        }
+        write_anonymize_map(&opt, anonymizer)?;
+    }
+    
+    if opt.append {
+        let carried = append::merge(&mut files, &mut tree, append::load(output_dir)?);
+        if carried > 0 {
+            println!("Appending to {} files from earlier runs", carried);
+        }
+        append::save(output_dir, &files)?;
+    }
+    
+    // Every run records what it saw, so a later --delta has something to compare against
+    let snapshot = delta::snapshot(&files);
+    let previous_snapshot = if opt.archive.is_none() { delta::load(output_dir)? } else { None };
+    if opt.archive.is_none() {
+        delta::save(output_dir, &snapshot)?;
+    }
+    if opt.delta {
+        match previous_snapshot {
+            Some(previous) => {
+                let changes = delta::changes(&previous, &snapshot);
+                for (path, change) in &changes {
+                    tree.mark(path, change.name());
+                }
+                files.retain(|f| changes.contains_key(&f.relative_path));
+                println!("Delta since the last run: {} files changed, {} deleted",
+                    files.len(), changes.values().filter(|c| **c == delta::Change::Deleted).count());
+            }
+            None => println!("No earlier run in {}, including every file", output_dir.display()),
+        }
+    }
+    
+    let packages = if opt.per_package { workspace::detect(&roots[0].dir) } else { None };
+    
+    let scores = (opt.sort == SortOrder::Importance || opt.importance_report).then(|| importance::score(&roots, &files, &weights));
+    
+    let stability;
+    let contracts;
+    if let Some(Command::Query { query, top_k, provider, model }) = &opt.command {
+        // Most relevant first, so the token budget drops the least relevant files
+        let embedder = Embedder::new(
+            provider.unwrap_or(opt.embedding_provider),
+            model.as_deref().or(opt.embedding_model.as_deref()),
+            opt.embedding_url.as_deref(),
+            opt.embedding_api_key_env.as_deref(),
+        )?;
+        println!("Ranking {} files against the query with {}", files.len(), embedder.model());
+        let ranked = query::rank_files(&files, query, &embedder, chars_per_token)?;
+        let mut report = String::new();
+        for ranked in ranked.iter().take(*top_k) {
+            report.push_str(&format!("{:.3}  {} (best match lines {}-{})\n",
+                ranked.score, ranked.path.display(), ranked.start_line, ranked.end_line));
+        }
+        fs::write(output_dir.join("relevance.txt"), report)?;
+        
+        let rank: HashMap<&PathBuf, usize> = ranked.iter().take(*top_k).enumerate().map(|(i, r)| (&r.path, i)).collect();
+        let mut selected = Vec::new();
+        for file in files.drain(..) {
+            if rank.contains_key(&file.relative_path) {
+                selected.push(file);
+            } else {
+                tree.exclude_file(&file.relative_path, file.size, file.tokens, "not relevant to query");
+            }
+        }
+        selected.sort_by_key(|file| rank[&file.relative_path]);
+        files = selected;
+    } else if opt.cache_friendly {
+        // Deterministic within each section, so unchanged sections render byte-for-byte the same
+        stability = history::classify(&roots, &files);
+        files.sort_by(|a, b| {
+            (stability[&a.relative_path], &a.relative_path).cmp(&(stability[&b.relative_path], &b.relative_path))
+        });
+        for section in [Stability::Stable, Stability::Occasional, Stability::Volatile] {
+            let count = files.iter().filter(|f| stability[&f.relative_path] == section).count();
+            println!("Cache section {}: {} files", section.name(), count);
+        }
+        framing.sections = Some(&stability);
+    } else {
+        sort::sort_files(&mut files, opt.sort, scores.as_ref());
+        sort::prioritize(&mut files, &priority);
+        if !opt.no_contracts {
+            contracts = contracts::detect(&files);
+            if !contracts.is_empty() {
+                contracts::place_first(&mut files, &contracts);
+                for (path, kind) in &contracts {
+                    tree.mark(path, &format!("{} contract", kind));
+                }
+                println!("Contracts placed first: {} files", contracts.len());
+                framing.contracts = Some(&contracts);
+            }
+        }
+    }
+    
+    // After sorting, so the copy that keeps its content is the one output first
+    if !opt.keep_duplicates {
+        let (replaced, saved) = dedup::dedup(&mut files, &mut tree, chars_per_token);
+        if replaced > 0 {
+            println!("Referred {} duplicate files to their first copy (~{} tokens saved)", replaced, saved);
+        }
+    }
+    
+    for note in apply_directory_budgets(&mut files, &mut tree, &budgets, opt.budget_by) {
+        println!("{}", note);
+    }
+    if let Some(budget) = token_budget {
+        if let Some(note) = apply_token_budget(&mut files, &mut tree, budget, opt.budget_by) {
+            println!("{}", note);
+        }
+    }
+    config.timings.add("selecting and sorting", selecting.elapsed());
+    let writing = Instant::now();
+    
+    write!(file_tree, "{}", tree.render_folded(opt.tree_depth))?;
+    if limit_reached {
+        writeln!(file_tree, "\n[Maximum file limit reached ({}). Some files were skipped.]", opt.max_files)?;
+    }
+    
+    if let Some(format) = opt.diagram {
+        fs::write(output_dir.join(format.file_name()), tree.render_diagram(format, opt.diagram_depth))?;
+    }
+    
+    if opt.stats {
+        let report = format!("{}\n{}",
+            stats::language_summary(&files), stats::extension_breakdown(&files));
+        fs::write(output_dir.join("stats.txt"), report)?;
+    }
+    
+    if opt.overview {
+        fs::write(output_dir.join("overview.md"), overview::render(&files, &detected_presets))?;
+    }
+    
+    if let Some(scores) = scores.as_ref().filter(|_| opt.importance_report) {
+        fs::write(output_dir.join("importance.txt"), importance::render(scores, &files, &weights))?;
+    }
+    
+    if let Some(count) = opt.with_log {
+        fs::write(output_dir.join("history.md"), history::render_log(&roots, &files, count, opt.log_per_file))?;
+    }
+    
+    if opt.dependencies {
+        fs::write(output_dir.join("dependencies.md"), manifests::render(&manifests::collect(&files)))?;
+    }
+    
+    if opt.dependency_tree {
+        let timeout = Duration::from_secs(opt.dependency_tree_timeout);
+        fs::write(output_dir.join("dependency-tree.txt"), deptree::capture(&roots, timeout))?;
+    }
+    
+    if opt.with_docs {
+        fs::write(output_dir.join("api.md"), apidocs::render(&files))?;
+    }
+    
+    if opt.todos {
+        fs::write(output_dir.join("todos.txt"), todos::render(&files))?;
+    }
+    
+    if opt.symbols {
+        let symbols = symbols::collect(&files);
+        fs::write(output_dir.join("symbols.txt"), symbols::render_text(&symbols))?;
+        fs::write(output_dir.join("symbols.json"), symbols::render_json(&symbols))?;
+    }
+    
+    if let Some(format) = opt.import_graph {
+        let graph = ImportResolver::new().graph(&files);
+        fs::write(output_dir.join(format.file_name()), imports::render(&graph, format))?;
+    }
+    
+    if opt.tree_only {
+        writeln!(file_tree, "\nTotal: {} files, {}, ~{} tokens",
+            files.len(), tree::format_size(tree.total_size()), tree.total_tokens())?;
+    } else if let Some(Command::Dataset { style, tasks, val_split, seed }) = &opt.command {
+        let options = DatasetOptions { style: *style, tasks, val_split: *val_split, seed: *seed };
+        let (train, val) = dataset::write_records(&files, &options, output_dir)?;
+        println!("Dataset written: {} training and {} validation records", train, val);
+    } else {
+        let bundled = match opt.merge_small {
+            Some(max_size) => {
+                let (bundles, bundled) = bundles::bundle_small(&files, max_size);
+                for bundle in &bundles {
+                    fs::write(paths::long_path(&output_dir.join(&bundle.file_name)), &bundle.content)?;
+                }
+                if !bundles.is_empty() {
+                    let count = bundled.iter().filter(|b| **b).count();
+                    println!("Merged {} small files into {} per-directory files", count, bundles.len());
+                }
+                bundled
+            }
+            None => vec![false; files.len()],
+        };
+        for (file, _) in files.iter().zip(&bundled).filter(|(_, bundled)| !**bundled) {
+            if let Err(error) = write_file_output(file, output_dir) {
+                errors.push(error);
+            }
+        }
+        
+        if !assets.is_empty() {
+            fs::write(output_dir.join("assets.txt"), binary::inventory(&assets))?;
+        }
+        
+        let chunk_size = opt.chunk_size.unwrap_or(query::CHUNK_TOKENS);
+        if opt.chunk_size.is_some() {
+            let chunks = chunk::chunk_files(&files, chunk_size, opt.chunk_overlap, chars_per_token);
+            chunk::write_jsonl(&output_dir.join("chunks.jsonl"), &chunks)?;
+            println!("Wrote {} chunks to: {}", chunks.len(), output_dir.join("chunks.jsonl").display());
+        }
+        
+        if let Some(path) = &opt.emit_embeddings {
+            let embedder = Embedder::new(
+                opt.embedding_provider,
+                opt.embedding_model.as_deref(),
+                opt.embedding_url.as_deref(),
+                opt.embedding_api_key_env.as_deref(),
+            )?;
+            let chunks = chunk::chunk_files(&files, chunk_size, opt.chunk_overlap, chars_per_token);
+            let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+            let vectors = embedder.embed(&texts)?;
+            embeddings::write_jsonl(path, &chunks, &vectors, embedder.model())?;
+            println!("Wrote {} chunk embeddings to: {}", chunks.len(), path.display());
+        }
+        
+        if opt.combined {
+            let embedded = if include_images.is_empty() {
+                Vec::new()
+            } else if matches!(opt.format, OutputFormat::Json | OutputFormat::Xml) {
+                images::embed_images(&assets, &include_images, opt.max_image_size, opt.image_format)
+            } else {
+                eprintln!("--include-images requires --format json or xml, images not embedded");
+                Vec::new()
+            };
+            let tabular = matches!(opt.format, OutputFormat::Sqlite | OutputFormat::Parquet | OutputFormat::Obsidian);
+            if tabular && (header.is_some() || footer.is_some()) {
+                eprintln!("--header-file and --footer-file don't apply to --format sqlite, parquet or obsidian");
+            }
+            // Databases and datasets are written whole, never split
+            if opt.format == OutputFormat::Sqlite {
+                let chunks = match opt.chunk_size {
+                    Some(size) => chunk::chunk_files(&files, size, opt.chunk_overlap, chars_per_token),
+                    None => Vec::new(),
+                };
+                sqlite::write_database(&files, &chunks, &output_dir.join("combined.db"))?;
+            } else if opt.format == OutputFormat::Parquet {
+                let project_license = license::detect(&roots[0].dir);
+                dataset::write_parquet(&files, project_license.as_deref(), &output_dir.join("combined.parquet"))?;
+            } else {
+                combined::write_combined_parts(&files, &embedded, framing, opt.format, output_dir, split, opt.part_headers)?;
+            }
+            
+            if opt.per_package {
+                match &packages {
+                    Some(packages) => {
+                        for package in packages {
+                            let package_files: Vec<SourceFile> = files
+                                .iter()
+                                .filter(|f| workspace::package_of(packages, &f.relative_path).is_some_and(|p| p.name == package.name))
+                                .cloned()
+                                .collect();
+                            if package_files.is_empty() {
+                                continue;
+                            }
+                            // Scoped npm names like @scope/pkg stay nested
+                            let package_dir = package
+                                .name
+                                .split('/')
+                                .fold(output_dir.join("packages"), |dir, part| dir.join(paths::safe_file_name(part)));
+                            fs::create_dir_all(&package_dir)?;
+                            combined::write_combined_parts(&package_files, &[], framing, opt.format, &package_dir, split, opt.part_headers)?;
+                        }
+                        fs::write(output_dir.join("packages").join("summary.txt"), workspace::summary(packages, &files))?;
+                        println!("Per-package outputs written to: {}", output_dir.join("packages").display());
+                    }
+                    None => eprintln!("--per-package requires a workspace, no per-package outputs written"),
+                }
+            }
+        }
+        
+        if opt.group_by == Some(GroupBy::Dir) {
+            if matches!(opt.format, OutputFormat::Sqlite | OutputFormat::Parquet) {
+                eprintln!("--group-by doesn't apply to --format sqlite or parquet, no grouped outputs written");
+            } else {
+                let groups = groups::by_dir(&files, opt.group_depth);
+                let groups_dir = output_dir.join("groups");
+                for (dir, group_files) in &groups {
+                    let group_dir = if dir == Path::new(".") {
+                        groups_dir.join("_root")
+                    } else {
+                        dir.components().fold(groups_dir.clone(), |out, part| out.join(paths::safe_file_name(&part.as_os_str().to_string_lossy())))
+                    };
+                    fs::create_dir_all(&group_dir)?;
+                    let group_header = format!("{}{}", header.as_deref().unwrap_or(""), groups::header(dir, group_files));
+                    let group_framing = Framing { header: Some(&group_header), ..framing };
+                    combined::write_combined_parts(group_files, &[], group_framing, opt.format, &group_dir, split, opt.part_headers)?;
+                }
+                fs::write(groups_dir.join("summary.txt"), groups::summary(&groups))?;
+                println!("Per-directory outputs written to: {}", groups_dir.display());
+            }
+        }
+        
+        if let Some(template) = &opt.template {
+            let combined = combined::render(&files, opt.format)?;
+            let prompt = template::render(template, &TemplateData {
+                files: &files,
+                file_tree: &tree.render(),
+                combined: &combined,
+                question: None,
+            })?;
+            let prompt_path = output_dir.join(template::output_name(template));
+            fs::write(&prompt_path, prompt)?;
+            println!("Prompt written to: {}", prompt_path.display());
+        }
+    }
+    
+    if config.timings.enabled() {
+        config.timings.add("writing", writing.elapsed());
+        let total = started.elapsed();
+        fs::write(output_dir.join("timings.txt"), config.timings.report(total))?;
+        println!("{}", config.timings.summary(total));
+    }
     
-    /// Maximum directory depth
-    #[structopt(long, default_value = "8")]
-    max_depth: usize,
-}
-
-// Safer indentation function that doesn't use repeat
-fn get_indent(depth: usize) -> String {
-    let max_indent = 10; // Maximum safe indent level
-    let safe_depth = depth.min(max_indent);
+    // Reported last so they aren't lost in the progress output, and kept with the context
+    if !errors.is_empty() {
+        for error in &errors {
+            eprintln!("Error: {}", error);
+        }
+        fs::write(output_dir.join("errors.txt"), error::report(&errors))?;
+        let plural = if errors.len() == 1 { "" } else { "s" };
+        eprintln!("{} problem{} during the run, listed in errors.txt", errors.len(), plural);
+    }
     
-    let mut result = String::with_capacity(safe_depth * 4); // Pre-allocate space for efficiency
-    for _ in 0..safe_depth {
-        result.push_str("│   ");
+    match opt.archive {
+        Some(format) => {
+            let archive_path = archive::write(output_dir, Path::new(&opt.output), format)?;
+            println!("Context archive written to: {}", archive_path.display());
+        }
+        None => println!("Context files generated in: {}", output_dir.display()),
     }
+    println!("Total files processed: {}", file_count);
     
-    result
+    if !opt.estimate_cost.is_empty() {
+        let total_tokens = tree.total_tokens();
+        println!("Estimated input cost for ~{} tokens:", total_tokens);
+        for (model, cost) in models::estimate_costs(&opt.estimate_cost, total_tokens, &pricing) {
+            match cost {
+                Some(cost) => println!("  {:<20} ${:.2}", model, cost),
+                None => println!("  {:<20} (no pricing known, use --pricing)", model),
+            }
+        }
+    }
+    Ok(())
 }
 
-fn main() -> io::Result<()> {
-    let opt = Opt::from_args();
-    
-    // Create output directory
-    let output_dir = Path::new(&opt.output);
-    fs::create_dir_all(output_dir)?;
-    
-    // Create file-tree.txt
-    let file_tree_path = output_dir.join("file-tree.txt");
-    let mut file_tree = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(file_tree_path)?;
-    
-    // Default directories to ignore
-    let mut default_ignores = HashSet::new();
-    default_ignores.insert("node_modules".to_string());
-    default_ignores.insert("target".to_string());
-    default_ignores.insert("dist".to_string());
-    default_ignores.insert("build".to_string());
-    default_ignores.insert(".git".to_string());
-    default_ignores.insert(".idea".to_string());
-    default_ignores.insert(".vscode".to_string());
-    default_ignores.insert("__pycache__".to_string());
-    
-    // Next.js specific directories
-    default_ignores.insert(".next".to_string());
-    default_ignores.insert("out".to_string());
-    default_ignores.insert("coverage".to_string());
-    default_ignores.insert(".vercel".to_string());
-    default_ignores.insert(".turbo".to_string());
+/// Directory names skipped anywhere in the tree
+fn ignored_dirs(opt: &Opt, profile: &Profile) -> HashSet<String> {
+    let mut default_ignores: HashSet<String> = profile.ignore.iter().cloned().collect();
     
     // Add user-specified ignores
     if !opt.ignore.is_empty() {
@@ -87,250 +1437,840 @@ fn main() -> io::Result<()> {
             default_ignores.insert(ignore.trim().to_string());
         }
     }
-    
-    println!("Processing directory: {}", opt.dir);
-    println!("Ignoring directories: {:?}", default_ignores);
-    println!("Maximum files: {}", opt.max_files);
-    println!("Maximum file size: {} bytes", opt.max_size);
-    println!("Maximum depth: {}", opt.max_depth);
-    
-    // Set up a custom walker with limits
-    let walker = WalkBuilder::new(&opt.dir)
-        .hidden(false) // Don't skip hidden files by default
-        .git_global(true) // Use global gitignore
-        .git_ignore(true) // Use .gitignore
-        .max_depth(Some(opt.max_depth)) // Limit directory depth
-        .max_filesize(Some(opt.max_size)) // Skip files larger than specified size
-        .build();
-    
-    // Initialize file tree string for the root directory
-    writeln!(file_tree, ".")?;
+    default_ignores
+}
+
+/// What the walk found, before any selection
+struct Collected {
+    files: Vec<SourceFile>,
+    assets: Vec<Asset>,
+    tree: FileTree,
+    file_count: usize,
+    limit_reached: bool,
+    /// Failures that cost a file but not the run
+    errors: Vec<ContextGenError>,
+}
+
+fn collect_files(
+    opt: &Opt,
+    roots: &[Root],
+    listed: Option<&[Listed]>,
+    config: &ProcessConfig,
+    ignores: &HashSet<String>,
+    output_dir: &Path,
+) -> io::Result<Collected> {
+    // The tree is collected during the walk and rendered at the end
+    let mut tree = FileTree::new(opt.tree_only);
+    let mut limit_reached = false;
     
     // Count processed files to prevent excessive processing
     let mut file_count = 0;
     let max_files = opt.max_files; // Use user-specified limit
+    let mut assets = Vec::new();
+    let mut seen = HashSet::new();
+    let mut names = HashSet::new();
+    // Vendored trees and submodules, with what they are
+    let mut vendored: Vec<(PathBuf, &str, usize, u64)> = Vec::new();
+    let mut errors = Vec::new();
+    // Found by the walk, then read and processed together
+    let mut pending = Vec::new();
+    let walking = Instant::now();
     
-    for result in walker {
-        if file_count >= max_files {
-            writeln!(file_tree, "\n[Maximum file limit reached ({}). Some files were skipped.]", max_files)?;
-            println!("Maximum file limit reached ({}). Some files were skipped.", max_files);
-            break;
+    if let Some(listed) = listed {
+        // Listed paths can point anywhere, including at what --append is adding to
+        let own_output = output_dir.canonicalize().ok();
+        for entry in listed {
+            if file_count >= max_files {
+                limit_reached = true;
+                break;
+            }
+            // Deleted files show up in `git diff --name-only` too
+            let Some((path, relative_path)) = roots::locate(&entry.path, roots) else {
+                eprintln!("Listed file {} not found, skipping", entry.path.display());
+                continue;
+            };
+            if own_output.as_ref().is_some_and(|dir| path.canonicalize().is_ok_and(|path| path.starts_with(dir))) {
+                eprintln!("Listed file {} is in the output directory, skipping", entry.path.display());
+                continue;
+            }
+            pending.push(Pending { path, relative_path: paths::normalize(&relative_path), ranges: entry.ranges.clone() });
+            file_count += 1;
         }
-        
-        match result {
-            Ok(entry) => {
-                let path = entry.path();
-                
-                // Skip the output directory itself
-                if path.starts_with(output_dir) {
-                    continue;
+        let files = process_files(&pending, &mut tree, &mut assets, &mut errors, config)?;
+        return Ok(Collected { files, assets, tree, file_count, limit_reached, errors });
+    }
+    
+    'roots: for root in roots {
+        let declared_submodules = submodules::declared(&root.dir);
+        let own_output = paths::output_under(&root.dir, output_dir);
+        for result in walk(opt, &root.dir)? {
+            if file_count >= max_files {
+                limit_reached = true;
+                if config.verbose {
+                    println!("Maximum file limit reached ({}). Some files were skipped.", max_files);
                 }
-                
-                // Skip directories in our default ignore list
-                let skip = path.components().any(|comp| {
-                    if let Some(name) = comp.as_os_str().to_str() {
-                        default_ignores.contains(name)
-                    } else {
-                        false
+                break 'roots;
+            }
+            
+            match result {
+                Ok(entry) => {
+                    let path = entry.path();
+                    
+                    // Skip the output directory itself
+                    if own_output.as_ref().is_some_and(|dir| path.starts_with(dir)) {
+                        continue;
                     }
-                });
-                
-                if skip {
-                    continue;
-                }
-                
-                // Use a safe way to get relative path
-                let relative_path = match path.strip_prefix(&opt.dir) {
-                    Ok(rel_path) => rel_path,
-                    Err(_) => {
-                        // If we can't get a relative path, just use the file name
-                        if let Some(file_name) = path.file_name() {
-                            Path::new(file_name)
+                    
+                    // Skip directories in our default ignore list
+                    let skip = path.components().any(|comp| {
+                        if let Some(name) = comp.as_os_str().to_str() {
+                            ignores.contains(name)
                         } else {
-                            continue; // Skip if we can't determine a path
+                            false
                         }
+                    });
+                    
+                    if skip {
+                        continue;
                     }
-                };
-                
-                // Add to file tree (with safety checks)
-                if path.is_dir() {
-                    // Limit nesting level for indentation to prevent overflow
-                    let component_count = relative_path.components().count();
-                    if component_count > 20 {
-                        writeln!(file_tree, "[Deeply nested directory skipped]")?;
+                    
+                    // Use a safe way to get relative path
+                    let root_relative = match path.strip_prefix(&root.dir) {
+                        Ok(rel_path) => rel_path,
+                        Err(_) => {
+                            // If we can't get a relative path, just use the file name
+                            if let Some(file_name) = path.file_name() {
+                                Path::new(file_name)
+                            } else {
+                                continue; // Skip if we can't determine a path
+                            }
+                        }
+                    };
+                    
+                    // The root directory itself is rendered as "." (or as its prefix with several roots)
+                    if root_relative.as_os_str().is_empty() {
+                        if !root.prefix.as_os_str().is_empty() {
+                            tree.add_dir(&root.prefix);
+                        }
                         continue;
                     }
+                    let relative_path = &paths::normalize(&root.prefix.join(root_relative));
+                    if root_relative.to_str().is_none() && config.verbose {
+                        eprintln!("{} isn't valid UTF-8, listed as {}", path.display(), relative_path.display());
+                    }
                     
-                    // Use our safe indent function
-                    let indent = get_indent(component_count.saturating_sub(1));
-                    let dir_name = relative_path.file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy();
+                    // Vendored trees and submodules are counted for their summary but never output
+                    if let Some((_, _, count, size)) = vendored.iter_mut().find(|(dir, ..)| relative_path.starts_with(dir)) {
+                        if path.is_file() {
+                            *count += 1;
+                            *size += fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+                        }
+                        continue;
+                    }
+                    if !opt.include_vendored && path.is_dir() && vendored::is_vendored(path) {
+                        vendored.push((relative_path.clone(), "vendored", 0, 0));
+                        continue;
+                    }
+                    if path.is_dir() && declared_submodules.iter().any(|d| d.as_path() == root_relative) && submodules::is_uninitialized(path) {
+                        tree.add_dir(relative_path);
+                        tree.add_skipped(relative_path, "git submodule, not initialized");
+                        continue;
+                    }
+                    if !opt.submodules && path.is_dir() && submodules::is_submodule(path) {
+                        vendored.push((relative_path.clone(), "git submodule", 0, 0));
+                        continue;
+                    }
                     
-                    writeln!(file_tree, "{}├── {}/", indent, dir_name)?;
-                } else if path.is_file() {
-                    process_file(path, relative_path, output_dir, &mut file_tree)?;
-                    file_count += 1;
+                    // Limit nesting level to keep the tree readable; only the first
+                    // directory too deep is listed, everything inside it is left out
+                    let nesting = root_relative.components().count();
+                    if nesting > opt.max_nesting {
+                        if path.is_dir() && nesting == opt.max_nesting + 1 {
+                            tree.add_dir(relative_path);
+                            tree.add_skipped(relative_path, "too deeply nested");
+                        }
+                        continue;
+                    }
                     
-                    if file_count % 100 == 0 {
-                        println!("Processed {} files...", file_count);
+                    // Add to file tree (with safety checks)
+                    if path.is_dir() {
+                        tree.add_dir(relative_path);
+                    } else if path.is_file() {
+                        // A symlinked directory can lead to files that were already included
+                        if opt.follow_symlinks {
+                            if let Ok(canonical) = path.canonicalize() {
+                                if !seen.insert(canonical) {
+                                    tree.add_skipped(relative_path, "duplicate via symlink");
+                                    continue;
+                                }
+                            }
+                        }
+                        
+                        // Names differing only in Unicode normalization would collide in every output
+                        if !names.insert(relative_path.clone()) {
+                            eprintln!("Skipping {}: same name as another file after Unicode normalization", path.display());
+                            continue;
+                        }
+                        
+                        pending.push(Pending { path: path.to_path_buf(), relative_path: relative_path.clone(), ranges: Vec::new() });
+                        file_count += 1;
                     }
                 }
-            }
-            Err(err) => {
-                eprintln!("Error: {}", err);
+                Err(err) => match symlink_loop(&err) {
+                    Some(child) => {
+                        let relative = child.strip_prefix(&root.dir).unwrap_or(child);
+                        tree.add_skipped(&root.prefix.join(relative), "symlink cycle");
+                    }
+                    None => errors.push(ContextGenError::Walk { root: root.dir.clone(), source: err }),
+                },
             }
         }
     }
     
-    println!("Context files generated in: {}", output_dir.display());
-    println!("Total files processed: {}", file_count);
-    Ok(())
+    for (dir, kind, count, size) in vendored {
+        tree.add_dir(&dir);
+        let plural = if count == 1 { "" } else { "s" };
+        tree.add_skipped(&dir, &format!("{}, {} file{}, {}", kind, count, plural, tree::format_size(size)));
+    }
+    config.timings.add("walking", walking.elapsed());
+    
+    let files = process_files(&pending, &mut tree, &mut assets, &mut errors, config)?;
+    Ok(Collected { files, assets, tree, file_count, limit_reached, errors })
 }
 
-fn process_file(
-    path: &Path,
-    relative_path: &Path,
-    output_dir: &Path,
-    file_tree: &mut File,
-) -> io::Result<()> {
-    // Safety check for path length
-    if relative_path.to_string_lossy().len() > 200 {
-        let indent = get_indent(relative_path.components().count().saturating_sub(1));
-        writeln!(file_tree, "{}├── ... (skipped - path too long)", indent)?;
-        return Ok(());
-    }
+/// A file found by the walk or listed, and the lines to keep of it
+struct Pending {
+    path: PathBuf,
+    relative_path: PathBuf,
+    ranges: Vec<(usize, usize)>,
+}
 
-    // Skip binary files and very large files
-    if is_binary_file(path)? || is_too_large(path)? {
-        let indent = get_indent(relative_path.components().count().saturating_sub(1));
-        writeln!(file_tree, "{}├── {} (skipped - binary or too large)", 
-            indent, 
-            relative_path.file_name().unwrap_or_default().to_string_lossy())?;
-        return Ok(());
-    }
-    
-    // Read file content - with proper error handling
-    let mut content = String::new();
-    match File::open(path) {
-        Ok(mut file) => {
-            if let Err(e) = file.read_to_string(&mut content) {
-                eprintln!("Error reading file {}: {}", path.display(), e);
-                let indent = get_indent(relative_path.components().count().saturating_sub(1));
-                writeln!(file_tree, "{}├── {} (skipped - error reading)", 
-                    indent, 
-                    relative_path.file_name().unwrap_or_default().to_string_lossy())?;
-                return Ok(());
+/// Process the files the walk found on `config.io_threads` threads, keeping
+/// them (and their assets and errors) in walk order
+fn process_files(
+    pending: &[Pending],
+    tree: &mut FileTree,
+    assets: &mut Vec<Asset>,
+    errors: &mut Vec<ContextGenError>,
+    config: &ProcessConfig,
+) -> io::Result<Vec<SourceFile>> {
+    type Processed = (Option<SourceFile>, Vec<Asset>, Vec<ContextGenError>);
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<io::Result<Processed>>>> = Mutex::new((0..pending.len()).map(|_| None).collect());
+    let worker = || {
+        let mut worker_tree = FileTree::new(tree.annotates());
+        loop {
+            let i = next.fetch_add(1, Ordering::Relaxed);
+            let Some(Pending { path, relative_path, ranges }) = pending.get(i) else { break };
+            let processing = Instant::now();
+            let mut file_assets = Vec::new();
+            let mut file_errors = Vec::new();
+            let result = process_file(path, relative_path, ranges, &mut worker_tree, &mut file_assets, &mut file_errors, config)
+                .map(|file| (file, file_assets, file_errors));
+            config.timings.file(relative_path, processing.elapsed());
+            results.lock().unwrap()[i] = Some(result);
+            
+            let count = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if config.verbose && count.is_multiple_of(100) {
+                println!("Processed {} files...", count);
             }
-        },
-        Err(e) => {
-            eprintln!("Error opening file {}: {}", path.display(), e);
-            return Ok(());
         }
+        worker_tree
+    };
+    let threads = config.io_threads.min(pending.len()).max(1);
+    let trees: Vec<FileTree> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (1..threads).map(|_| scope.spawn(worker)).collect();
+        let mut trees = vec![worker()];
+        trees.extend(handles.into_iter().map(|h| h.join().expect("file processing thread panicked")));
+        trees
+    });
+    for worker_tree in trees {
+        tree.merge(worker_tree);
     }
     
-    // Create a safe filename for the output
-    let safe_filename = sanitize_filename(&relative_path.to_string_lossy());
-    let output_file_path = output_dir.join(format!("{}.txt", safe_filename));
+    let mut files = Vec::new();
+    for result in results.into_inner().unwrap().into_iter().flatten() {
+        let (file, file_assets, file_errors) = result?;
+        files.extend(file);
+        assets.extend(file_assets);
+        errors.extend(file_errors);
+    }
+    Ok(files)
+}
+
+/// Walker over a root with the ignore rules and limits every walk shares
+fn walk(opt: &Opt, dir: &Path) -> io::Result<ignore::Walk> {
+    let ignore_files = profiles::ignore_file_names(&opt.ignore_file_types)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
     
-    // Create output file with error handling
-    let mut output_file = match File::create(&output_file_path) {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!("Error creating output file {}: {}", output_file_path.display(), e);
-            return Ok(());
+    // Set up a custom walker with limits
+    let mut walker = WalkBuilder::new(dir);
+    for name in &ignore_files {
+        walker.add_custom_ignore_filename(name);
+    }
+    Ok(walker
+        .hidden(false) // Don't skip hidden files by default
+        .git_global(true) // Use global gitignore
+        .git_ignore(true) // Use .gitignore
+        .max_depth(Some(opt.max_depth)) // Limit directory depth
+        .follow_links(opt.follow_symlinks) // Loops back into an ancestor come back as errors
+        // Not even walked into, a virtualenv alone can hold tens of thousands of files
+        .filter_entry(|entry| !(entry.path().is_dir() && profiles::is_python_environment(entry.path())))
+        .build())
+}
+
+/// Runs above either of these ask first, a mistyped path can mean a home directory
+const LARGE_RUN_FILES: usize = 10_000;
+const LARGE_RUN_TOKENS: usize = 2_000_000;
+
+/// Estimate the run from file sizes alone and, if it's very large, ask whether
+/// to go on. Without a terminal to ask on it warns and goes on.
+fn confirm_large_run(opt: &Opt, roots: &[Root], ignores: &HashSet<String>, output_dir: &Path, chars_per_token: f64) -> io::Result<bool> {
+    let mut files = 0;
+    let mut bytes = 0;
+    'roots: for root in roots {
+        let own_output = paths::output_under(&root.dir, output_dir);
+        for entry in walk(opt, &root.dir)?.flatten() {
+            let path = entry.path();
+            if own_output.as_ref().is_some_and(|dir| path.starts_with(dir))
+                || path.components().any(|comp| comp.as_os_str().to_str().is_some_and(|name| ignores.contains(name)))
+            {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else { continue };
+            if !metadata.is_file() || metadata.len() > opt.max_size {
+                continue;
+            }
+            files += 1;
+            bytes += metadata.len();
+            if files >= opt.max_files {
+                break 'roots;
+            }
         }
-    };
+    }
+    let tokens = (bytes as f64 / chars_per_token) as usize;
+    if files < LARGE_RUN_FILES && tokens < LARGE_RUN_TOKENS {
+        return Ok(true);
+    }
+
+    let summary = format!("about to process {} files (~{} tokens)", thousands(files), short_count(tokens));
+    if !io::stdin().is_terminal() {
+        eprintln!("Warning: {} (narrow it down with --ignore or --max-files)", summary);
+        return Ok(true);
+    }
+    eprint!("{}, continue? [y/N] ", summary[..1].to_uppercase() + &summary[1..]);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// 41238 as "41,238"
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// 9300000 as "9.3M"
+fn short_count(n: usize) -> String {
+    match n {
+        n if n >= 1_000_000 => format!("{:.1}M", n as f64 / 1_000_000.0),
+        n if n >= 1_000 => format!("{:.1}K", n as f64 / 1_000.0),
+        n => n.to_string(),
+    }
+}
+
+/// The directory that leads back into one of its ancestors, for a loop error
+fn symlink_loop(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithPath { err, .. } | ignore::Error::WithDepth { err, .. } => symlink_loop(err),
+        _ => None,
+    }
+}
+
+/// Report detected secrets, failing with --fail-on-secrets, then apply --anonymize;
+/// everything that leaves the machine (output files, `release`, `ask`, `serve`) goes through here
+fn guard_content(opt: &Opt, files: &mut [SourceFile]) -> Result<Option<Anonymizer>, ContextGenError> {
+    let with_secrets: Vec<&SourceFile> = files.iter().filter(|f| !f.redactions.is_empty()).collect();
+    for file in &with_secrets {
+        eprintln!("Secrets detected in {}: {}", file.relative_path.display(), file.redactions.join(", "));
+    }
+    if opt.fail_on_secrets && !with_secrets.is_empty() {
+        return Err(ContextGenError::InvalidInput(format!(
+            "Secrets detected in {} files (remove --fail-on-secrets to redact them instead)",
+            with_secrets.len()
+        )));
+    }
     
-    // Write file name with extension
-    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
-    if let Err(e) = writeln!(output_file, "{}", file_name) {
-        eprintln!("Error writing to output file: {}", e);
-        return Ok(());
+    if !opt.anonymize {
+        return Ok(None);
+    }
+    let mut anonymizer = Anonymizer::new(&opt.anonymize_terms);
+    if let Some(map_path) = &opt.anonymize_map {
+        anonymizer.load_mapping(map_path)?;
+    }
+    anonymizer.anonymize(files);
+    Ok(Some(anonymizer))
+}
+
+/// The mapping undoes the anonymization, so it goes in the output directory
+/// itself, never the staging directory whose contents --archive packs
+fn write_anonymize_map(opt: &Opt, anonymizer: &Anonymizer) -> io::Result<()> {
+    let output_dir = Path::new(&opt.output);
+    fs::create_dir_all(output_dir)?;
+    let map_path = output_dir.join("anonymize-map.txt");
+    fs::write(&map_path, anonymizer.mapping_file())?;
+    println!("Anonymization mapping written to: {} (keep it private, it reverses the anonymization)", map_path.display());
+    Ok(())
+}
+
+/// Narrow the collected files down to the ones asked for with --entry, --package and --grep
+fn select_files(
+    opt: &Opt,
+    roots: &[Root],
+    files: &mut Vec<SourceFile>,
+    tree: &mut FileTree,
+    chars_per_token: f64,
+) -> io::Result<()> {
+    // Narrow down to the entry points and what they import
+    if !opt.entry.is_empty() {
+        let mut entries = Vec::new();
+        for entry in opt.entry.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let entry = Path::new(entry.trim_start_matches("./")).to_path_buf();
+            if !files.iter().any(|f| f.relative_path == entry) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Entry file '{}' was not found or was skipped", entry.display()),
+                ));
+            }
+            entries.push(entry);
+        }
+        let included = if opt.follow_imports {
+            let graph = ImportResolver::new().graph(files);
+            imports::closure(&graph, &entries, opt.depth)
+        } else {
+            entries.into_iter().collect()
+        };
+        files.retain(|file| {
+            let keep = included.contains(&file.relative_path);
+            if !keep {
+                tree.exclude_file(&file.relative_path, file.size, file.tokens, "not imported by entry");
+            }
+            keep
+        });
     }
     
-    if let Err(e) = writeln!(output_file) {
-        eprintln!("Error writing to output file: {}", e);
-        return Ok(());
+    if !opt.package.is_empty() {
+        let packages = workspace::detect(&roots[0].dir).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "--package requires a Cargo, pnpm, yarn or npm workspace")
+        })?;
+        let names: Vec<&str> = opt.package.split(',').map(str::trim).filter(|n| !n.is_empty()).collect();
+        let selected = workspace::select(&packages, &names, opt.with_path_deps)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        files.retain(|file| {
+            let keep = workspace::package_of(&packages, &file.relative_path).is_some_and(|p| selected.contains(&p.name));
+            if !keep {
+                tree.exclude_file(&file.relative_path, file.size, file.tokens, "outside selected packages");
+            }
+            keep
+        });
     }
     
-    // Write content with error handling
-    if let Err(e) = write!(output_file, "{}", content) {
-        eprintln!("Error writing content to output file: {}", e);
+    if let Some(since) = opt.modified_since {
+        let changed = history::changed_since(roots, files, since);
+        files.retain(|file| {
+            let keep = changed.contains(&file.relative_path);
+            if !keep {
+                tree.exclude_file(&file.relative_path, file.size, file.tokens, "not changed recently");
+            }
+            keep
+        });
     }
     
-    // Add to file tree
-    let indent = get_indent(relative_path.components().count().saturating_sub(1));
-    writeln!(file_tree, "{}├── {}", 
-        indent, 
-        relative_path.file_name().unwrap_or_default().to_string_lossy())?;
+    if let Some(author) = &opt.author {
+        let authored = history::authored_by(roots, files, author, opt.modified_since, opt.author_share)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        files.retain(|file| {
+            let keep = authored.contains(&file.relative_path);
+            if !keep {
+                tree.exclude_file(&file.relative_path, file.size, file.tokens, "not by author");
+            }
+            keep
+        });
+    }
     
+    // Topical slicing; files without a match are left out of the tree entirely
+    if let Some(pattern) = &opt.grep {
+        let pattern = Regex::new(pattern).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        files.retain_mut(|file| {
+            if !pattern.is_match(&file.content) {
+                tree.remove_file(&file.relative_path, file.size, file.tokens);
+                return false;
+            }
+            if let Some(context) = opt.grep_context {
+                let before = file.tokens;
+                file.content = transform::matching_lines(&file.content, &pattern, context);
+                file.tokens = tokens::estimate_tokens(&file.content, chars_per_token);
+                tree.update_tokens(&file.relative_path, before, file.tokens);
+            }
+            true
+        });
+    }
     Ok(())
 }
 
-fn sanitize_filename(path: &str) -> String {
-    // Replace path separators and limit length
-    path.replace("/", "_")
-        .replace("\\", "_")
-        .chars()
-        .take(150) // Limit filename length
-        .collect::<String>()
+/// Keep files in output order until the budget is spent; when some didn't fit,
+/// a note saying how far over the whole set would have gone. Leaving files out
+/// is what a budget is for, so it's not an error.
+fn apply_token_budget(files: &mut Vec<SourceFile>, tree: &mut FileTree, budget: usize, by: BudgetBy) -> Option<String> {
+    let all: Vec<usize> = (0..files.len()).collect();
+    let (needed, fits) = fit_budget(files, &all, budget, by);
+    let reasons: Vec<Option<String>> = fits.iter().map(|fits| (!fits).then(|| "over token budget".to_string())).collect();
+    let excluded = exclude_unfit(files, tree, &reasons);
+    (excluded > 0).then(|| format!("Token budget of {} exceeded, {} tokens needed ({} files left out)", budget, needed, excluded))
+}
+
+/// `--budgets`: each file counts against the most specific directory with a
+/// budget it's in, files in none of them only against --token-budget. Returns
+/// a note for each directory that went over.
+fn apply_directory_budgets(files: &mut Vec<SourceFile>, tree: &mut FileTree, budgets: &[(PathBuf, usize)], by: BudgetBy) -> Vec<String> {
+    let mut reasons: Vec<Option<String>> = vec![None; files.len()];
+    let mut notes = Vec::new();
+    for (dir, budget) in budgets {
+        let members: Vec<usize> = (0..files.len())
+            .filter(|&i| tokens::budget_directory(&files[i].relative_path, budgets) == Some(dir.as_path()))
+            .collect();
+        let (needed, fits) = fit_budget(files, &members, *budget, by);
+        let mut excluded = 0;
+        for &i in members.iter().filter(|&&i| !fits[i]) {
+            reasons[i] = Some(format!("over {} budget", tokens::directory_label(dir)));
+            excluded += 1;
+        }
+        if excluded > 0 {
+            notes.push(format!("Token budget of {} for {} exceeded, {} tokens needed ({} files left out)", budget, tokens::directory_label(dir), needed, excluded));
+        }
+    }
+    exclude_unfit(files, tree, &reasons);
+    notes
+}
+
+/// The tokens `candidates` (indices into `files`) need, and which of them fit
+/// in `budget`, by index into `files`. They take their place in output order
+/// or by centrality; either way the kept ones stay in output order.
+fn fit_budget(files: &[SourceFile], candidates: &[usize], budget: usize, by: BudgetBy) -> (usize, Vec<bool>) {
+    let needed: usize = candidates.iter().map(|&i| files[i].tokens).sum();
+    let mut offered = candidates.to_vec();
+    if by == BudgetBy::Centrality && needed > budget {
+        let centrality = imports::centrality(&ImportResolver::new().graph(files));
+        let of = |i: usize| centrality.get(&files[i].relative_path).copied().unwrap_or(0.0);
+        offered.sort_by(|&a, &b| of(b).total_cmp(&of(a)).then(a.cmp(&b)));
+    }
+    let mut used = 0;
+    let mut fits = vec![false; files.len()];
+    for i in offered {
+        if used + files[i].tokens <= budget {
+            used += files[i].tokens;
+            fits[i] = true;
+        }
+    }
+    (needed, fits)
+}
+
+/// Drop the files with a reason, recording it in the tree; returns how many
+fn exclude_unfit(files: &mut Vec<SourceFile>, tree: &mut FileTree, reasons: &[Option<String>]) -> usize {
+    let before = files.len();
+    let mut reasons = reasons.iter();
+    files.retain(|file| match reasons.next() {
+        Some(Some(reason)) => {
+            tree.exclude_file(&file.relative_path, file.size, file.tokens, reason);
+            false
+        }
+        _ => true,
+    });
+    before - files.len()
 }
 
-fn is_binary_file(path: &Path) -> io::Result<bool> {
-    // Read the first 8KB of the file
-    let mut buffer = [0; 8192];
+fn process_file(
+    path: &Path,
+    relative_path: &Path,
+    line_ranges: &[(usize, usize)],
+    tree: &mut FileTree,
+    assets: &mut Vec<Asset>,
+    errors: &mut Vec<ContextGenError>,
+    config: &ProcessConfig,
+) -> io::Result<Option<SourceFile>> {
+    // Safety check for path length
+    if relative_path.to_string_lossy().chars().count() > config.max_path_length {
+        tree.add_skipped(relative_path, "path too long");
+        return Ok(None);
+    }
+
+    if let Some(reason) = config.tests.skip_reason(relative_path) {
+        tree.add_skipped(relative_path, reason);
+        return Ok(None);
+    }
+    if config.profile.excludes_extension(relative_path) {
+        tree.add_skipped(relative_path, "excluded by preset");
+        return Ok(None);
+    }
+    // Asked for once, --io-throttle counts every request
+    let metadata = config.fs.metadata(path);
+    if !config.include_fixtures {
+        let size = metadata.as_ref().map(|m| m.len).unwrap_or(0);
+        if let Some(reason) = testfiles::fixture_reason(relative_path, size) {
+            tree.add_skipped(relative_path, reason);
+            return Ok(None);
+        }
+    }
+
+    // Repos already mark generated and vendored files for GitHub's language stats
+    let linguist = config.gitattributes.iter().find_map(|(prefix, attributes)| {
+        attributes.linguist_reason(relative_path.strip_prefix(prefix).ok()?)
+    });
+    if let Some(reason) = linguist {
+        tree.add_skipped(relative_path, reason);
+        return Ok(None);
+    }
+
+    // Preprocessor output replaces the file, whatever it is; a failed command costs the file
+    let reading = Instant::now();
+    let mut extracted = None;
+    if let Some(command) = config.preprocessors.command_for(relative_path) {
+        match preprocess::run(command, path) {
+            Ok(output) => extracted = Some(encoding::decode(&output).0),
+            Err(reason) => {
+                errors.push(ContextGenError::Preprocess { path: path.to_path_buf(), command: command.to_string(), reason });
+                tree.add_skipped(relative_path, "preprocessor failed");
+                return Ok(None);
+            }
+        }
+    }
+    
+    // Documents are converted to text; on failure they are treated like any other binary
+    if extracted.is_none() && config.extract_documents && documents::is_document(path) {
+        match documents::extract_text(path) {
+            Ok(text) => extracted = Some(text),
+            Err(reason) => errors.push(ContextGenError::Encoding { path: path.to_path_buf(), reason }),
+        }
+    }
+    
+    // LFS pointers stand in for objects that may never have been downloaded
+    if extracted.is_none() {
+        if let Some(pointer) = lfs::read_pointer(config.fs.as_ref(), path, metadata.as_ref().map(|m| m.len).unwrap_or(0)) {
+            let unfetched = format!("LFS pointer, {} object", tree::format_size(pointer.size));
+            if config.lfs_fetch.is_none_or(|max| pointer.size > max) {
+                tree.add_skipped(relative_path, &unfetched);
+                return Ok(None);
+            }
+            match lfs::fetch(path, &pointer) {
+                Ok(object) if content_inspector::inspect(&object[..object.len().min(8192)]).is_binary() => {
+                    tree.add_skipped(relative_path, "binary LFS object");
+                    return Ok(None);
+                }
+                Ok(object) => extracted = Some(encoding::decode(&object).0),
+                Err(reason) => {
+                    errors.push(ContextGenError::LfsFetch { path: path.to_path_buf(), reason });
+                    tree.add_skipped(relative_path, &unfetched);
+                    return Ok(None);
+                }
+            }
+        }
+    }
+    
+    // Skip binary files and very large files; binaries are listed in assets.txt
+    if extracted.is_none() && config.binary.is_binary(config.fs.as_ref(), path, relative_path) {
+        tree.add_skipped(relative_path, "binary");
+        assets.push(Asset {
+            path: path.to_path_buf(),
+            relative_path: relative_path.to_path_buf(),
+            size: metadata.as_ref().map(|m| m.len).unwrap_or(0),
+            mime: binary::guess_mime(path),
+        });
+        return Ok(None);
+    }
+    // Lock files are summarized and data files sampled, so their size doesn't matter
+    let file_name = relative_path.file_name().unwrap_or_default().to_string_lossy();
+    let summarize_lock = config.summarize_lockfiles && lockfile::is_lockfile(&file_name);
+    // Line ranges make the size of the whole file irrelevant too, and replace summaries and samples
+    let whole_file = line_ranges.is_empty();
+    let sample_rows = config.sample_data.filter(|_| whole_file && sample::is_data_file(&file_name));
+    if extracted.is_none() && whole_file && !summarize_lock && sample_rows.is_none() && is_too_large(&metadata, path, config.max_size) {
+        tree.add_skipped(relative_path, "too large");
+        return Ok(None);
+    }
     
-    match File::open(path) {
-        Ok(mut file) => {
-            let bytes_read = match file.read(&mut buffer) {
+    // Preprocessor output and extracted text don't line up with what git blames
+    let from_file = extracted.is_none();
+    let mut cut_down = false;
+    let mut content = match extracted {
+        Some(text) => text,
+        None => {
+            // Read file content - with proper error handling
+            let bytes = match config.fs.read(path) {
                 Ok(bytes) => bytes,
-                Err(_) => return Ok(true), // If we can't read, assume binary
+                Err(source) => {
+                    errors.push(ContextGenError::Read { path: path.to_path_buf(), source });
+                    tree.add_skipped(relative_path, "error reading");
+                    return Ok(None);
+                }
             };
             
-            // If file is empty, it's not binary
-            if bytes_read == 0 {
-                return Ok(false);
+            // Mapped files are cut down to the lines or rows kept before decoding,
+            // so only those are ever copied onto the heap
+            let mut bytes = bytes;
+            if bytes.is_mapped() && encoding::ascii_compatible(&bytes) {
+                if !whole_file {
+                    bytes = vfs::Contents::Heap(transform::select_line_bytes(&bytes, line_ranges));
+                    cut_down = true;
+                } else if let Some(rows) = sample_rows {
+                    bytes = vfs::Contents::Heap(sample::sample_bytes(&file_name, &bytes, rows));
+                    cut_down = true;
+                }
             }
             
-            // Check for null bytes or other binary indicators
-            for &byte in &buffer[..bytes_read] {
-                if byte == 0 {
-                    return Ok(true);
-                }
+            // Legacy encodings are transcoded rather than dropped
+            let (content, source_encoding) = encoding::decode(&bytes);
+            if config.verbose && source_encoding != "UTF-8" {
+                println!("Transcoded {} from {}", relative_path.display(), source_encoding);
             }
-        },
-        Err(_) => return Ok(true), // If we can't open, assume binary
+            content
+        }
+    };
+    config.timings.add("reading", reading.elapsed());
+    
+    if !whole_file && !cut_down {
+        content = transform::select_lines(&content, line_ranges);
     }
     
-    // Check file extension for common binary formats
-    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
-    let binary_extensions = [
-        "png", "jpg", "jpeg", "gif", "bmp", "tiff",
-        "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx",
-        "zip", "tar", "gz", "rar", "7z",
-        "exe", "dll", "so", "dylib", "bin",
-        "mp3", "mp4", "wav", "avi", "mov",
-    ];
+    if summarize_lock && whole_file {
+        if let Some(summary) = lockfile::summarize(&file_name, &content, path) {
+            content = summary;
+        }
+    }
     
-    if binary_extensions.contains(&extension.to_lowercase().as_str()) {
-        return Ok(true);
+    // Notebook outputs are mostly base64 blobs and metadata
+    if config.flatten_notebooks && notebook::is_notebook(&file_name) {
+        if let Some(flattened) = notebook::flatten(&content) {
+            content = flattened;
+        }
+    }
+    
+    // Data files are useful for their schema, not for every row
+    if let Some(rows) = sample_rows.filter(|_| !cut_down) {
+        content = sample::sample(&file_name, &content, rows);
+    }
+    
+    // A single bundle.min.js can blow the whole token budget
+    if !config.include_generated && !summarize_lock && sample_rows.is_none() {
+        if let Some(reason) = generated::detect(&file_name, &content) {
+            tree.add_skipped(relative_path, reason);
+            return Ok(None);
+        }
+    }
+    
+    // Variable names in .env files are useful context, their values are not
+    if let Some(mask) = &config.env_mask {
+        if secrets::is_env_file(&file_name) {
+            content = secrets::mask_env_values(&content, mask);
+        }
+    }
+    
+    let language = language::detect_source(relative_path, &content);
+    if let Some(language) = language {
+        // License banners are removed first, they are comments themselves
+        if config.strip_license_headers {
+            content = transform::strip_license_header(&content, language);
+        }
+        if config.mode == Mode::Skeleton {
+            if let Some(skeleton) = skeleton::skeleton(&content, language) {
+                content = skeleton;
+            }
+        }
+        if config.strip_comments {
+            content = transform::strip_comments(&content, language);
+        }
+    }
+    
+    if !config.whitespace.is_noop() {
+        content = transform::normalize_whitespace(&content, &config.whitespace, language);
+    }
+    
+    if let Some(mode) = config.blame.filter(|_| from_file) {
+        if let Some(blame) = history::blame(path) {
+            let comment = language.and_then(|l| l.line_comments.first().copied());
+            content = history::annotate(&content, &blame, mode, comment);
+        }
     }
     
-    Ok(false)
+    let mut redactions = Vec::new();
+    if let Some(scanner) = &config.secrets {
+        let (redacted, found) = scanner.redact(&content);
+        content = redacted;
+        redactions = found;
+    }
+    
+    let metadata = metadata.ok();
+    let tokenizing = Instant::now();
+    let mut tokens = tokens::estimate_tokens(&content, config.chars_per_token);
+    config.timings.add("tokenizing", tokenizing.elapsed());
+    
+    // Byte size is a poor proxy for tokens, a minified or CJK-heavy file is dense
+    if let Some((max_tokens, over)) = config.max_tokens_per_file.filter(|(max, _)| tokens > *max) {
+        match over {
+            OverLimit::Skip => {
+                tree.add_skipped(relative_path, &format!("~{} tokens, over --max-tokens-per-file", tokens));
+                return Ok(None);
+            }
+            OverLimit::Truncate => {
+                content = tokens::truncate_source(&content, language, max_tokens, config.chars_per_token);
+                tokens = tokens::estimate_tokens(&content, config.chars_per_token);
+                tree.mark(relative_path, "truncated");
+            }
+        }
+    }
+    let file = SourceFile {
+        relative_path: relative_path.to_path_buf(),
+        size: metadata.map(|m| m.len).unwrap_or(content.len() as u64),
+        modified: metadata.and_then(|m| m.modified),
+        tokens,
+        content,
+        redactions,
+    };
+    
+    // Add to file tree
+    tree.add_file(relative_path, file.size, file.tokens);
+    
+    Ok(Some(file))
 }
 
-fn is_too_large(path: &Path) -> io::Result<bool> {
-    // Get current metadata
-    match fs::metadata(path) {
-        Ok(metadata) => {
-            // Default threshold: 1MB (will be overridden by max_size parameter)
-            Ok(metadata.len() > 1_000_000)
-        },
+fn write_file_output(file: &SourceFile, output_dir: &Path) -> Result<(), ContextGenError> {
+    // Create a safe filename for the output
+    let safe_filename = paths::safe_file_name(&file.relative_path.to_string_lossy());
+    let output_file_path = paths::long_path(&output_dir.join(format!("{}.txt", safe_filename)));
+    
+    // File name with extension, a blank line, then the content
+    let file_name = file.relative_path.file_name().unwrap_or_default().to_string_lossy();
+    File::create(&output_file_path)
+        .and_then(|mut output_file| write!(output_file, "{}\n\n{}", file_name, file.content))
+        .map_err(|source| ContextGenError::Output { path: output_file_path, source })
+}
+
+fn is_too_large(metadata: &io::Result<vfs::Metadata>, path: &Path, max_size: u64) -> bool {
+    match metadata {
+        Ok(metadata) => metadata.len > max_size,
         Err(e) => {
             eprintln!("Couldn't get metadata for {}: {}", path.display(), e);
             // If we can't determine size, assume it's not too large
-            Ok(false)
+            false
         }
     }
 }
\ No newline at end of file
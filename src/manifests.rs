@@ -0,0 +1,247 @@
+// manifests.rs
+//! Direct dependencies, features and scripts from the Cargo.toml, package.json,
+//! pyproject.toml and go.mod files among the collected files.
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::SourceFile;
+
+#[derive(Default)]
+pub struct Dependency {
+    pub name: String,
+    /// Version requirement as written, or where it comes from (path, git)
+    pub version: String,
+    /// Cargo features turned on
+    pub features: Vec<String>,
+    pub optional: bool,
+}
+
+#[derive(Default)]
+pub struct Manifest {
+    pub path: PathBuf,
+    /// The ecosystem: Cargo, npm, Python or Go
+    pub kind: &'static str,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub dependencies: Vec<Dependency>,
+    /// Development, build and test dependencies
+    pub dev_dependencies: Vec<Dependency>,
+    /// Cargo features and what each turns on
+    pub features: Vec<(String, Vec<String>)>,
+    /// npm scripts and Python console scripts, with their command
+    pub scripts: Vec<(String, String)>,
+}
+
+/// Every manifest that parses, in file order
+pub fn collect(files: &[SourceFile]) -> Vec<Manifest> {
+    files.iter().filter_map(|file| parse(&file.relative_path, &file.content)).collect()
+}
+
+fn parse(path: &Path, content: &str) -> Option<Manifest> {
+    let mut manifest = Manifest { path: path.to_path_buf(), ..Manifest::default() };
+    match path.file_name()?.to_str()? {
+        "Cargo.toml" => {
+            let toml: Value = content.parse().ok()?;
+            manifest.kind = "Cargo";
+            (manifest.name, manifest.description) = toml_names(toml.get("package"));
+            manifest.dependencies = cargo_dependencies(toml.get("dependencies"));
+            // A virtual workspace manifest only declares shared versions
+            manifest.dependencies.extend(cargo_dependencies(toml.get("workspace").and_then(|w| w.get("dependencies"))));
+            manifest.dev_dependencies = cargo_dependencies(toml.get("dev-dependencies"));
+            manifest.dev_dependencies.extend(cargo_dependencies(toml.get("build-dependencies")));
+            if let Some(features) = toml.get("features").and_then(Value::as_table) {
+                manifest.features = features.iter().map(|(name, enables)| (name.clone(), strings(enables))).collect();
+            }
+        }
+        "package.json" => {
+            let json: serde_json::Value = serde_json::from_str(content).ok()?;
+            let field = |key: &str| json.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            manifest.kind = "npm";
+            (manifest.name, manifest.description) = (field("name"), field("description"));
+            manifest.dependencies = npm_dependencies(json.get("dependencies"));
+            manifest.dependencies.extend(npm_dependencies(json.get("peerDependencies")));
+            manifest.dependencies.extend(npm_dependencies(json.get("optionalDependencies")).into_iter().map(|d| Dependency { optional: true, ..d }));
+            manifest.dev_dependencies = npm_dependencies(json.get("devDependencies"));
+            if let Some(scripts) = json.get("scripts").and_then(|s| s.as_object()) {
+                manifest.scripts = scripts.iter().map(|(name, command)| (name.clone(), command.as_str().unwrap_or("").to_string())).collect();
+            }
+        }
+        "pyproject.toml" => {
+            let toml: Value = content.parse().ok()?;
+            let project = toml.get("project");
+            let poetry = toml.get("tool").and_then(|t| t.get("poetry"));
+            manifest.kind = "Python";
+            (manifest.name, manifest.description) = toml_names(project.or(poetry));
+
+            manifest.dependencies = pep508_dependencies(project.and_then(|p| p.get("dependencies")));
+            if let Some(extras) = project.and_then(|p| p.get("optional-dependencies")).and_then(Value::as_table) {
+                for list in extras.values() {
+                    manifest.dependencies.extend(pep508_dependencies(Some(list)).into_iter().map(|d| Dependency { optional: true, ..d }));
+                }
+            }
+            if let Some(poetry) = poetry {
+                // Poetry lists the Python version among the dependencies
+                manifest.dependencies.extend(cargo_dependencies(poetry.get("dependencies")).into_iter().filter(|d| d.name != "python"));
+                manifest.dev_dependencies.extend(cargo_dependencies(poetry.get("dev-dependencies")));
+                if let Some(groups) = poetry.get("group").and_then(Value::as_table) {
+                    for group in groups.values() {
+                        manifest.dev_dependencies.extend(cargo_dependencies(group.get("dependencies")));
+                    }
+                }
+            }
+            let scripts = project.and_then(|p| p.get("scripts")).or(poetry.and_then(|p| p.get("scripts")));
+            if let Some(scripts) = scripts.and_then(Value::as_table) {
+                manifest.scripts = scripts.iter().map(|(name, target)| (name.clone(), target.as_str().unwrap_or("").to_string())).collect();
+            }
+        }
+        "go.mod" => {
+            manifest.kind = "Go";
+            let mut in_require = false;
+            for line in content.lines() {
+                let line = line.trim();
+                if let Some(module) = line.strip_prefix("module ") {
+                    manifest.name = Some(module.trim().to_string());
+                } else if line.starts_with("require (") {
+                    in_require = true;
+                } else if in_require && line == ")" {
+                    in_require = false;
+                } else if let Some(require) = line.strip_prefix("require ").or(in_require.then_some(line)) {
+                    // Indirect requirements are other modules' dependencies
+                    if require.contains("// indirect") {
+                        continue;
+                    }
+                    let mut parts = require.split_whitespace();
+                    if let (Some(module), Some(version)) = (parts.next(), parts.next()) {
+                        manifest.dependencies.push(Dependency { name: module.to_string(), version: version.to_string(), ..Dependency::default() });
+                    }
+                }
+            }
+        }
+        _ => return None,
+    }
+    Some(manifest)
+}
+
+/// Everything the manifests declare, one section per manifest
+pub fn render(manifests: &[Manifest]) -> String {
+    let mut out = String::from("# Dependencies\n");
+    if manifests.is_empty() {
+        out.push_str("\nNo Cargo.toml, package.json, pyproject.toml or go.mod found.\n");
+    }
+    for manifest in manifests {
+        let name = manifest.name.as_deref().map(|n| format!(" ({})", n)).unwrap_or_default();
+        let _ = writeln!(out, "\n## {}{}\n", manifest.path.to_string_lossy().replace('\\', "/"), name);
+        if let Some(description) = &manifest.description {
+            let _ = writeln!(out, "{}\n", description);
+        }
+        let _ = writeln!(out, "{} dependencies ({}):", manifest.kind, manifest.dependencies.len());
+        render_dependencies(&mut out, &manifest.dependencies);
+        if !manifest.dev_dependencies.is_empty() {
+            let _ = writeln!(out, "\nDevelopment dependencies ({}):", manifest.dev_dependencies.len());
+            render_dependencies(&mut out, &manifest.dev_dependencies);
+        }
+        if !manifest.features.is_empty() {
+            out.push_str("\nFeatures:\n");
+            for (feature, enables) in &manifest.features {
+                let _ = writeln!(out, "- {} = [{}]", feature, enables.join(", "));
+            }
+        }
+        if !manifest.scripts.is_empty() {
+            out.push_str("\nScripts:\n");
+            for (script, command) in &manifest.scripts {
+                let _ = writeln!(out, "- {}: `{}`", script, command);
+            }
+        }
+    }
+    out
+}
+
+fn render_dependencies(out: &mut String, dependencies: &[Dependency]) {
+    if dependencies.is_empty() {
+        out.push_str("- (none)\n");
+    }
+    for dependency in dependencies {
+        let _ = write!(out, "- {} {}", dependency.name, dependency.version);
+        if !dependency.features.is_empty() {
+            let _ = write!(out, ", features: {}", dependency.features.join(", "));
+        }
+        if dependency.optional {
+            out.push_str(", optional");
+        }
+        out.push('\n');
+    }
+}
+
+/// The name and description of a [package] or [project] table
+fn toml_names(table: Option<&Value>) -> (Option<String>, Option<String>) {
+    let field = |key: &str| table.and_then(|t| t.get(key)).and_then(Value::as_str).map(str::to_string);
+    (field("name"), field("description"))
+}
+
+/// A TOML table of `name = "1.0"` or `name = { version = "1.0", ... }` entries
+fn cargo_dependencies(table: Option<&Value>) -> Vec<Dependency> {
+    let Some(table) = table.and_then(Value::as_table) else { return Vec::new() };
+    table
+        .iter()
+        .map(|(name, spec)| {
+            let version = match spec {
+                Value::String(version) => version.clone(),
+                Value::Table(spec) => {
+                    let field = |key: &str| spec.get(key).and_then(Value::as_str);
+                    match (field("version"), field("path"), field("git")) {
+                        (Some(version), _, _) => version.to_string(),
+                        (None, Some(path), _) => format!("path {}", path),
+                        (None, None, Some(git)) => format!("git {}", git),
+                        _ if spec.get("workspace").is_some() => "workspace".to_string(),
+                        _ => "*".to_string(),
+                    }
+                }
+                _ => "*".to_string(),
+            };
+            let features = spec.get("features").map(strings).unwrap_or_default();
+            let optional = spec.get("optional").and_then(Value::as_bool).unwrap_or(false);
+            Dependency { name: name.clone(), version, features, optional }
+        })
+        .collect()
+}
+
+fn npm_dependencies(object: Option<&serde_json::Value>) -> Vec<Dependency> {
+    let Some(object) = object.and_then(|o| o.as_object()) else { return Vec::new() };
+    object
+        .iter()
+        .map(|(name, version)| Dependency {
+            name: name.clone(),
+            version: version.as_str().unwrap_or("*").to_string(),
+            ..Dependency::default()
+        })
+        .collect()
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value.as_array().map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect()).unwrap_or_default()
+}
+
+/// A list of requirement strings like `requests>=2.28; python_version > "3.8"`
+fn pep508_dependencies(list: Option<&Value>) -> Vec<Dependency> {
+    let Some(list) = list.and_then(Value::as_array) else { return Vec::new() };
+    list.iter()
+        .filter_map(Value::as_str)
+        .map(|requirement| {
+            let requirement = requirement.split(';').next().unwrap_or(requirement).trim();
+            let end = requirement.find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))).unwrap_or(requirement.len());
+            let rest = requirement[end..].trim_start();
+            // Extras like requests[socks] aren't part of the version
+            let version = match rest.strip_prefix('[') {
+                Some(extras) => extras.split_once(']').map(|(_, v)| v).unwrap_or("").trim(),
+                None => rest.trim(),
+            };
+            Dependency {
+                name: requirement[..end].to_string(),
+                version: if version.is_empty() { "*".to_string() } else { version.to_string() },
+                ..Dependency::default()
+            }
+        })
+        .collect()
+}
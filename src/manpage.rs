@@ -0,0 +1,74 @@
+// manpage.rs
+use std::io;
+
+use structopt::clap::App;
+
+/// A roff man page built from the command's help, so it always lists the
+/// options of this build
+pub fn render(mut app: App) -> io::Result<String> {
+    let mut help = Vec::new();
+    app.write_long_help(&mut help).map_err(|e| io::Error::other(e.to_string()))?;
+    let help = String::from_utf8_lossy(&help);
+
+    let name = env!("CARGO_PKG_NAME");
+    let mut out = String::new();
+    out.push_str(&format!(".TH {} 1 \"\" \"{} {}\"\n", name.to_uppercase(), name, env!("CARGO_PKG_VERSION")));
+    out.push_str(&format!(".SH NAME\n{} \\- {}\n", escape(name), escape(env!("CARGO_PKG_DESCRIPTION"))));
+
+    // Help sections are an unindented heading ("OPTIONS:") followed by indented
+    // entries, each a name with its description beside or below it
+    let mut section = String::new();
+    for line in help.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let text = line.trim();
+        if text.is_empty() {
+            continue;
+        }
+        if indent == 0 {
+            if let Some(heading) = text.strip_suffix(':') {
+                section = heading.to_string();
+                let title = if section == "USAGE" { "SYNOPSIS" } else { heading };
+                out.push_str(&format!(".SH {}\n", title));
+            }
+            continue;
+        }
+        if section.is_empty() {
+            continue;
+        }
+        if section == "USAGE" {
+            out.push_str(&format!("{}\n", escape(text)));
+            continue;
+        }
+
+        let is_entry = indent <= 8 && (text.starts_with('-') || text.starts_with('<') || section == "SUBCOMMANDS");
+        if is_entry {
+            let (entry, description) = text.split_once("  ").unwrap_or((text, ""));
+            out.push_str(&format!(".TP\n\\fB{}\\fR\n", escape(entry.trim())));
+            if !description.trim().is_empty() {
+                out.push_str(&format!("{}\n", escape(description.trim())));
+            }
+        } else {
+            out.push_str(&format!("{}\n", escape(text)));
+        }
+    }
+
+    out.push_str(".SH ENVIRONMENT\n");
+    out.push_str("Every option can be set as an LLM_CONTEXT_GEN_ variable, e.g. LLM_CONTEXT_GEN_MAX_FILES=500; \
+flags take true or false. LLM_CONTEXT_GEN_CONFIG names a config file to use instead of .llm-context-gen.toml. \
+OPENAI_API_KEY and ANTHROPIC_API_KEY are used by embeddings and ask.\n");
+    out.push_str(".SH FILES\n");
+    out.push_str(".TP\n\\fB.llm-context-gen.toml\\fR\n");
+    out.push_str("Options for runs from this directory, e.g. max-files = 500. The command line wins over the \
+environment, which wins over this file.\n");
+    Ok(out)
+}
+
+/// Backslashes and hyphens are special in roff, as are dots and quotes starting a line
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
@@ -0,0 +1,193 @@
+// mcp.rs
+//! Model Context Protocol server: JSON-RPC 2.0 messages, one per line, on
+//! stdin and stdout. Every request regenerates the context so answers follow
+//! the working tree as it changes.
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::combined::{self, OutputFormat};
+use crate::tokens::BudgetBy;
+use crate::transform;
+use crate::tree::FileTree;
+use crate::SourceFile;
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Lines of context around each search match unless the caller asks otherwise
+const SEARCH_CONTEXT: usize = 2;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Files and tree of a fresh walk, sorted in output order
+pub type Snapshot = (Vec<SourceFile>, FileTree);
+
+pub fn serve(snapshot: impl Fn() -> io::Result<Snapshot>, format: OutputFormat, budget_by: BudgetBy) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(&message, &snapshot, format, budget_by),
+            Err(e) => Some(error(Value::Null, PARSE_ERROR, &format!("Parse error: {}", e))),
+        };
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// The response to a message, or None for notifications
+fn handle(message: &Value, snapshot: &impl Fn() -> io::Result<Snapshot>, format: OutputFormat, budget_by: BudgetBy) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let result = match message["method"].as_str().unwrap_or_default() {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {}, "resources": {} },
+            "serverInfo": { "name": "llm-context-gen", "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => call_tool(&params, snapshot, format, budget_by),
+        "resources/list" => list_resources(snapshot),
+        "resources/read" => read_resource(&params, snapshot),
+        method => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+    };
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error(id, code, &message),
+    })
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "get_file_tree",
+            "description": "The project's file tree with sizes, token estimates and why files were skipped",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "get_file",
+            "description": "The processed content of one file, by path relative to the project root",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "path": { "type": "string" } },
+                "required": ["path"],
+            },
+        },
+        {
+            "name": "search",
+            "description": "Lines matching a regular expression in every file, grouped by file",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string" },
+                    "context": { "type": "integer", "description": "Lines shown around each match" },
+                },
+                "required": ["pattern"],
+            },
+        },
+        {
+            "name": "get_context",
+            "description": "The combined context of the project, cut off at a token budget",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "budget": { "type": "integer", "description": "Maximum tokens" } },
+            },
+        },
+    ])
+}
+
+type RpcResult = Result<Value, (i64, String)>;
+
+fn call_tool(params: &Value, snapshot: &impl Fn() -> io::Result<Snapshot>, format: OutputFormat, budget_by: BudgetBy) -> RpcResult {
+    let arguments = &params["arguments"];
+    let text = match params["name"].as_str().unwrap_or_default() {
+        "get_file_tree" => snapshot().map(|(_, tree)| tree.render()),
+        "get_file" => {
+            let path = arguments["path"].as_str().ok_or((INVALID_PARAMS, "get_file requires a path".to_string()))?;
+            snapshot().and_then(|(files, _)| find_file(&files, path).map(|f| f.content.clone()))
+        }
+        "search" => {
+            let pattern = arguments["pattern"].as_str().ok_or((INVALID_PARAMS, "search requires a pattern".to_string()))?;
+            let pattern = Regex::new(pattern).map_err(|e| (INVALID_PARAMS, e.to_string()))?;
+            let context = arguments["context"].as_u64().map_or(SEARCH_CONTEXT, |c| c as usize);
+            snapshot().map(|(files, _)| search(&files, &pattern, context))
+        }
+        "get_context" => snapshot().and_then(|(mut files, mut tree)| {
+            if let Some(budget) = arguments["budget"].as_u64() {
+                crate::apply_token_budget(&mut files, &mut tree, budget as usize, budget_by);
+            }
+            combined::render(&files, format)
+        }),
+        name => return Err((INVALID_PARAMS, format!("Unknown tool '{}'", name))),
+    };
+    // Tool failures are reported to the model rather than as protocol errors
+    Ok(match text {
+        Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+        Err(e) => json!({ "content": [{ "type": "text", "text": e.to_string() }], "isError": true }),
+    })
+}
+
+pub fn find_file<'a>(files: &'a [SourceFile], path: &str) -> io::Result<&'a SourceFile> {
+    let path = Path::new(path.trim_start_matches("./"));
+    files.iter().find(|f| f.relative_path == path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("File '{}' is not in the context (missing, ignored or skipped)", path.display()),
+        )
+    })
+}
+
+/// Matching lines of every file that has a match, under its path
+pub fn search(files: &[SourceFile], pattern: &Regex, context: usize) -> String {
+    let mut out = String::new();
+    for file in files.iter().filter(|f| pattern.is_match(&f.content)) {
+        let lines = transform::matching_lines(&file.content, pattern, context);
+        out.push_str(&format!("{}\n{}\n\n", file.relative_path.display(), lines.trim_end()));
+    }
+    if out.is_empty() {
+        out.push_str("No matches");
+    }
+    out
+}
+
+/// The file tree plus one resource per file
+fn list_resources(snapshot: &impl Fn() -> io::Result<Snapshot>) -> RpcResult {
+    let (files, _) = snapshot().map_err(|e| (INTERNAL_ERROR, e.to_string()))?;
+    let mut resources = vec![json!({ "uri": "context://file-tree", "name": "file-tree.txt", "mimeType": "text/plain" })];
+    for file in &files {
+        let path = file.relative_path.to_string_lossy().replace('\\', "/");
+        resources.push(json!({ "uri": format!("context://file/{}", path), "name": path, "mimeType": "text/plain" }));
+    }
+    Ok(json!({ "resources": resources }))
+}
+
+fn read_resource(params: &Value, snapshot: &impl Fn() -> io::Result<Snapshot>) -> RpcResult {
+    let uri = params["uri"].as_str().ok_or((INVALID_PARAMS, "resources/read requires a uri".to_string()))?;
+    let (files, tree) = snapshot().map_err(|e| (INTERNAL_ERROR, e.to_string()))?;
+    let text = if uri == "context://file-tree" {
+        tree.render()
+    } else if let Some(path) = uri.strip_prefix("context://file/") {
+        find_file(&files, path).map_err(|e| (INVALID_PARAMS, e.to_string()))?.content.clone()
+    } else {
+        return Err((INVALID_PARAMS, format!("Unknown resource '{}'", uri)));
+    };
+    Ok(json!({ "contents": [{ "uri": uri, "mimeType": "text/plain", "text": text }] }))
+}
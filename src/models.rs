@@ -0,0 +1,136 @@
+// models.rs
+use std::collections::HashMap;
+
+/// Built-in information about a hosted model
+pub struct ModelInfo {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Input price in USD per million tokens
+    pub input_price: f64,
+    /// Context window in tokens
+    pub context_window: usize,
+    /// Average characters per token of the model's tokenizer on source code
+    pub chars_per_token: f64,
+}
+
+impl ModelInfo {
+    /// Default token budget, leaving room in the window for the question and answer
+    pub fn token_budget(&self) -> usize {
+        self.context_window / 5 * 4
+    }
+}
+
+pub static MODELS: &[ModelInfo] = &[
+    ModelInfo {
+        name: "gpt-4o",
+        aliases: &[],
+        input_price: 2.50,
+        context_window: 128_000,
+        chars_per_token: 4.0,
+    },
+    ModelInfo {
+        name: "gpt-4o-mini",
+        aliases: &[],
+        input_price: 0.15,
+        context_window: 128_000,
+        chars_per_token: 4.0,
+    },
+    ModelInfo {
+        name: "gpt-4.1",
+        aliases: &[],
+        input_price: 2.00,
+        context_window: 1_047_576,
+        chars_per_token: 4.0,
+    },
+    ModelInfo {
+        name: "claude-3-7-sonnet",
+        aliases: &["claude-3-7", "claude-sonnet"],
+        input_price: 3.00,
+        context_window: 200_000,
+        chars_per_token: 3.5,
+    },
+    ModelInfo {
+        name: "claude-3-5-haiku",
+        aliases: &["claude-haiku"],
+        input_price: 0.80,
+        context_window: 200_000,
+        chars_per_token: 3.5,
+    },
+    ModelInfo {
+        name: "claude-3-opus",
+        aliases: &["claude-opus"],
+        input_price: 15.00,
+        context_window: 200_000,
+        chars_per_token: 3.5,
+    },
+    ModelInfo {
+        name: "gemini-1.5-pro",
+        aliases: &["gemini-pro"],
+        input_price: 1.25,
+        context_window: 2_000_000,
+        chars_per_token: 4.0,
+    },
+    ModelInfo {
+        name: "gemini-1.5-flash",
+        aliases: &["gemini-flash"],
+        input_price: 0.075,
+        context_window: 1_000_000,
+        chars_per_token: 4.0,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static ModelInfo> {
+    let name = name.trim().to_lowercase();
+    MODELS
+        .iter()
+        .find(|m| m.name == name || m.aliases.contains(&name.as_str()))
+}
+
+/// Input prices per million tokens: the built-in table overridden by
+/// `model=price` pairs (comma-separated)
+pub fn pricing_table(overrides: &str) -> Result<HashMap<String, f64>, String> {
+    let mut table: HashMap<String, f64> = MODELS
+        .iter()
+        .map(|m| (m.name.to_string(), m.input_price))
+        .collect();
+
+    for pair in overrides.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (model, price) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid pricing entry '{}' (expected model=price)", pair))?;
+        let price: f64 = price
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid price in pricing entry '{}'", pair))?;
+        table.insert(canonical_name(model), price);
+    }
+
+    Ok(table)
+}
+
+/// Estimated input cost in USD for each requested model
+pub fn estimate_costs(
+    models: &str,
+    tokens: usize,
+    pricing: &HashMap<String, f64>,
+) -> Vec<(String, Option<f64>)> {
+    models
+        .split(',')
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .map(|model| {
+            let name = canonical_name(model);
+            let cost = pricing
+                .get(&name)
+                .map(|price| tokens as f64 / 1_000_000.0 * price);
+            (name, cost)
+        })
+        .collect()
+}
+
+fn canonical_name(model: &str) -> String {
+    match find(model) {
+        Some(info) => info.name.to_string(),
+        None => model.trim().to_lowercase(),
+    }
+}
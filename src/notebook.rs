@@ -0,0 +1,38 @@
+// notebook.rs
+use serde_json::Value;
+
+pub fn is_notebook(file_name: &str) -> bool {
+    file_name.to_lowercase().ends_with(".ipynb")
+}
+
+/// Flatten a Jupyter notebook into its code and markdown cells (percent format),
+/// dropping outputs and execution metadata. Returns None if the JSON can't be parsed.
+pub fn flatten(content: &str) -> Option<String> {
+    let notebook: Value = serde_json::from_str(content).ok()?;
+    let cells = notebook.get("cells")?.as_array()?;
+
+    let mut out = String::new();
+    for cell in cells {
+        let source = cell_source(cell);
+        if source.trim().is_empty() {
+            continue;
+        }
+        match cell.get("cell_type").and_then(Value::as_str) {
+            Some("code") => out.push_str("# %%\n"),
+            Some("markdown") => out.push_str("# %% [markdown]\n"),
+            _ => continue,
+        }
+        out.push_str(source.trim_end());
+        out.push_str("\n\n");
+    }
+    Some(out)
+}
+
+/// Cell sources are either a string or a list of lines
+fn cell_source(cell: &Value) -> String {
+    match cell.get("source") {
+        Some(Value::String(source)) => source.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
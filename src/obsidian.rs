@@ -0,0 +1,135 @@
+// obsidian.rs
+//! `--format obsidian`: a vault of markdown notes, one per file with its path,
+//! language and tags in the frontmatter and wikilinks to the files it imports
+//! and is imported by. Logseq reads the same layout.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::combined::code_fence;
+use crate::imports::ImportResolver;
+use crate::language;
+use crate::paths;
+use crate::SourceFile;
+
+/// Write the vault into `dir`: the notes in the project's own directory
+/// layout, plus an index.md linking to all of them
+pub fn write_vault(files: &[SourceFile], dir: &Path) -> io::Result<()> {
+    let imports = ImportResolver::new().graph(files);
+    let mut imported_by: BTreeMap<&PathBuf, BTreeSet<&PathBuf>> = BTreeMap::new();
+    for (file, targets) in &imports {
+        for target in targets {
+            imported_by.entry(target).or_default().insert(file);
+        }
+    }
+
+    for file in files {
+        let path = dir.join(note_path(&file.relative_path));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let imports = imports.get(&file.relative_path).into_iter().flatten();
+        let imported_by = imported_by.get(&file.relative_path).into_iter().flatten().copied();
+        fs::write(paths::long_path(&path), note(file, imports, imported_by))?;
+    }
+    fs::write(dir.join("index.md"), index(files))
+}
+
+fn note<'a>(
+    file: &SourceFile,
+    imports: impl Iterator<Item = &'a PathBuf>,
+    imported_by: impl Iterator<Item = &'a PathBuf>,
+) -> String {
+    let path = display(&file.relative_path);
+    let language = language::of(file).map(|l| l.name);
+    let mut out = String::from("---\n");
+    let _ = writeln!(out, "path: \"{}\"", path.replace('"', "\\\""));
+    if let Some(language) = language {
+        let _ = writeln!(out, "language: {}", language);
+    }
+    let _ = writeln!(out, "tokens: {}", file.tokens);
+    let tags = tags(&file.relative_path, language);
+    if !tags.is_empty() {
+        let _ = writeln!(out, "tags: [{}]", tags.join(", "));
+    }
+    out.push_str("---\n\n");
+
+    let name = file.relative_path.file_name().unwrap_or_default().to_string_lossy();
+    let _ = writeln!(out, "# {}\n", name);
+    let imports: Vec<String> = imports.map(|p| link(p)).collect();
+    if !imports.is_empty() {
+        let _ = writeln!(out, "Imports: {}\n", imports.join(", "));
+    }
+    let imported_by: Vec<String> = imported_by.map(|p| link(p)).collect();
+    if !imported_by.is_empty() {
+        let _ = writeln!(out, "Imported by: {}\n", imported_by.join(", "));
+    }
+
+    let fence = code_fence(&file.content);
+    let _ = writeln!(out, "{}{}", fence, language::fence_language(file));
+    out.push_str(&file.content);
+    if !file.content.ends_with('\n') {
+        out.push('\n');
+    }
+    let _ = writeln!(out, "{}", fence);
+    out
+}
+
+/// The home note: every file, grouped by directory
+fn index(files: &[SourceFile]) -> String {
+    let mut by_dir: BTreeMap<String, Vec<&SourceFile>> = BTreeMap::new();
+    for file in files {
+        let dir = file.relative_path.parent().map(display).unwrap_or_default();
+        by_dir.entry(dir).or_default().push(file);
+    }
+    let tokens: usize = files.iter().map(|f| f.tokens).sum();
+    let mut out = format!("# Index\n\n{} files, ~{} tokens\n", files.len(), tokens);
+    for (dir, dir_files) in by_dir {
+        let _ = writeln!(out, "\n## {}\n", if dir.is_empty() { "/" } else { &dir });
+        for file in dir_files {
+            let _ = writeln!(out, "- {}", link(&file.relative_path));
+        }
+    }
+    out
+}
+
+/// Tags for the language and the top-level directory, in Obsidian's tag syntax
+fn tags(path: &Path, language: Option<&str>) -> Vec<String> {
+    let tag = |text: &str| -> String {
+        text.to_lowercase().chars().map(|c| if c.is_alphanumeric() || c == '_' || c == '-' { c } else { '-' }).collect()
+    };
+    let mut tags: Vec<String> = language.map(tag).into_iter().collect();
+    let mut components = path.components();
+    if let (Some(top), Some(_)) = (components.next(), components.next()) {
+        let top = tag(&top.as_os_str().to_string_lossy());
+        if !top.trim_matches('-').is_empty() && !tags.contains(&top) {
+            tags.push(top);
+        }
+    }
+    tags
+}
+
+/// `src/main.rs` is the note `src/main.rs.md`
+fn note_path(relative_path: &Path) -> PathBuf {
+    let mut path: PathBuf = relative_path
+        .components()
+        .map(|c| paths::safe_file_name(&c.as_os_str().to_string_lossy()))
+        .collect();
+    let name = format!("{}.md", path.file_name().unwrap_or_default().to_string_lossy());
+    path.set_file_name(name);
+    path
+}
+
+/// A wikilink by the note's full path (`main.py` alone would be taken for an
+/// attachment), shown as the file name
+fn link(relative_path: &Path) -> String {
+    let target = display(&note_path(relative_path));
+    let name = relative_path.file_name().unwrap_or_default().to_string_lossy();
+    format!("[[{}|{}]]", target, name)
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
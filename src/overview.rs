@@ -0,0 +1,276 @@
+// overview.rs
+//! `overview.md`: an orientation page put together from heuristics (project
+//! type, frameworks, entry points, what each top-level directory is for), for
+//! the model to read before the files themselves.
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::language;
+use crate::manifests::{self, Manifest};
+use crate::SourceFile;
+
+/// Dependencies that tell what kind of project this is, and the framework's name
+const FRAMEWORKS: &[(&str, &str)] = &[
+    ("actix-web", "Actix Web"),
+    ("axum", "Axum"),
+    ("rocket", "Rocket"),
+    ("warp", "Warp"),
+    ("tokio", "Tokio"),
+    ("tauri", "Tauri"),
+    ("bevy", "Bevy"),
+    ("leptos", "Leptos"),
+    ("clap", "clap"),
+    ("structopt", "structopt"),
+    ("next", "Next.js"),
+    ("react", "React"),
+    ("vue", "Vue"),
+    ("nuxt", "Nuxt"),
+    ("svelte", "Svelte"),
+    ("@sveltejs/kit", "SvelteKit"),
+    ("@angular/core", "Angular"),
+    ("express", "Express"),
+    ("fastify", "Fastify"),
+    ("@nestjs/core", "NestJS"),
+    ("electron", "Electron"),
+    ("django", "Django"),
+    ("flask", "Flask"),
+    ("fastapi", "FastAPI"),
+    ("torch", "PyTorch"),
+    ("tensorflow", "TensorFlow"),
+    ("github.com/gin-gonic/gin", "Gin"),
+    ("github.com/labstack/echo/v4", "Echo"),
+    ("github.com/gofiber/fiber/v2", "Fiber"),
+    ("github.com/spf13/cobra", "Cobra"),
+];
+
+/// Files that start a program or make up a library's public face
+const ENTRY_FILES: &[&str] = &[
+    "lib.rs",
+    "index.js",
+    "index.ts",
+    "index.jsx",
+    "index.tsx",
+    "index.mjs",
+    "server.js",
+    "server.ts",
+    "app.py",
+    "manage.py",
+    "__main__.py",
+    "wsgi.py",
+    "asgi.py",
+];
+
+/// Code that shows a file defines a program's main function
+const MAIN_MARKERS: &[(&str, &str)] = &[
+    ("rs", "fn main("),
+    ("go", "func main("),
+    ("py", "if __name__ == \"__main__\""),
+    ("py", "if __name__ == '__main__'"),
+    ("java", "public static void main("),
+    ("kt", "fun main("),
+    ("c", "int main("),
+    ("cpp", "int main("),
+];
+
+/// Files read first for a directory's description
+const DESCRIBING_FILES: &[&str] = &["README.md", "README", "README.txt", "mod.rs", "lib.rs", "main.rs", "__init__.py", "doc.go"];
+
+/// The overview of what was collected; `project_types` are the detected presets
+pub fn render(files: &[SourceFile], project_types: &[&str]) -> String {
+    let manifests = manifests::collect(files);
+    let mut out = String::from("# Project overview\n\n");
+    out.push_str("Put together from file names, manifests and comments; the files themselves have the details.\n\n");
+
+    let tokens: usize = files.iter().map(|f| f.tokens).sum();
+    let _ = writeln!(out, "- Files: {} (~{} tokens)", files.len(), tokens);
+    if !project_types.is_empty() {
+        let _ = writeln!(out, "- Project type: {}", project_types.join(", "));
+    }
+    let frameworks = frameworks(&manifests);
+    if !frameworks.is_empty() {
+        let _ = writeln!(out, "- Frameworks and key libraries: {}", frameworks.join(", "));
+    }
+
+    let entry_points: Vec<&SourceFile> = files.iter().filter(|f| is_entry_point(f)).collect();
+    if !entry_points.is_empty() {
+        out.push_str("\n## Entry points\n\n");
+        for file in entry_points {
+            let _ = writeln!(out, "- `{}`", display(&file.relative_path));
+        }
+    }
+
+    // Directories at the top, with the files in each
+    let mut dirs: BTreeMap<PathBuf, Vec<&SourceFile>> = BTreeMap::new();
+    for file in files {
+        let mut components = file.relative_path.components();
+        if let (Some(first), Some(_)) = (components.next(), components.next()) {
+            dirs.entry(PathBuf::from(first.as_os_str())).or_default().push(file);
+        }
+    }
+    if !dirs.is_empty() {
+        out.push_str("\n## Directories\n\n");
+        for (dir, dir_files) in &dirs {
+            let tokens: usize = dir_files.iter().map(|f| f.tokens).sum();
+            let _ = write!(out, "- `{}/` ({} files, ~{} tokens)", display(dir), dir_files.len(), tokens);
+            match describe(dir, dir_files, &manifests) {
+                Some(description) => {
+                    let _ = writeln!(out, ": {}", description);
+                }
+                None => out.push('\n'),
+            }
+        }
+    }
+
+    if !manifests.is_empty() {
+        out.push_str("\n## Dependencies\n\n");
+        for manifest in &manifests {
+            let name = manifest.name.as_deref().map(|n| format!(" ({})", n)).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "- `{}`{}: {} {} dependencies, {} development",
+                display(&manifest.path),
+                name,
+                manifest.dependencies.len(),
+                manifest.kind,
+                manifest.dev_dependencies.len()
+            );
+        }
+    }
+    out
+}
+
+/// Known frameworks among all direct dependencies, in table order, with the
+/// version the first manifest using them asks for
+fn frameworks(manifests: &[Manifest]) -> Vec<String> {
+    FRAMEWORKS
+        .iter()
+        .filter_map(|(dependency, name)| {
+            let found = manifests
+                .iter()
+                .flat_map(|m| m.dependencies.iter().chain(&m.dev_dependencies))
+                .find(|d| d.name.eq_ignore_ascii_case(dependency))?;
+            Some(format!("{} {}", name, found.version))
+        })
+        .collect()
+}
+
+fn is_entry_point(file: &SourceFile) -> bool {
+    let name = file.relative_path.file_name().unwrap_or_default().to_string_lossy();
+    // index.js deep in a tree is a module's, not the project's
+    if ENTRY_FILES.contains(&name.as_ref()) && file.relative_path.components().count() <= 3 {
+        return true;
+    }
+    let extension = file.relative_path.extension().unwrap_or_default().to_string_lossy();
+    MAIN_MARKERS
+        .iter()
+        .filter(|(ext, _)| *ext == extension)
+        .any(|(_, marker)| file.content.lines().any(|line| line.trim_start().starts_with(marker)))
+}
+
+/// A line about the directory: the first paragraph of its README, its
+/// manifest's description, or the doc comment opening its most telling file,
+/// looking into subdirectories (e.g. src/*/) if the directory itself has none
+fn describe(dir: &Path, files: &[&SourceFile], manifests: &[Manifest]) -> Option<String> {
+    let rank = |file: &&&SourceFile| {
+        let name = file.relative_path.file_name().unwrap_or_default().to_string_lossy();
+        DESCRIBING_FILES.iter().position(|d| *d == name)
+    };
+    let mut candidates: Vec<&&SourceFile> = files.iter().filter(|f| rank(f).is_some()).collect();
+    candidates.sort_by_key(|f| (f.relative_path.components().count(), rank(f)));
+
+    let depth = |path: &Path| path.components().count();
+    let manifest_description = |level: usize| {
+        manifests
+            .iter()
+            .filter(|m| m.path.starts_with(dir) && depth(&m.path) == level)
+            .find_map(|m| m.description.as_deref().map(sentence))
+    };
+    for level in depth(dir) + 1..=depth(dir) + 2 {
+        let at_level = candidates.iter().filter(|f| depth(&f.relative_path) == level);
+        let found = at_level
+            .clone()
+            .filter(|f| is_readme(&f.relative_path))
+            .find_map(|f| readme_summary(&f.content))
+            .or_else(|| manifest_description(level))
+            .or_else(|| at_level.filter(|f| !is_readme(&f.relative_path)).find_map(|f| doc_comment(&f.relative_path, &f.content)));
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+fn is_readme(path: &Path) -> bool {
+    path.file_stem().is_some_and(|s| s.eq_ignore_ascii_case("readme"))
+}
+
+/// The first line of prose, skipping headings, badges and HTML
+fn readme_summary(content: &str) -> Option<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with(['#', '!', '<', '[', '=', '-', '`', '|']))
+        .map(sentence)
+}
+
+/// The comment or docstring that opens a file, unless it only names the file
+/// or carries a license
+fn doc_comment(path: &Path, content: &str) -> Option<String> {
+    let language = language::detect(path)?;
+    // Doc comments (`//!`, `/**`) before the plain comments they start with
+    let mut openers: Vec<&str> = Vec::new();
+    if language.line_comments.contains(&"//") {
+        openers.extend(["//!", "///"]);
+    }
+    let c_blocks = language.block_comments.iter().any(|(start, _)| *start == "/*");
+    if c_blocks {
+        openers.push("/**");
+    }
+    openers.extend(language.line_comments);
+    openers.extend(language.block_comments.iter().map(|(start, _)| *start));
+    // The middle lines of a /* ... */ block
+    if c_blocks {
+        openers.push("*");
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut comment: Vec<&str> = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with("#!") || (line.is_empty() && comment.is_empty()) {
+            continue;
+        }
+        let Some(opener) = openers.iter().find(|o| line.starts_with(**o)) else { break };
+        let mut text = line[opener.len()..].trim();
+        for (_, end) in language.block_comments {
+            text = text.trim_end_matches(end).trim();
+        }
+        let boilerplate = text == file_name || text.contains("Copyright") || text.contains("SPDX-License");
+        if text.is_empty() || boilerplate {
+            // The paragraph so far is the description
+            if !comment.is_empty() {
+                break;
+            }
+            continue;
+        }
+        comment.push(text);
+    }
+    (!comment.is_empty()).then(|| sentence(&comment.join(" ")))
+}
+
+/// Up to the end of the first sentence, at most about a line long
+fn sentence(text: &str) -> String {
+    let text = match text.find(". ") {
+        Some(end) => &text[..=end],
+        None => text,
+    };
+    if text.chars().count() > 120 {
+        format!("{}...", text.chars().take(117).collect::<String>().trim_end())
+    } else {
+        text.trim().to_string()
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
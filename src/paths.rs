@@ -0,0 +1,84 @@
+// paths.rs
+use std::path::{Path, PathBuf};
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Longest output file name, leaving room for the extension
+const MAX_NAME_CHARS: usize = 150;
+
+/// Device names Windows reserves in every directory, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// The path as it appears in outputs. Names are NFC-normalized, so a file
+/// checked out on macOS (which decomposes accents) and elsewhere gets the same
+/// entry, and names that aren't valid UTF-8 have the invalid bytes replaced.
+pub fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy().nfc().collect::<String>())
+        .collect()
+}
+
+/// The output directory as paths from walking `root` spell it (`./llm-context`
+/// for `-o llm-context` under `.`), or None when it isn't under the root or
+/// doesn't exist yet. Both are canonicalized first, so any spelling of either matches.
+pub fn output_under(root: &Path, output: &Path) -> Option<PathBuf> {
+    let inside = output.canonicalize().ok()?.strip_prefix(root.canonicalize().ok()?).ok()?.to_path_buf();
+    Some(root.join(inside))
+}
+
+/// A single file name that can be created on Windows, macOS and Linux:
+/// separators and characters Windows rejects become `_`, trailing dots and
+/// spaces are dropped and reserved device names get a `_` prefix
+pub fn safe_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_NAME_CHARS)
+        .collect();
+    let name = name.trim_end_matches(['.', ' ']);
+    if name.is_empty() {
+        return "_".to_string();
+    }
+
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    if RESERVED_NAMES.iter().any(|reserved| stem.eq_ignore_ascii_case(reserved)) {
+        format!("_{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// The path to use when writing outputs. Windows refuses paths longer than
+/// MAX_PATH (260 characters) unless they use the `\\?\` extended-length form,
+/// which deeply nested projects easily exceed; elsewhere the path is unchanged.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> PathBuf {
+    // Files may have 260 characters but directories only 248
+    const MAX_PATH: usize = 248;
+
+    let absolute = match std::path::absolute(path) {
+        Ok(absolute) => absolute,
+        Err(_) => return path.to_path_buf(),
+    };
+    let text = absolute.to_string_lossy();
+    if text.len() < MAX_PATH || text.starts_with(r"\\?\") {
+        return path.to_path_buf();
+    }
+    match text.strip_prefix(r"\\") {
+        // Network shares use the \\?\UNC\server\share form
+        Some(share) => PathBuf::from(format!(r"\\?\UNC\{}", share)),
+        None => PathBuf::from(format!(r"\\?\{}", text)),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
@@ -0,0 +1,221 @@
+// picker.rs
+//! Terminal file picker for `--interactive`: a tree with checkboxes, live
+//! token totals and fuzzy search over paths.
+use std::collections::HashSet;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::SourceFile;
+
+/// A directory or file line of the tree
+struct Entry {
+    path: PathBuf,
+    depth: usize,
+    name: String,
+    /// Index into the files, for file entries
+    file: Option<usize>,
+}
+
+struct Picker<'a> {
+    files: &'a [SourceFile],
+    entries: Vec<Entry>,
+    selected: Vec<bool>,
+    /// Typed while searching; searching shows matching files only
+    query: String,
+    searching: bool,
+    list: ListState,
+}
+
+/// Let the user pick files, all selected to begin with. Returns which files
+/// were picked, or None if the picker was cancelled.
+pub fn pick(files: &[SourceFile]) -> io::Result<Option<Vec<bool>>> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "--interactive needs a terminal"));
+    }
+    let mut picker = Picker::new(files);
+    let mut terminal = ratatui::init();
+    let result = picker.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl<'a> Picker<'a> {
+    fn new(files: &'a [SourceFile]) -> Self {
+        let mut order: Vec<usize> = (0..files.len()).collect();
+        order.sort_by(|&a, &b| files[a].relative_path.cmp(&files[b].relative_path));
+
+        // Directories are listed once, before the first file inside them
+        let mut entries = Vec::new();
+        let mut listed_dirs = HashSet::new();
+        for i in order {
+            let path = &files[i].relative_path;
+            let mut dir = PathBuf::new();
+            let components: Vec<_> = path.components().collect();
+            for (depth, component) in components.iter().enumerate() {
+                let name = component.as_os_str().to_string_lossy().into_owned();
+                dir.push(component);
+                if depth + 1 == components.len() {
+                    entries.push(Entry { path: path.clone(), depth, name, file: Some(i) });
+                } else if listed_dirs.insert(dir.clone()) {
+                    entries.push(Entry { path: dir.clone(), depth, name: format!("{}/", name), file: None });
+                }
+            }
+        }
+
+        let mut list = ListState::default();
+        list.select(Some(0));
+        Picker { files, entries, selected: vec![true; files.len()], query: String::new(), searching: false, list }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<Option<Vec<bool>>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if let Some(done) = self.handle(key) {
+                    return Ok(done.then(|| self.selected.clone()));
+                }
+            }
+        }
+    }
+
+    /// Entries on screen: the whole tree, or the files matching the search
+    fn visible(&self) -> Vec<&Entry> {
+        if self.query.is_empty() {
+            return self.entries.iter().collect();
+        }
+        self.entries
+            .iter()
+            .filter(|e| e.file.is_some() && fuzzy_match(&self.query, &e.path.to_string_lossy()))
+            .collect()
+    }
+
+    /// Some(true) to confirm, Some(false) to cancel
+    fn handle(&mut self, key: KeyEvent) -> Option<bool> {
+        let count = self.visible().len();
+        let current = self.list.selected().unwrap_or(0);
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(false);
+        }
+        if self.searching {
+            match key.code {
+                KeyCode::Char(c) => self.query.push(c),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Esc => {
+                    self.query.clear();
+                    self.searching = false;
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Up | KeyCode::Down => {
+                    self.searching = false;
+                    return self.handle(key);
+                }
+                _ => {}
+            }
+            self.list.select(Some(0));
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Enter => return Some(true),
+            KeyCode::Esc | KeyCode::Char('q') => return Some(false),
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Up | KeyCode::Char('k') => self.list.select(Some(current.saturating_sub(1))),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select(Some((current + 1).min(count.saturating_sub(1)))),
+            KeyCode::PageUp => self.list.select(Some(current.saturating_sub(20))),
+            KeyCode::PageDown => self.list.select(Some((current + 20).min(count.saturating_sub(1)))),
+            KeyCode::Char(' ') => {
+                if let Some(path) = self.visible().get(current).map(|e| e.path.clone()) {
+                    self.toggle(&path);
+                }
+            }
+            KeyCode::Char('a') => {
+                // Everything shown, so "a" after a search picks just the matches
+                let shown: Vec<usize> = self.visible().iter().filter_map(|e| e.file).collect();
+                let select = shown.iter().any(|&i| !self.selected[i]);
+                for i in shown {
+                    self.selected[i] = select;
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// A file flips; a directory selects everything in it unless it already is
+    fn toggle(&mut self, path: &Path) {
+        let inside: Vec<usize> = self.files_under(path).collect();
+        let select = inside.iter().any(|&i| !self.selected[i]);
+        for i in inside {
+            self.selected[i] = select;
+        }
+    }
+
+    fn files_under<'p>(&'p self, path: &'p Path) -> impl Iterator<Item = usize> + 'p {
+        self.files.iter().enumerate().filter(move |(_, f)| f.relative_path.starts_with(path)).map(|(i, _)| i)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        let picked = self.selected.iter().filter(|s| **s).count();
+        let tokens: usize = self.files.iter().zip(&self.selected).filter(|(_, s)| **s).map(|(f, _)| f.tokens).sum();
+        let total: usize = self.files.iter().map(|f| f.tokens).sum();
+        frame.render_widget(
+            Paragraph::new(format!(
+                "Selected {} of {} files, ~{} of ~{} tokens",
+                picked,
+                self.files.len(),
+                tokens,
+                total
+            ))
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+            header,
+        );
+
+        let searching = !self.query.is_empty();
+        let items: Vec<ListItem> = self
+            .visible()
+            .iter()
+            .map(|entry| {
+                let inside: Vec<usize> = self.files_under(&entry.path).collect();
+                let picked = inside.iter().filter(|&&i| self.selected[i]).count();
+                let tokens: usize = inside.iter().filter(|&&i| self.selected[i]).map(|&i| self.files[i].tokens).sum();
+                let check = if picked == inside.len() { "[x]" } else if picked == 0 { "[ ]" } else { "[-]" };
+                let (indent, name) = if searching {
+                    (String::new(), entry.path.to_string_lossy().into_owned())
+                } else {
+                    ("  ".repeat(entry.depth), entry.name.clone())
+                };
+                ListItem::new(Line::from(format!("{}{} {} (~{} tokens)", indent, check, name, tokens)))
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, body, &mut self.list);
+
+        let help = if self.searching {
+            format!("/{}  (Enter to keep the filter, Esc to clear it)", self.query)
+        } else {
+            "Space pick  a pick all shown  / search  Enter generate  q cancel".to_string()
+        };
+        frame.render_widget(Paragraph::new(help), footer);
+    }
+}
+
+/// Whether the query's characters appear in the path in order, ignoring case
+fn fuzzy_match(query: &str, path: &str) -> bool {
+    let mut chars = path.chars().flat_map(char::to_lowercase);
+    query.chars().flat_map(char::to_lowercase).all(|q| chars.any(|c| c == q))
+}
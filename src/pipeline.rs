@@ -0,0 +1,119 @@
+// pipeline.rs
+//! From a list of paths to the context, without the command line's walk,
+//! presets and output files: what a wasm32 build runs over a `vfs::Memory`.
+//! Each file is checked, decoded, redacted and counted the same way the
+//! command line does it, and rendered like combined.txt.
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::binary;
+use crate::encoding;
+use crate::error::ContextGenError;
+use crate::generated;
+use crate::secrets::SecretScanner;
+use crate::tokens;
+use crate::tree::FileTree;
+use crate::vfs::FileSystem;
+use crate::SourceFile;
+
+/// The command line's defaults for what it has options for
+pub struct Options {
+    /// Files over this many bytes are skipped
+    pub max_size: u64,
+    pub chars_per_token: f64,
+    pub redact_secrets: bool,
+    /// Keep minified bundles and files marked as generated
+    pub include_generated: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { max_size: 500_000, chars_per_token: tokens::DEFAULT_CHARS_PER_TOKEN, redact_secrets: true, include_generated: false }
+    }
+}
+
+/// The files that made it in, in the order given, and the tree of everything
+pub struct Context {
+    pub files: Vec<SourceFile>,
+    pub tree: FileTree,
+    /// Files that couldn't be read, which are also marked in the tree
+    pub errors: Vec<ContextGenError>,
+}
+
+impl Context {
+    /// The tree followed by every file, as file-tree.txt and combined.txt would have them
+    pub fn render(&self) -> String {
+        let mut out = self.tree.render().into_bytes();
+        out.push(b'\n');
+        for file in &self.files {
+            // Writing to a Vec can't fail
+            let _ = write_section(&mut out, file);
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+}
+
+/// Load `paths` from `fs`, skipping binaries, files that are too large and
+/// generated code, with the reason in the tree
+pub fn generate(fs: &dyn FileSystem, paths: &[PathBuf], options: &Options) -> Context {
+    let scanner = options.redact_secrets.then(SecretScanner::new);
+    let mut tree = FileTree::new(false);
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        let read = fs.metadata(path).and_then(|metadata| {
+            if metadata.len > options.max_size {
+                return Ok(None);
+            }
+            fs.read(path).map(|bytes| Some((metadata, bytes)))
+        });
+        let (metadata, bytes) = match read {
+            Ok(Some(read)) => read,
+            Ok(None) => {
+                tree.add_skipped(path, "too large");
+                continue;
+            }
+            Err(source) => {
+                errors.push(ContextGenError::Read { path: path.clone(), source });
+                tree.add_skipped(path, "error reading");
+                continue;
+            }
+        };
+        if binary::looks_binary(path, &bytes[..bytes.len().min(binary::SNIFF_BYTES)]) {
+            tree.add_skipped(path, "binary");
+            continue;
+        }
+
+        let (mut content, _) = encoding::decode(&bytes);
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !options.include_generated {
+            if let Some(reason) = generated::detect(&file_name, &content) {
+                tree.add_skipped(path, reason);
+                continue;
+            }
+        }
+        let mut redactions = Vec::new();
+        if let Some(scanner) = &scanner {
+            (content, redactions) = scanner.redact(&content);
+        }
+
+        let tokens = tokens::estimate_tokens(&content, options.chars_per_token);
+        tree.add_file(path, metadata.len, tokens);
+        files.push(SourceFile { relative_path: path.clone(), size: metadata.len, modified: metadata.modified, tokens, content, redactions });
+    }
+    Context { files, tree, errors }
+}
+
+/// One file's part of the plain text context: a header with its path, then the content
+pub fn write_section(out: &mut impl Write, file: &SourceFile) -> io::Result<()> {
+    let separator = "=".repeat(64);
+    writeln!(out, "{}", separator)?;
+    writeln!(out, "File: {}", file.relative_path.display())?;
+    writeln!(out, "{}", separator)?;
+    writeln!(out)?;
+    write!(out, "{}", file.content)?;
+    if !file.content.ends_with('\n') {
+        writeln!(out)?;
+    }
+    writeln!(out)
+}
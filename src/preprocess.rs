@@ -0,0 +1,93 @@
+// preprocess.rs
+//! `--preprocess "*.ipynb=jupyter nbconvert --to script {file} --stdout"`, or a
+//! `[preprocess]` table in the config file: files matching the glob are run
+//! through the command and its output is included instead of the file.
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use globset::{Glob, GlobMatcher};
+
+/// Placeholder for the file's path; without it the content is piped to stdin
+const FILE_PLACEHOLDER: &str = "{file}";
+
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessors {
+    rules: Vec<(GlobMatcher, String)>,
+}
+
+impl Preprocessors {
+    /// Rules as `GLOB=COMMAND`, the first matching one applies
+    pub fn new(rules: &[String]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let (glob, command) = rule.split_once('=').ok_or_else(|| format!("Invalid preprocessor '{}' (expected GLOB=COMMAND)", rule))?;
+                let glob = Glob::new(glob.trim()).map_err(|e| format!("Invalid preprocessor glob '{}': {}", glob.trim(), e))?;
+                Ok((glob.compile_matcher(), command.trim().to_string()))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Preprocessors { rules })
+    }
+
+    /// The command for a file, by its path in the output
+    pub fn command_for(&self, relative_path: &Path) -> Option<&str> {
+        self.rules.iter().find(|(glob, _)| glob.is_match(relative_path)).map(|(_, command)| command.as_str())
+    }
+}
+
+/// Run `command` on the file at `path` and return what it prints
+pub fn run(command: &str, path: &Path) -> Result<Vec<u8>, String> {
+    let piped = !command.contains(FILE_PLACEHOLDER);
+    let command_line = command.replace(FILE_PLACEHOLDER, &quote(&path.to_string_lossy()));
+    let mut child = shell(&command_line)
+        .stdin(if piped { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("couldn't run: {}", e))?;
+
+    // Written from another thread, a command that prints as it reads would fill its pipe
+    let writer = child.stdin.take().map(|mut stdin| {
+        let content = std::fs::read(path);
+        thread::spawn(move || -> io::Result<()> { stdin.write_all(&content?) })
+    });
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if let Some(Ok(Err(e))) = writer.map(|w| w.join()) {
+        // A command that doesn't read all of stdin closes the pipe early, that's fine
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return Err(e.to_string());
+        }
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("no output").trim();
+        return Err(format!("{}: {}", output.status, reason));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(windows)]
+fn shell(command_line: &str) -> Command {
+    let mut command = Command::new("cmd");
+    command.args(["/C", command_line]);
+    command
+}
+
+#[cfg(not(windows))]
+fn shell(command_line: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.args(["-c", command_line]);
+    command
+}
+
+#[cfg(windows)]
+fn quote(path: &str) -> String {
+    format!("\"{}\"", path)
+}
+
+#[cfg(not(windows))]
+fn quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', "'\\''"))
+}
@@ -0,0 +1,216 @@
+// profiles.rs
+//! Per-ecosystem defaults for `--preset`: which directories to skip, which
+//! files to put first and which extensions are never worth including.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use toml::Value;
+
+use crate::roots::Root;
+
+/// Skipped whatever the ecosystem: editor, version control and tool cache
+/// directories (a Python tool run once leaves its cache in any repository), and
+/// the build and dependency directories always skipped before presets existed,
+/// since a Rust project with a web/ frontend still has a node_modules
+const COMMON_IGNORES: &[&str] = &[
+    ".git", ".idea", ".vscode", ".venv", ".tox", ".nox", ".mypy_cache", ".pytest_cache", ".ruff_cache",
+    "node_modules", "target", "dist", "build", "__pycache__", ".next", "out", "coverage", ".vercel", ".turbo",
+];
+
+struct Builtin {
+    name: &'static str,
+    ignore: &'static [&'static str],
+    priority: &'static [&'static str],
+    exclude_extensions: &'static [&'static str],
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "rust",
+        ignore: &["target"],
+        priority: &["README.md", "Cargo.toml", "src/main.rs", "src/lib.rs"],
+        exclude_extensions: &["rlib", "rmeta", "pdb"],
+    },
+    Builtin {
+        name: "node",
+        ignore: &["node_modules", "dist", "build", "out", "coverage", ".next", ".nuxt", ".svelte-kit", ".vercel", ".turbo", ".parcel-cache"],
+        priority: &["README.md", "package.json", "tsconfig.json", "src/index.*", "index.*"],
+        exclude_extensions: &["map", "tsbuildinfo"],
+    },
+    Builtin {
+        name: "python",
+        ignore: &["__pycache__", "build", "dist"],
+        priority: &["README.md", "pyproject.toml", "setup.py", "setup.cfg", "requirements.txt"],
+        exclude_extensions: &["pyc", "pyo", "pyd"],
+    },
+    Builtin {
+        name: "go",
+        ignore: &["bin"],
+        priority: &["README.md", "go.mod", "main.go", "cmd/*/main.go"],
+        exclude_extensions: &["test", "prof"],
+    },
+    Builtin {
+        name: "java",
+        ignore: &["target", "build", "out", ".gradle"],
+        priority: &["README.md", "pom.xml", "build.gradle", "build.gradle.kts", "settings.gradle", "settings.gradle.kts"],
+        exclude_extensions: &["class", "jar", "war"],
+    },
+];
+
+/// What applies when no project type is detected: a mix of Rust, Node (Next.js) and Python
+const DEFAULT: Builtin = Builtin {
+    name: "default",
+    ignore: &[],
+    priority: &["README.md", "Cargo.toml", "package.json", "pyproject.toml", "go.mod"],
+    exclude_extensions: &[],
+};
+
+/// The defaults one or more presets add up to
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    pub names: Vec<String>,
+    pub ignore: Vec<String>,
+    /// Globs, most important first
+    pub priority: Vec<String>,
+    pub exclude_extensions: Vec<String>,
+}
+
+impl Profile {
+    pub fn default_profile() -> Self {
+        let mut profile = Profile::default();
+        profile.add_builtin(&DEFAULT);
+        profile
+    }
+
+    /// Combine comma-separated presets: built-in names (rust, node, python, go,
+    /// java, default) or TOML files with `ignore`, `priority` and `exclude_extensions`
+    /// lists and an optional `extends = "node"`
+    pub fn load(presets: &str) -> io::Result<Self> {
+        let mut profile = Profile::default();
+        for preset in presets.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            profile.add_preset(preset, 0)?;
+        }
+        Ok(profile)
+    }
+
+    fn add_preset(&mut self, preset: &str, depth: usize) -> io::Result<()> {
+        if let Some(builtin) = BUILTINS.iter().chain([&DEFAULT]).find(|b| b.name.eq_ignore_ascii_case(preset)) {
+            self.add_builtin(builtin);
+            return Ok(());
+        }
+        // Custom presets extending each other could loop
+        if depth > 8 || !Path::new(preset).is_file() {
+            let names: Vec<&str> = BUILTINS.iter().chain([&DEFAULT]).map(|b| b.name).collect();
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown preset '{}' (expected {} or a TOML file)", preset, names.join(", ")),
+            ));
+        }
+
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid preset {}: {}", preset, e));
+        let config: Value = fs::read_to_string(preset)?.parse().map_err(|e: toml::de::Error| invalid(e.to_string()))?;
+        if let Some(base) = config.get("extends") {
+            let base = base.as_str().ok_or_else(|| invalid("'extends' must be a preset name".to_string()))?;
+            self.add_preset(base, depth + 1)?;
+        }
+        let list = |key: &str| -> io::Result<Vec<String>> {
+            match config.get(key) {
+                None => Ok(Vec::new()),
+                Some(value) => value
+                    .as_array()
+                    .and_then(|items| items.iter().map(|item| item.as_str().map(str::to_string)).collect())
+                    .ok_or_else(|| invalid(format!("'{}' must be a list of strings", key))),
+            }
+        };
+        self.names.push(preset.to_string());
+        extend_unique(&mut self.ignore, owned(COMMON_IGNORES));
+        extend_unique(&mut self.ignore, list("ignore")?);
+        extend_unique(&mut self.priority, list("priority")?);
+        extend_unique(&mut self.exclude_extensions, list("exclude_extensions")?);
+        Ok(())
+    }
+
+    fn add_builtin(&mut self, builtin: &Builtin) {
+        self.names.push(builtin.name.to_string());
+        extend_unique(&mut self.ignore, owned(COMMON_IGNORES));
+        extend_unique(&mut self.ignore, owned(builtin.ignore));
+        extend_unique(&mut self.priority, owned(builtin.priority));
+        extend_unique(&mut self.exclude_extensions, owned(builtin.exclude_extensions));
+    }
+
+    /// Whether files with this extension are left out
+    pub fn excludes_extension(&self, path: &Path) -> bool {
+        let extension = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+        !extension.is_empty() && self.exclude_extensions.iter().any(|e| e.trim_start_matches('.') == extension)
+    }
+}
+
+/// Files at the top of a project that identify its ecosystem
+const MARKERS: &[(&str, &[&str])] = &[
+    ("rust", &["Cargo.toml"]),
+    ("node", &["package.json"]),
+    ("python", &["pyproject.toml", "setup.py", "setup.cfg", "requirements.txt", "Pipfile"]),
+    ("go", &["go.mod"]),
+    ("java", &["pom.xml", "build.gradle", "build.gradle.kts"]),
+];
+
+/// Built-in presets matching the marker files in any of the roots, in a fixed order
+pub fn detect(roots: &[Root]) -> Vec<&'static str> {
+    MARKERS
+        .iter()
+        .filter(|(_, files)| roots.iter().any(|root| files.iter().any(|file| root.dir.join(file).is_file())))
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/// Ecosystem ignore files `--ignore-file-types` can apply on top of .gitignore
+const IGNORE_FILES: &[(&str, &str)] = &[
+    ("docker", ".dockerignore"),
+    ("npm", ".npmignore"),
+    ("eslint", ".eslintignore"),
+    ("prettier", ".prettierignore"),
+    ("vercel", ".vercelignore"),
+    ("gcloud", ".gcloudignore"),
+    ("helm", ".helmignore"),
+];
+
+/// File names for comma-separated ignore file types, e.g. "docker,npm"
+pub fn ignore_file_names(types: &str) -> Result<Vec<&'static str>, String> {
+    types
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| {
+            IGNORE_FILES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(t))
+                .map(|(_, file)| *file)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = IGNORE_FILES.iter().map(|(name, _)| *name).collect();
+                    format!("Unknown ignore file type '{}' (expected {})", t, names.join(", "))
+                })
+        })
+        .collect()
+}
+
+/// Python environments and build metadata, whatever they're named: virtualenvs
+/// (`venv/`, `env/`, ...) have a pyvenv.cfg, conda environments a conda-meta
+/// directory, and setuptools writes `*.egg-info` next to the sources
+pub fn is_python_environment(dir: &Path) -> bool {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    name.ends_with(".egg-info") || dir.join("pyvenv.cfg").is_file() || dir.join("conda-meta").is_dir()
+}
+
+fn owned(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+/// Later presets only add what earlier ones don't have, keeping the first position
+fn extend_unique(list: &mut Vec<String>, items: Vec<String>) {
+    for item in items {
+        if !list.contains(&item) {
+            list.push(item);
+        }
+    }
+}
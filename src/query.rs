@@ -0,0 +1,55 @@
+// query.rs
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+use crate::chunk;
+use crate::embeddings::{self, Embedder};
+use crate::SourceFile;
+
+/// Tokens per chunk when ranking files against a query
+pub const CHUNK_TOKENS: usize = 400;
+
+/// A file's relevance, taken from its best-matching chunk
+pub struct Ranked {
+    pub path: PathBuf,
+    pub score: f32,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// Score every file by its best-matching chunk, most relevant first
+pub fn rank_files(
+    files: &[SourceFile],
+    query: &str,
+    embedder: &Embedder,
+    chars_per_token: f64,
+) -> io::Result<Vec<Ranked>> {
+    let chunks = chunk::chunk_files(files, CHUNK_TOKENS, 0, chars_per_token);
+    // The path is embedded too, file names are often the best hint
+    let texts: Vec<String> = chunks
+        .iter()
+        .map(|c| format!("{}\n{}", c.path.display(), c.text))
+        .collect();
+    let mut inputs: Vec<&str> = vec![query];
+    inputs.extend(texts.iter().map(String::as_str));
+    let vectors = embedder.embed(&inputs)?;
+    let (query_vector, chunk_vectors) = vectors.split_first().expect("query embedding");
+
+    let mut best: HashMap<&PathBuf, Ranked> = HashMap::new();
+    for (chunk, vector) in chunks.iter().zip(chunk_vectors) {
+        let score = embeddings::cosine(query_vector, vector);
+        if best.get(&chunk.path).is_none_or(|r| score > r.score) {
+            best.insert(&chunk.path, Ranked {
+                path: chunk.path.clone(),
+                score,
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+            });
+        }
+    }
+
+    let mut ranked: Vec<Ranked> = best.into_values().collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    Ok(ranked)
+}
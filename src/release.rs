@@ -0,0 +1,108 @@
+// release.rs
+//! `release v1.4.0..v1.5.0`: what went into a release, for "write the release
+//! notes" prompts. The changelog entries added between the refs, the commit
+//! messages, then each changed file's diff (or its content at the later ref).
+use std::path::{Path, PathBuf};
+
+use crate::history;
+use crate::secrets::SecretScanner;
+use crate::tokens;
+use crate::SourceFile;
+
+/// Changelog names looked for at the later ref, in this order
+const CHANGELOGS: &[&str] = &["CHANGELOG.md", "CHANGES.md", "HISTORY.md", "NEWS.md", "CHANGELOG", "CHANGES"];
+
+/// The release context, first the summary and commit messages then one file per change
+pub struct Release {
+    pub files: Vec<SourceFile>,
+    pub commits: usize,
+    pub changed: usize,
+}
+
+/// Collect a release from the repository at `dir`; `range` is `FROM..TO`, or
+/// just `FROM` for everything since then. With `secrets`, every part is redacted
+/// the way files from the working tree are.
+pub fn collect(dir: &Path, range: &str, contents: bool, max_size: u64, secrets: Option<&SecretScanner>) -> Result<Release, String> {
+    let (from, to) = match range.split_once("..") {
+        Some((from, to)) => (from, to.trim_start_matches('.')),
+        None => (range, ""),
+    };
+    let to = if to.is_empty() { "HEAD" } else { to };
+    let range = format!("{}..{}", from, to);
+    for rev in [from, to] {
+        history::git(dir, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])
+            .ok_or_else(|| format!("'{}' is not a commit or tag in {}", rev, dir.display()))?;
+    }
+
+    let log = history::git(dir, &["log", "--reverse", "--format=%x00%h %as %an%n%n%B", &range]).unwrap_or_default();
+    let commits: Vec<&str> = log.split('\0').map(str::trim_end).filter(|c| !c.is_empty()).collect();
+
+    // Status and numstat both list files in diff order; numstat says "-" for binaries
+    let status = history::git(dir, &["diff", "--name-status", "--no-renames", &range]).unwrap_or_default();
+    let numstat = history::git(dir, &["diff", "--numstat", "--no-renames", &range]).unwrap_or_default();
+    let changes: Vec<(&str, &str, bool)> = status
+        .lines()
+        .zip(numstat.lines())
+        .filter_map(|(status, numstat)| {
+            let (kind, path) = status.split_once('\t')?;
+            Some((kind, path, numstat.starts_with("-\t-\t")))
+        })
+        .collect();
+
+    let changelog = CHANGELOGS.iter().find(|name| changes.iter().any(|(_, path, _)| path == *name));
+    let mut summary = format!("Release {}: {} commit{}, {} file{} changed\n", range, commits.len(), plural(commits.len()), changes.len(), plural(changes.len()));
+    if let Some(stat) = history::git(dir, &["diff", "--shortstat", &range]) {
+        summary.push_str(stat.trim());
+        summary.push('\n');
+    }
+    summary.push('\n');
+    for (kind, path, binary) in &changes {
+        summary.push_str(&format!("{} {}{}\n", kind, path, if *binary { " (binary)" } else { "" }));
+    }
+
+    let mut files = vec![loaded("RELEASE.txt", summary, secrets)];
+    if let Some(name) = changelog {
+        let diff = history::git(dir, &["diff", "--unified=0", &range, "--", name]).unwrap_or_default();
+        let added: Vec<&str> = diff.lines().filter(|l| !l.starts_with("+++")).filter_map(|l| l.strip_prefix('+')).collect();
+        files.push(loaded(name, format!("{}\n", added.join("\n")), secrets));
+    }
+    files.push(loaded("COMMITS.txt", format!("{}\n", commits.join("\n\n")), secrets));
+
+    for (kind, path, binary) in &changes {
+        if *binary || Some(path) == changelog {
+            continue;
+        }
+        let content = if contents && *kind != "D" {
+            history::git(dir, &["show", &format!("{}:{}", to, path)])
+        } else {
+            history::git(dir, &["diff", &range, "--", path])
+        };
+        let content = content.unwrap_or_default();
+        let content = if content.len() as u64 > max_size {
+            format!("({} omitted, {} bytes is over --max-size)\n", if contents { "Content" } else { "Diff" }, content.len())
+        } else {
+            content
+        };
+        files.push(loaded(path, content, secrets));
+    }
+    Ok(Release { files, commits: commits.len(), changed: changes.len() })
+}
+
+fn loaded(path: &str, content: String, secrets: Option<&SecretScanner>) -> SourceFile {
+    let (content, redactions) = match secrets {
+        Some(scanner) => scanner.redact(&content),
+        None => (content, Vec::new()),
+    };
+    SourceFile {
+        relative_path: PathBuf::from(path),
+        size: content.len() as u64,
+        modified: None,
+        tokens: tokens::estimate_tokens(&content, tokens::DEFAULT_CHARS_PER_TOKEN),
+        content,
+        redactions,
+    }
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
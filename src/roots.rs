@@ -0,0 +1,135 @@
+// roots.rs
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use crate::imports;
+use crate::workspace;
+
+/// A directory whose files go into the context
+pub struct Root {
+    pub dir: PathBuf,
+    /// Prepended to the paths of this root's files so several roots can share
+    /// one tree; empty when there is a single root (unless appending)
+    pub prefix: PathBuf,
+}
+
+/// Roots from `--dir` values, each of which may hold several comma-separated
+/// paths or glob patterns such as `services/*`. `labeled` prefixes a single
+/// root's paths too, so `--append` runs over different roots don't collide.
+pub fn expand(dirs: &[String], labeled: bool) -> Result<Vec<Root>, String> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for pattern in dirs.iter().flat_map(|d| d.split(',')).map(str::trim).filter(|p| !p.is_empty()) {
+        if !pattern.contains(['*', '?', '[']) {
+            paths.push(PathBuf::from(pattern));
+            continue;
+        }
+        let (base, relative) = match pattern.strip_prefix('/') {
+            Some(relative) => (Path::new("/"), relative),
+            None => (Path::new("."), pattern),
+        };
+        let matched = workspace::expand_dirs(base, relative);
+        if matched.is_empty() {
+            return Err(format!("No directories match --dir '{}'", pattern));
+        }
+        paths.extend(matched.into_iter().map(|dir| base.join(dir)));
+    }
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+
+    if paths.len() == 1 && !labeled {
+        return Ok(paths.into_iter().map(|dir| Root { dir, prefix: PathBuf::new() }).collect());
+    }
+    let mut roots: Vec<Root> = Vec::new();
+    for dir in paths {
+        let label = label(&dir);
+        // Two roots with the same name get numbered prefixes
+        let mut prefix = label.clone();
+        let mut n = 2;
+        while roots.iter().any(|r| r.prefix == prefix) {
+            prefix = PathBuf::from(format!("{}-{}", label.display(), n));
+            n += 1;
+        }
+        roots.push(Root { dir, prefix });
+    }
+    Ok(roots)
+}
+
+/// `services/api` stays as is; `../other-repo`, `/abs/path` and `.` become
+/// the name of the directory itself
+fn label(dir: &Path) -> PathBuf {
+    let normalized = imports::normalize(dir);
+    let nested = normalized.components().all(|c| matches!(c, Component::Normal(_)));
+    if nested && !normalized.as_os_str().is_empty() {
+        return normalized;
+    }
+    dir.canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("root"))
+}
+
+/// A file named explicitly, with the 1-based inclusive line ranges to keep
+/// (all of it when empty)
+pub struct Listed {
+    pub path: PathBuf,
+    pub ranges: Vec<(usize, usize)>,
+}
+
+/// Parse `path`, `path:42`, `path:120-340` or `path:1-20,80-95`
+pub fn parse_listed(entry: &str) -> Result<Listed, String> {
+    let ranges_suffix = entry.rsplit_once(':').filter(|(path, ranges)| {
+        !path.is_empty() && !Path::new(entry).exists() && ranges.chars().all(|c| c.is_ascii_digit() || c == '-' || c == ',')
+    });
+    let Some((path, suffix)) = ranges_suffix else {
+        return Ok(Listed { path: PathBuf::from(entry), ranges: Vec::new() });
+    };
+    let mut ranges = Vec::new();
+    for range in suffix.split(',') {
+        let (start, end) = range.split_once('-').unwrap_or((range, range));
+        match (start.parse::<usize>(), end.parse::<usize>()) {
+            (Ok(start), Ok(end)) if start >= 1 && start <= end => ranges.push((start, end)),
+            _ => return Err(format!("Invalid line range '{}' in '{}' (expected START-END, counting from 1)", range, entry)),
+        }
+    }
+    Ok(Listed { path: PathBuf::from(path), ranges })
+}
+
+/// Entries listed one per line in a file, or on stdin for `-`
+pub fn read_file_list(source: &str) -> io::Result<Vec<Listed>> {
+    let content = if source == "-" {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        content
+    } else {
+        fs::read_to_string(source)?
+    };
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| parse_listed(l).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e)))
+        .collect()
+}
+
+/// Where a listed path is on disk, and its path in the output: relative to the
+/// root containing it, else as listed. Paths are tried relative to the current
+/// directory first, then to the first root, as `git diff --name-only` prints
+/// them relative to the repository.
+pub fn locate(listed: &Path, roots: &[Root]) -> Option<(PathBuf, PathBuf)> {
+    let path = if listed.is_file() { listed.to_path_buf() } else { roots.first()?.dir.join(listed) };
+    if !path.is_file() {
+        return None;
+    }
+    let absolute = path.canonicalize().ok()?;
+    for root in roots {
+        let Ok(dir) = root.dir.canonicalize() else { continue };
+        if let Ok(relative) = absolute.strip_prefix(&dir) {
+            return Some((path.clone(), root.prefix.join(relative)));
+        }
+    }
+    let relative = imports::normalize(listed);
+    let relative = relative.strip_prefix("/").map(Path::to_path_buf).unwrap_or(relative);
+    Some((path, relative))
+}
@@ -0,0 +1,88 @@
+// sample.rs
+use std::collections::VecDeque;
+
+const DATA_EXTENSIONS: &[&str] = &["csv", "tsv", "jsonl", "ndjson"];
+
+pub fn is_data_file(file_name: &str) -> bool {
+    let lower = file_name.to_lowercase();
+    DATA_EXTENSIONS.iter().any(|ext| lower.ends_with(&format!(".{}", ext)))
+}
+
+/// Keep the header (CSV/TSV only) plus the first and last `rows` rows,
+/// with a note saying how many rows were left out
+pub fn sample(file_name: &str, content: &str, rows: usize) -> String {
+    String::from_utf8(sample_bytes(file_name, content.as_bytes(), rows)).expect("whole records of UTF-8 are UTF-8")
+}
+
+/// `sample` on bytes not decoded yet, so a large file is only decoded for the
+/// rows kept; records end at `\n` in any ASCII-compatible encoding. Only the
+/// rows kept are held on to, however many the file has.
+pub fn sample_bytes(file_name: &str, content: &[u8], rows: usize) -> Vec<u8> {
+    let lower = file_name.to_lowercase();
+    let has_header = lower.ends_with(".csv") || lower.ends_with(".tsv");
+
+    let mut records = split_records(content, has_header);
+    let header = if has_header { records.next() } else { None };
+    let mut first = Vec::new();
+    let mut last = VecDeque::new();
+    let mut total = 0;
+    for record in records {
+        total += 1;
+        if first.len() < rows {
+            first.push(record);
+        } else {
+            last.push_back(record);
+            if last.len() > rows {
+                last.pop_front();
+            }
+        }
+    }
+    if total <= rows * 2 {
+        return content.to_vec();
+    }
+
+    let mut out = Vec::new();
+    if let Some(header) = header {
+        push_record(&mut out, header);
+    }
+    for record in first {
+        push_record(&mut out, record);
+    }
+    out.extend_from_slice(format!("... ({} of {} rows omitted) ...\n", total - rows * 2, total).as_bytes());
+    for record in last {
+        push_record(&mut out, record);
+    }
+    out
+}
+
+/// Split into non-blank records; quoted CSV fields may contain newlines
+fn split_records(content: &[u8], quoted: bool) -> impl Iterator<Item = &[u8]> {
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut i = 0;
+    std::iter::from_fn(move || {
+        while start < content.len() {
+            let record = loop {
+                match content.get(i) {
+                    None => break &content[start..],
+                    Some(b'"') if quoted => in_quotes = !in_quotes,
+                    Some(b'\n') if !in_quotes => break &content[start..i],
+                    _ => {}
+                }
+                i += 1;
+            };
+            i += 1;
+            start = i;
+            if !record.trim_ascii().is_empty() {
+                return Some(record);
+            }
+        }
+        None
+    })
+}
+
+fn push_record(out: &mut Vec<u8>, record: &[u8]) {
+    let end = record.iter().rposition(|b| *b != b'\r').map_or(0, |i| i + 1);
+    out.extend_from_slice(&record[..end]);
+    out.push(b'\n');
+}
@@ -0,0 +1,82 @@
+// secrets.rs
+use regex::Regex;
+
+/// Detects common credential patterns and replaces them with `[REDACTED:<kind>]`
+pub struct SecretScanner {
+    rules: Vec<(&'static str, Regex)>,
+}
+
+impl Default for SecretScanner {
+    fn default() -> Self {
+        SecretScanner::new()
+    }
+}
+
+impl SecretScanner {
+    pub fn new() -> Self {
+        let patterns: &[(&str, &str)] = &[
+            (
+                "private-key",
+                r"-----BEGIN [A-Z ]*PRIVATE KEY( BLOCK)?-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY( BLOCK)?-----",
+            ),
+            ("aws-key", r"\b(AKIA|ASIA)[0-9A-Z]{16}\b"),
+            (
+                "aws-secret",
+                r#"(?i)aws_secret_access_key\s*[:=]\s*["']?[A-Za-z0-9/+=]{40}["']?"#,
+            ),
+            ("github-token", r"\b(ghp|gho|ghu|ghs|ghr)_[A-Za-z0-9]{36,}\b"),
+            ("slack-token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}\b"),
+            ("stripe-key", r"\b(sk|rk)_(live|test)_[A-Za-z0-9]{16,}\b"),
+            ("bearer-token", r"(?i)\bbearer\s+[A-Za-z0-9\-._~+/]{20,}=*"),
+            (
+                "connection-string",
+                r"\b(postgres(ql)?|mysql|mongodb(\+srv)?|redis|amqp)://[^\s:/@]+:[^\s@]+@[^\s'\x22]+",
+            ),
+        ];
+
+        let rules = patterns
+            .iter()
+            .map(|(kind, pattern)| (*kind, Regex::new(pattern).expect("invalid secret pattern")))
+            .collect();
+        SecretScanner { rules }
+    }
+
+    /// Redact all secrets in `content`, returning the kinds that were found
+    pub fn redact(&self, content: &str) -> (String, Vec<&'static str>) {
+        let mut redacted = content.to_string();
+        let mut found = Vec::new();
+        for (kind, regex) in &self.rules {
+            if regex.is_match(&redacted) {
+                let count = regex.find_iter(&redacted).count();
+                found.extend(std::iter::repeat_n(*kind, count));
+                redacted = regex
+                    .replace_all(&redacted, format!("[REDACTED:{}]", kind).as_str())
+                    .into_owned();
+            }
+        }
+        (redacted, found)
+    }
+}
+
+/// `.env`, `.env.local`, `.env.production`, ...
+pub fn is_env_file(file_name: &str) -> bool {
+    file_name == ".env" || file_name.starts_with(".env.")
+}
+
+/// Keep variable names from a dotenv file but replace every non-empty value with `mask`
+pub fn mask_env_values(content: &str, mask: &str) -> String {
+    let mut masked = String::with_capacity(content.len());
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        match line.split_once('=') {
+            Some((key, value)) if !trimmed.starts_with('#') && !value.trim().is_empty() => {
+                masked.push_str(key);
+                masked.push('=');
+                masked.push_str(mask);
+            }
+            _ => masked.push_str(line),
+        }
+        masked.push('\n');
+    }
+    masked
+}
@@ -0,0 +1,115 @@
+// selfupdate.rs
+//! `self-update`: replace the running binary with the latest GitHub release's,
+//! after checking it against the SHA-256 checksum published next to it.
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Release binaries are a few MB; anything far bigger isn't one
+const MAX_DOWNLOAD_BYTES: u64 = 200_000_000;
+
+/// The release asset built for this OS and architecture by the release workflow;
+/// None where there is none, rather than a binary that can't run here
+fn asset_name() -> Option<&'static str> {
+    match (env::consts::OS, env::consts::ARCH) {
+        ("linux", "x86_64") => Some("llm-context-gen-linux-x86_64"),
+        ("linux", "aarch64") => Some("llm-context-gen-linux-aarch64"),
+        ("macos", "x86_64") => Some("llm-context-gen-macos-x86_64"),
+        ("macos", "aarch64") => Some("llm-context-gen-macos-aarch64"),
+        ("windows", "x86_64") => Some("llm-context-gen-windows-x86_64.exe"),
+        _ => None,
+    }
+}
+
+/// Check for a newer release and, unless `check_only`, install it
+pub fn run(check_only: bool) -> io::Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let repository = env!("CARGO_PKG_REPOSITORY").trim_end_matches('/');
+    let slug = repository.strip_prefix("https://github.com/").unwrap_or(repository);
+    let release = get_json(&format!("https://api.github.com/repos/{}/releases/latest", slug))?;
+
+    let tag = release["tag_name"].as_str().ok_or_else(|| io::Error::other("Latest release has no tag"))?;
+    let latest = tag.trim_start_matches('v');
+    if version(latest) <= version(current) {
+        println!("llm-context-gen {} is the latest version", current);
+        return Ok(());
+    }
+    println!("llm-context-gen {} is available (installed: {})", latest, current);
+    if check_only {
+        return Ok(());
+    }
+
+    let name = asset_name().ok_or_else(|| io::Error::other(format!("No release binary is built for {} on {}, not updating", env::consts::OS, env::consts::ARCH)))?;
+    let download_url = |file: &str| {
+        release["assets"]
+            .as_array()
+            .and_then(|assets| assets.iter().find(|a| a["name"] == file))
+            .and_then(|a| a["browser_download_url"].as_str())
+            .map(str::to_string)
+            .ok_or_else(|| io::Error::other(format!("Release {} has no {}", tag, file)))
+    };
+    // Refuse to install anything that can't be verified
+    let checksum = String::from_utf8_lossy(&download(&download_url(&format!("{}.sha256", name))?)?).into_owned();
+    let expected = checksum.split_whitespace().next().unwrap_or_default().to_lowercase();
+    let binary = download(&download_url(name)?)?;
+    let actual: String = Sha256::digest(&binary).iter().map(|b| format!("{:02x}", b)).collect();
+    if actual != expected {
+        return Err(io::Error::other(format!("Checksum mismatch for {} (expected {}, got {}), not installed", name, expected, actual)));
+    }
+
+    let installed = env::current_exe()?;
+    replace(&installed, &binary)?;
+    println!("Updated {} to {}", installed.display(), latest);
+    Ok(())
+}
+
+/// "0.1.10" as [0, 1, 10], so it sorts after "0.1.9"
+fn version(text: &str) -> Vec<u64> {
+    text.split(['.', '-', '+']).map_while(|part| part.parse().ok()).collect()
+}
+
+fn get_json(url: &str) -> io::Result<Value> {
+    agent(url)?.into_json()
+}
+
+fn download(url: &str) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    agent(url)?.into_reader().take(MAX_DOWNLOAD_BYTES).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn agent(url: &str) -> io::Result<ureq::Response> {
+    ureq::get(url)
+        .set("User-Agent", concat!("llm-context-gen/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .map_err(|e| io::Error::other(format!("Request to {} failed: {}", url, e)))
+}
+
+/// Write the new binary next to the old one and move it into place, so a
+/// failed download never leaves a half-written executable behind
+fn replace(installed: &Path, binary: &[u8]) -> io::Result<()> {
+    let staged = with_suffix(installed, ".new");
+    fs::write(&staged, binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+    // Windows can't overwrite a running executable, but it can rename it
+    if cfg!(windows) {
+        let old = with_suffix(installed, ".old");
+        let _ = fs::remove_file(&old);
+        fs::rename(installed, &old)?;
+    }
+    fs::rename(&staged, installed)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
@@ -0,0 +1,108 @@
+// settings.rs
+//! Options from the environment and a config file. The command line wins over
+//! `LLM_CONTEXT_GEN_*` variables, which win over `.llm-context-gen.toml`,
+//! which wins over the built-in defaults.
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use toml::Value;
+
+/// `LLM_CONTEXT_GEN_MAX_FILES=500` is `--max-files 500`
+const ENV_PREFIX: &str = "LLM_CONTEXT_GEN_";
+
+/// Read from the working directory unless LLM_CONTEXT_GEN_CONFIG names another file
+const CONFIG_FILE: &str = ".llm-context-gen.toml";
+
+/// Options that run commands or send files elsewhere, which the config file in the
+/// working directory (possibly from a just-cloned repository) only sets with the
+/// flag after them: (option, allowing flag, what it does)
+const GATED_OPTIONS: &[(&str, &str, &str)] = &[
+    ("preprocess", "allow_preprocess", "runs commands"),
+    ("emit_embeddings", "allow_network", "sends files over the network"),
+    ("embedding_provider", "allow_network", "sends files over the network"),
+    ("embedding_url", "allow_network", "sends files over the network"),
+    ("embedding_api_key_env", "allow_network", "sends files over the network"),
+];
+
+const CONFIG_VAR: &str = "LLM_CONTEXT_GEN_CONFIG";
+
+/// The command line with the options it doesn't give filled in from the
+/// environment and the config file. `given` says whether the command line
+/// sets an option, by its snake_case name (e.g. `max_files`).
+pub fn fill_in(args: Vec<OsString>, given: impl Fn(&str) -> bool) -> io::Result<Vec<OsString>> {
+    let mut settings: Vec<(String, Vec<String>)> = Vec::new();
+    let mut add = |name: String, values: Vec<String>| {
+        if !given(&name) && !settings.iter().any(|(existing, _)| *existing == name) {
+            settings.push((name, values));
+        }
+    };
+
+    // Sorted so the filled-in arguments don't depend on the environment's order
+    let mut vars: Vec<(String, String)> = env::vars().filter(|(key, _)| key.starts_with(ENV_PREFIX) && key != CONFIG_VAR).collect();
+    vars.sort();
+    // Only the command line and the environment can allow the project config to run commands
+    // or reach the network
+    let allowed = |flag: &str| {
+        given(flag) || vars.iter().any(|(key, value)| key[ENV_PREFIX.len()..].eq_ignore_ascii_case(flag) && !value.eq_ignore_ascii_case("false"))
+    };
+    let allowed: Vec<&str> = GATED_OPTIONS.iter().map(|&(_, flag, _)| flag).filter(|flag| allowed(flag)).collect();
+    for (key, value) in vars {
+        add(key[ENV_PREFIX.len()..].to_lowercase(), vec![value]);
+    }
+
+    let (config_path, required) = match env::var_os(CONFIG_VAR) {
+        Some(path) => (PathBuf::from(path), true),
+        None => (PathBuf::from(CONFIG_FILE), false),
+    };
+    if required || config_path.is_file() {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid config {}: {}", config_path.display(), e));
+        let config: toml::Table = fs::read_to_string(&config_path)?.parse().map_err(|e: toml::de::Error| invalid(e.to_string()))?;
+        for (key, value) in config {
+            let values = config_values(&value).ok_or_else(|| invalid(format!("'{}' must be a string, number, boolean or list", key)))?;
+            let name = key.replace('-', "_").to_lowercase();
+            if let Some(&(_, flag, effect)) = GATED_OPTIONS.iter().find(|&&(option, _, _)| option == name) {
+                if !required && !allowed.contains(&flag) {
+                    eprintln!("Ignoring '{}' in {}, it {} (pass --{} to use it)", key, config_path.display(), effect, flag.replace('_', "-"));
+                    continue;
+                }
+            }
+            add(name, values);
+        }
+    }
+
+    // Inserted before the command line's own arguments, which may start a subcommand
+    let mut filled = args.into_iter();
+    let mut out: Vec<OsString> = filled.next().into_iter().collect();
+    for (name, values) in settings {
+        let flag = format!("--{}", name.replace('_', "-"));
+        for value in values {
+            match value.to_lowercase().as_str() {
+                "true" => out.push(flag.clone().into()),
+                "false" => {}
+                _ => out.push(format!("{}={}", flag, value).into()),
+            }
+        }
+    }
+    out.extend(filled);
+    Ok(out)
+}
+
+/// Flags as true/false, lists as one value each, and tables (e.g. `[preprocess]`)
+/// as one `key=value` each
+fn config_values(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::String(s) => Some(vec![s.clone()]),
+        Value::Integer(i) => Some(vec![i.to_string()]),
+        Value::Float(f) => Some(vec![f.to_string()]),
+        Value::Boolean(b) => Some(vec![b.to_string()]),
+        Value::Array(items) => items.iter().map(|item| config_values(item).and_then(|v| v.into_iter().next())).collect(),
+        Value::Table(table) => table
+            .iter()
+            .map(|(key, item)| config_values(item).and_then(|v| v.into_iter().next()).map(|v| format!("{}={}", key, v)))
+            .collect(),
+        _ => None,
+    }
+}
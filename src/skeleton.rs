@@ -0,0 +1,281 @@
+// skeleton.rs
+use std::str::FromStr;
+
+use tree_sitter::{Node, Parser, Tree};
+
+use crate::language::Language;
+
+/// How much of each file's content is emitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Full,
+    /// Imports, type definitions and function signatures only
+    Skeleton,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(Mode::Full),
+            "skeleton" => Ok(Mode::Skeleton),
+            _ => Err(format!("Unknown mode '{}' (expected full or skeleton)", s)),
+        }
+    }
+}
+
+/// Which syntax nodes make up a language's skeleton
+struct Grammar {
+    language: fn() -> tree_sitter::Language,
+    /// Top-level nodes that are kept, with function bodies elided
+    keep: &'static [&'static str],
+    /// Nodes kept when they directly precede a kept node (doc comments, attributes)
+    leading: &'static [&'static str],
+    /// Function-like nodes whose `body` field is elided
+    functions: &'static [&'static str],
+    placeholder: &'static str,
+}
+
+const JS_KEEP: &[&str] = &[
+    "import_statement", "export_statement", "class_declaration", "function_declaration",
+    "generator_function_declaration", "lexical_declaration",
+];
+const TS_KEEP: &[&str] = &[
+    "import_statement", "export_statement", "class_declaration", "abstract_class_declaration",
+    "function_declaration", "generator_function_declaration", "function_signature", "lexical_declaration",
+    "interface_declaration", "type_alias_declaration", "enum_declaration", "module",
+    "internal_module", "ambient_declaration",
+];
+const JS_FUNCTIONS: &[&str] = &[
+    "function_declaration", "generator_function_declaration", "function_expression",
+    "generator_function", "arrow_function", "method_definition",
+];
+
+static RUST: Grammar = Grammar {
+    language: || tree_sitter_rust::LANGUAGE.into(),
+    keep: &[
+        "use_declaration", "extern_crate_declaration", "mod_item", "struct_item", "enum_item",
+        "union_item", "type_item", "trait_item", "impl_item", "function_item", "function_signature_item",
+    ],
+    leading: &["line_comment", "block_comment", "attribute_item"],
+    functions: &["function_item"],
+    placeholder: "{ ... }",
+};
+
+static PYTHON: Grammar = Grammar {
+    language: || tree_sitter_python::LANGUAGE.into(),
+    keep: &[
+        "import_statement", "import_from_statement", "future_import_statement",
+        "class_definition", "function_definition", "decorated_definition",
+    ],
+    leading: &["comment"],
+    functions: &["function_definition"],
+    placeholder: "...",
+};
+
+static JAVASCRIPT: Grammar = Grammar {
+    language: || tree_sitter_javascript::LANGUAGE.into(),
+    keep: JS_KEEP,
+    leading: &["comment"],
+    functions: JS_FUNCTIONS,
+    placeholder: "{ ... }",
+};
+
+static TYPESCRIPT: Grammar = Grammar {
+    language: || tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+    keep: TS_KEEP,
+    leading: &["comment"],
+    functions: JS_FUNCTIONS,
+    placeholder: "{ ... }",
+};
+
+static TSX: Grammar = Grammar {
+    language: || tree_sitter_typescript::LANGUAGE_TSX.into(),
+    keep: TS_KEEP,
+    leading: &["comment"],
+    functions: JS_FUNCTIONS,
+    placeholder: "{ ... }",
+};
+
+static GO: Grammar = Grammar {
+    language: || tree_sitter_go::LANGUAGE.into(),
+    keep: &[
+        "package_clause", "import_declaration", "type_declaration",
+        "function_declaration", "method_declaration",
+    ],
+    leading: &["comment"],
+    functions: &["function_declaration", "method_declaration", "func_literal"],
+    placeholder: "{ ... }",
+};
+
+fn grammar(language: &Language) -> Option<&'static Grammar> {
+    match language.name {
+        "Rust" => Some(&RUST),
+        "Python" => Some(&PYTHON),
+        "JavaScript" | "JSX" => Some(&JAVASCRIPT),
+        "TypeScript" => Some(&TYPESCRIPT),
+        "TSX" => Some(&TSX),
+        "Go" => Some(&GO),
+        _ => None,
+    }
+}
+
+/// Parse with the language's tree-sitter grammar, if there is one
+pub fn parse(content: &str, language: &Language) -> Option<Tree> {
+    let grammar = grammar(language)?;
+    let mut parser = Parser::new();
+    parser.set_language(&(grammar.language)()).ok()?;
+    parser.parse(content, None)
+}
+
+/// Reduce a file to its imports, type definitions and function signatures.
+/// Returns None for languages without a grammar or if parsing fails.
+pub fn skeleton(content: &str, language: &Language) -> Option<String> {
+    signatures(content, language).map(|(skeleton, _)| skeleton)
+}
+
+/// The skeleton and how many function bodies it elided
+pub fn signatures(content: &str, language: &Language) -> Option<(String, usize)> {
+    let grammar = grammar(language)?;
+    let tree = parse(content, language)?;
+
+    let mut out = String::new();
+    let mut leading: Vec<Node> = Vec::new();
+    let mut last_row: Option<usize> = None;
+    let mut elided = 0;
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    for node in root.named_children(&mut cursor) {
+        let kind = node.kind();
+        if grammar.leading.contains(&kind) {
+            leading.push(node);
+            continue;
+        }
+        if !grammar.keep.contains(&kind) {
+            leading.clear();
+            continue;
+        }
+
+        let start = leading.first().copied().unwrap_or(node);
+        // Keep one blank line where the source had at least one
+        if let Some(row) = last_row {
+            out.push('\n');
+            if start.start_position().row > row + 1 {
+                out.push('\n');
+            }
+        }
+        for comment in leading.drain(..) {
+            out.push_str(content[comment.byte_range()].trim_end());
+            out.push('\n');
+        }
+        let (item, bodies) = elide_bodies(node, content, grammar);
+        out.push_str(&item);
+        elided += bodies;
+        last_row = Some(node.end_position().row);
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Some((out, elided))
+}
+
+/// Cut a file down to about `max_chars` by eliding function bodies, largest
+/// first, so everything else (imports, types, signatures, small functions)
+/// stays verbatim. Returns the content and how many bodies were elided, or None
+/// for languages without a grammar; it can still be over when the bodies
+/// weren't enough.
+pub fn fit(content: &str, language: &Language, max_chars: usize) -> Option<(String, usize)> {
+    let grammar = grammar(language)?;
+    let tree = parse(content, language)?;
+    let mut bodies = Vec::new();
+    collect_bodies(tree.root_node(), grammar, &mut bodies);
+
+    let mut by_size: Vec<(usize, usize)> = bodies.iter().map(|&(start, end)| (content[start..end].chars().count(), start)).collect();
+    by_size.sort_by(|a, b| b.cmp(a));
+    let mut chars = content.chars().count();
+    let mut elided = Vec::new();
+    for (size, start) in by_size {
+        // Past here eliding saves nothing, "{}" is shorter than "{ ... }"
+        if chars <= max_chars || size <= grammar.placeholder.len() {
+            break;
+        }
+        chars = chars + grammar.placeholder.len() - size;
+        elided.push(start);
+    }
+
+    let mut out = String::new();
+    let mut position = 0;
+    for &(start, end) in bodies.iter().filter(|(start, _)| elided.contains(start)) {
+        out.push_str(&content[position..start]);
+        out.push_str(grammar.placeholder);
+        position = end;
+    }
+    out.push_str(&content[position..]);
+    Some((out, elided.len()))
+}
+
+/// Every function with a block body as (signature, body), for languages with a grammar.
+/// The signature includes doc comments and attributes directly above the function.
+pub fn functions(content: &str, language: &Language) -> Vec<(String, String)> {
+    let (Some(grammar), Some(tree)) = (grammar(language), parse(content, language)) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    collect_functions(tree.root_node(), content, grammar, &mut found);
+    found
+}
+
+fn collect_functions(node: Node, content: &str, grammar: &Grammar, found: &mut Vec<(String, String)>) {
+    if grammar.functions.contains(&node.kind()) {
+        if let Some(body) = node.child_by_field_name("body").filter(|b| !is_expression_body(*b)) {
+            let mut start = node;
+            while let Some(previous) = start.prev_named_sibling().filter(|p| grammar.leading.contains(&p.kind())) {
+                start = previous;
+            }
+            // Include the indentation of the first line so bodies stay aligned
+            let line_start = content[..start.start_byte()].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let signature = &content[line_start..body.start_byte()];
+            found.push((signature.to_string(), content[body.byte_range()].to_string()));
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_functions(child, content, grammar, found);
+    }
+}
+
+/// Source text of `node` with every function body replaced by the placeholder,
+/// and how many there were
+fn elide_bodies(node: Node, content: &str, grammar: &Grammar) -> (String, usize) {
+    let mut bodies = Vec::new();
+    collect_bodies(node, grammar, &mut bodies);
+
+    let mut out = String::new();
+    let mut position = node.start_byte();
+    for &(start, end) in &bodies {
+        out.push_str(&content[position..start]);
+        out.push_str(grammar.placeholder);
+        position = end;
+    }
+    out.push_str(&content[position..node.end_byte()]);
+    (out, bodies.len())
+}
+
+fn collect_bodies(node: Node, grammar: &Grammar, bodies: &mut Vec<(usize, usize)>) {
+    if grammar.functions.contains(&node.kind()) {
+        // Expression-bodied arrow functions are already just a signature and an expression
+        if let Some(body) = node.child_by_field_name("body").filter(|b| !is_expression_body(*b)) {
+            bodies.push((body.start_byte(), body.end_byte()));
+            return;
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_bodies(child, grammar, bodies);
+    }
+}
+
+fn is_expression_body(body: Node) -> bool {
+    !matches!(body.kind(), "block" | "statement_block")
+}
@@ -0,0 +1,125 @@
+// sort.rs
+use std::cmp::Reverse;
+use std::path::Path;
+use std::str::FromStr;
+
+use globset::GlobSet;
+
+use crate::importance::Scores;
+use crate::SourceFile;
+
+/// Order in which files appear in the combined output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Alphabetical by relative path
+    Path,
+    /// Smallest files first
+    Size,
+    /// Most recently modified first
+    Mtime,
+    /// Fewest tokens first
+    Tokens,
+    /// Highest importance score first
+    Importance,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "path" => Ok(SortOrder::Path),
+            "size" => Ok(SortOrder::Size),
+            "mtime" => Ok(SortOrder::Mtime),
+            "tokens" => Ok(SortOrder::Tokens),
+            "importance" => Ok(SortOrder::Importance),
+            _ => Err(format!(
+                "Unknown sort order '{}' (expected path, size, mtime, tokens or importance)",
+                s
+            )),
+        }
+    }
+}
+
+/// `importance` holds the full scores for `SortOrder::Importance`; without
+/// them files are ranked by their path alone
+pub fn sort_files(files: &mut [SourceFile], order: SortOrder, importance: Option<&Scores>) {
+    // Always sort by path first so ties are broken deterministically
+    files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    match order {
+        SortOrder::Path => {}
+        SortOrder::Size => files.sort_by_key(|f| f.size),
+        SortOrder::Mtime => files.sort_by_key(|f| Reverse(f.modified)),
+        SortOrder::Tokens => files.sort_by_key(|f| f.tokens),
+        SortOrder::Importance => match importance {
+            Some(scores) => files.sort_by(|a, b| {
+                let score = |f: &SourceFile| scores.get(&f.relative_path).map_or(f64::MIN, |s| s.total);
+                score(b).total_cmp(&score(a))
+            }),
+            None => files.sort_by_key(|f| Reverse(importance_score(&f.relative_path))),
+        },
+    }
+}
+
+/// Move files matching a priority pattern to the front, in pattern order.
+/// Files matching no pattern keep their existing relative order.
+pub fn prioritize(files: &mut [SourceFile], priority: &GlobSet) {
+    if priority.is_empty() {
+        return;
+    }
+    files.sort_by_key(|f| {
+        priority
+            .matches(&f.relative_path)
+            .into_iter()
+            .min()
+            .unwrap_or(usize::MAX)
+    });
+}
+
+/// Heuristic score of how useful a file is for orienting a model in the project
+pub fn importance_score(relative_path: &Path) -> i32 {
+    let file_name = relative_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    let path = relative_path.to_string_lossy().to_lowercase();
+    let mut score = 0;
+
+    // Project documentation and manifests
+    if file_name.starts_with("readme") {
+        score += 100;
+    }
+    let manifests = [
+        "cargo.toml", "package.json", "pyproject.toml", "go.mod",
+        "pom.xml", "build.gradle", "gemfile", "composer.json",
+    ];
+    if manifests.contains(&file_name.as_str()) {
+        score += 80;
+    }
+
+    // Typical entry points
+    let stem = file_name.split('.').next().unwrap_or("");
+    let entry_points = ["main", "lib", "index", "app", "mod", "__init__", "server"];
+    if entry_points.contains(&stem) {
+        score += 50;
+    }
+
+    // Source directories matter more than tests, examples and docs
+    if path.starts_with("src/") || path.contains("/src/") {
+        score += 20;
+    }
+    if path.contains("test") || path.contains("spec") {
+        score -= 30;
+    }
+    if path.starts_with("examples/") || path.starts_with("docs/") {
+        score -= 10;
+    }
+
+    // Files closer to the root tend to be more central
+    let depth = relative_path.components().count() as i32;
+    score -= depth * 5;
+
+    score
+}
@@ -0,0 +1,116 @@
+// sqlite.rs
+//! Schema of `--format sqlite` databases:
+//!
+//! ```sql
+//! CREATE TABLE metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+//! CREATE TABLE files (
+//!     id INTEGER PRIMARY KEY,
+//!     path TEXT NOT NULL UNIQUE,
+//!     language TEXT,
+//!     size INTEGER NOT NULL,     -- bytes on disk
+//!     modified INTEGER,          -- unix seconds
+//!     tokens INTEGER NOT NULL,   -- estimate for the emitted content
+//!     content TEXT NOT NULL
+//! );
+//! CREATE TABLE chunks (
+//!     id INTEGER PRIMARY KEY,
+//!     file_id INTEGER NOT NULL REFERENCES files(id),
+//!     start_line INTEGER NOT NULL,  -- 1-based, inclusive
+//!     end_line INTEGER NOT NULL,
+//!     tokens INTEGER NOT NULL,
+//!     content TEXT NOT NULL
+//! );
+//! ```
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use rusqlite::{params, Connection};
+
+use crate::chunk::Chunk;
+use crate::language;
+use crate::SourceFile;
+
+const SCHEMA: &str = "
+CREATE TABLE metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    language TEXT,
+    size INTEGER NOT NULL,
+    modified INTEGER,
+    tokens INTEGER NOT NULL,
+    content TEXT NOT NULL
+);
+CREATE TABLE chunks (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    start_line INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    tokens INTEGER NOT NULL,
+    content TEXT NOT NULL
+);
+CREATE INDEX chunks_file ON chunks(file_id);
+";
+
+/// Write files and chunks into a fresh database at `path`
+pub fn write_database(files: &[SourceFile], chunks: &[Chunk], path: &Path) -> io::Result<()> {
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    write(files, chunks, path).map_err(|e| io::Error::other(format!("Error writing {}: {}", path.display(), e)))
+}
+
+fn write(files: &[SourceFile], chunks: &[Chunk], path: &Path) -> rusqlite::Result<()> {
+    let mut db = Connection::open(path)?;
+    db.execute_batch(SCHEMA)?;
+    let tx = db.transaction()?;
+
+    tx.execute(
+        "INSERT INTO metadata (key, value) VALUES ('generator', ?1), ('files', ?2), ('chunks', ?3)",
+        params![
+            format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            files.len().to_string(),
+            chunks.len().to_string(),
+        ],
+    )?;
+
+    let mut ids: HashMap<&PathBuf, i64> = HashMap::new();
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO files (path, language, size, modified, tokens, content) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for file in files {
+            let modified = file
+                .modified
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64);
+            insert.execute(params![
+                file.relative_path.to_string_lossy(),
+                language::of(file).map(|l| l.name),
+                file.size as i64,
+                modified,
+                file.tokens as i64,
+                file.content,
+            ])?;
+            ids.insert(&file.relative_path, tx.last_insert_rowid());
+        }
+
+        let mut insert = tx.prepare(
+            "INSERT INTO chunks (file_id, start_line, end_line, tokens, content) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for chunk in chunks {
+            let Some(file_id) = ids.get(&chunk.path) else { continue };
+            insert.execute(params![
+                file_id,
+                chunk.start_line as i64,
+                chunk.end_line as i64,
+                chunk.tokens as i64,
+                chunk.text,
+            ])?;
+        }
+    }
+    tx.commit()
+}
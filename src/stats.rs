@@ -0,0 +1,146 @@
+// stats.rs
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::language::{self, Language};
+use crate::tree::format_size;
+use crate::SourceFile;
+
+#[derive(Default)]
+struct LanguageStats {
+    files: usize,
+    lines: usize,
+    code: usize,
+    comments: usize,
+    blanks: usize,
+}
+
+impl LanguageStats {
+    fn add(&mut self, other: &LanguageStats) {
+        self.files += other.files;
+        self.lines += other.lines;
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+    }
+}
+
+/// Summarize lines of code, comments and blanks per language (tokei-style)
+pub fn language_summary(files: &[SourceFile]) -> String {
+    let mut by_language: BTreeMap<&str, LanguageStats> = BTreeMap::new();
+    for file in files {
+        let language = language::of(file);
+        let stats = count_lines(&file.content, language);
+        by_language
+            .entry(language.map(|l| l.name).unwrap_or("Other"))
+            .or_default()
+            .add(&stats);
+    }
+
+    // Largest languages first
+    let mut rows: Vec<(&str, LanguageStats)> = by_language.into_iter().collect();
+    rows.sort_by_key(|(_, stats)| Reverse(stats.code));
+
+    let mut out = String::new();
+    let rule = "-".repeat(70);
+    let _ = writeln!(out, "{:<20} {:>8} {:>10} {:>10} {:>10} {:>8}",
+        "Language", "Files", "Lines", "Code", "Comments", "Blanks");
+    let _ = writeln!(out, "{}", rule);
+
+    let mut total = LanguageStats::default();
+    for (name, stats) in &rows {
+        write_row(&mut out, name, stats);
+        total.add(stats);
+    }
+
+    let _ = writeln!(out, "{}", rule);
+    write_row(&mut out, "Total", &total);
+    out
+}
+
+#[derive(Default)]
+struct ExtensionStats {
+    files: usize,
+    bytes: u64,
+    tokens: usize,
+}
+
+/// Files, bytes and tokens per extension, largest token share first
+pub fn extension_breakdown(files: &[SourceFile]) -> String {
+    let mut by_extension: BTreeMap<String, ExtensionStats> = BTreeMap::new();
+    for file in files {
+        let extension = file
+            .relative_path
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy().to_lowercase()))
+            .unwrap_or_else(|| "(none)".to_string());
+        let stats = by_extension.entry(extension).or_default();
+        stats.files += 1;
+        stats.bytes += file.size;
+        stats.tokens += file.tokens;
+    }
+
+    let mut rows: Vec<(String, ExtensionStats)> = by_extension.into_iter().collect();
+    rows.sort_by_key(|(_, stats)| Reverse(stats.tokens));
+    let total_tokens: usize = rows.iter().map(|(_, stats)| stats.tokens).sum();
+
+    let mut out = String::new();
+    let rule = "-".repeat(70);
+    let _ = writeln!(out, "{:<20} {:>8} {:>12} {:>12} {:>8}",
+        "Extension", "Files", "Size", "Tokens", "Share");
+    let _ = writeln!(out, "{}", rule);
+    for (extension, stats) in &rows {
+        let share = if total_tokens == 0 {
+            0.0
+        } else {
+            stats.tokens as f64 * 100.0 / total_tokens as f64
+        };
+        let _ = writeln!(out, "{:<20} {:>8} {:>12} {:>12} {:>7.1}%",
+            extension, stats.files, format_size(stats.bytes), stats.tokens, share);
+    }
+    out
+}
+
+fn write_row(out: &mut String, name: &str, stats: &LanguageStats) {
+    let _ = writeln!(out, "{:<20} {:>8} {:>10} {:>10} {:>10} {:>8}",
+        name, stats.files, stats.lines, stats.code, stats.comments, stats.blanks);
+}
+
+fn count_lines(content: &str, language: Option<&Language>) -> LanguageStats {
+    let mut stats = LanguageStats { files: 1, ..LanguageStats::default() };
+    let (line_comments, block_comments) = match language {
+        Some(l) => (l.line_comments, l.block_comments),
+        None => (&[][..], &[][..]),
+    };
+
+    // Closing delimiter of the block comment we are currently inside, if any
+    let mut in_block: Option<&str> = None;
+    for line in content.lines() {
+        stats.lines += 1;
+        let trimmed = line.trim();
+
+        if let Some(end) = in_block {
+            stats.comments += 1;
+            if trimmed.contains(end) {
+                in_block = None;
+            }
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            stats.blanks += 1;
+        } else if line_comments.iter().any(|prefix| trimmed.starts_with(prefix)) {
+            stats.comments += 1;
+        } else if let Some((start, end)) = block_comments.iter().find(|(start, _)| trimmed.starts_with(start)) {
+            stats.comments += 1;
+            if !trimmed[start.len()..].contains(end) {
+                in_block = Some(end);
+            }
+        } else {
+            stats.code += 1;
+        }
+    }
+
+    stats
+}
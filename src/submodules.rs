@@ -0,0 +1,32 @@
+// submodules.rs
+//! Git submodules: summarized as one tree entry like vendored code unless
+//! `--submodules` is given, when they're walked like any other directory
+//! (with their own .gitignore files).
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Whether a directory is the checkout of a submodule, whose `.git` is a file
+/// pointing into the superproject's .git/modules (a worktree's points elsewhere)
+pub fn is_submodule(dir: &Path) -> bool {
+    match fs::read_to_string(dir.join(".git")) {
+        Ok(link) => link.starts_with("gitdir:") && link.replace('\\', "/").contains("/modules/"),
+        Err(_) => false,
+    }
+}
+
+/// Paths of the submodules declared in the .gitmodules at `root`
+pub fn declared(root: &Path) -> Vec<PathBuf> {
+    let Ok(gitmodules) = fs::read_to_string(root.join(".gitmodules")) else { return Vec::new() };
+    gitmodules
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "path").then(|| PathBuf::from(value.trim()))
+        })
+        .collect()
+}
+
+/// A declared submodule that was never checked out is just an empty directory
+pub fn is_uninitialized(dir: &Path) -> bool {
+    fs::read_dir(dir).map(|mut entries| entries.next().is_none()).unwrap_or(false)
+}
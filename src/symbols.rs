@@ -0,0 +1,175 @@
+// symbols.rs
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+use tree_sitter::Node;
+
+use crate::language::{self, Language};
+use crate::skeleton;
+use crate::SourceFile;
+
+/// A named definition and where it is
+pub struct Symbol {
+    pub name: String,
+    pub kind: &'static str,
+    pub path: PathBuf,
+    /// 1-based line in the emitted content
+    pub line: usize,
+}
+
+/// Definition node kinds and the symbol kind they produce
+const DEFINITIONS: &[(&str, &str)] = &[
+    // Rust
+    ("function_item", "function"),
+    ("function_signature_item", "function"),
+    ("struct_item", "struct"),
+    ("enum_item", "enum"),
+    ("union_item", "union"),
+    ("trait_item", "trait"),
+    ("type_item", "type"),
+    ("const_item", "const"),
+    ("static_item", "static"),
+    ("mod_item", "module"),
+    ("macro_definition", "macro"),
+    // Python
+    ("function_definition", "function"),
+    ("class_definition", "class"),
+    // JavaScript / TypeScript
+    ("function_declaration", "function"),
+    ("generator_function_declaration", "function"),
+    ("class_declaration", "class"),
+    ("abstract_class_declaration", "class"),
+    ("method_definition", "method"),
+    ("interface_declaration", "interface"),
+    ("type_alias_declaration", "type"),
+    ("enum_declaration", "enum"),
+    // Go
+    ("method_declaration", "method"),
+    ("type_spec", "type"),
+    ("const_spec", "const"),
+];
+
+/// Nodes whose definitions are qualified with the container's name
+const CONTAINERS: &[&str] = &[
+    "impl_item", "trait_item", "mod_item", "class_definition", "class_declaration",
+    "abstract_class_declaration", "class",
+];
+
+/// Every function, type and constant defined in the files
+pub fn collect(files: &[SourceFile]) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    for file in files {
+        let Some(language) = language::of(file) else { continue };
+        let Some(tree) = skeleton::parse(&file.content, language) else { continue };
+        let mut found = Vec::new();
+        visit(tree.root_node(), &file.content, language, None, &mut found);
+        symbols.extend(found.into_iter().map(|(name, kind, line)| Symbol {
+            name,
+            kind,
+            path: file.relative_path.clone(),
+            line,
+        }));
+    }
+    symbols
+}
+
+fn visit(
+    node: Node,
+    source: &str,
+    language: &Language,
+    container: Option<&str>,
+    found: &mut Vec<(String, &'static str, usize)>,
+) {
+    let separator = if language.name == "Rust" { "::" } else { "." };
+    let qualify = |name: &str| match container {
+        Some(container) => format!("{}{}{}", container, separator, name),
+        None => name.to_string(),
+    };
+    let text = |n: Node| source[n.byte_range()].to_string();
+
+    let kind = node.kind();
+    if let Some((_, symbol_kind)) = DEFINITIONS.iter().find(|(k, _)| *k == kind) {
+        if let Some(name) = node.child_by_field_name("name") {
+            let symbol_kind = if *symbol_kind == "function" && container.is_some() { "method" } else { symbol_kind };
+            found.push((qualify(&text(name)), symbol_kind, node.start_position().row + 1));
+        }
+    } else if let Some(symbol) = top_level_binding(node, source, language) {
+        found.push((symbol.0, symbol.1, node.start_position().row + 1));
+    }
+
+    // Methods are named after the type they belong to
+    let scope = if CONTAINERS.contains(&kind) {
+        node.child_by_field_name("name")
+            .or_else(|| node.child_by_field_name("type"))
+            .map(|n| qualify(&text(n)))
+    } else {
+        None
+    };
+    let container = scope.as_deref().or(container);
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        visit(child, source, language, container, found);
+    }
+}
+
+/// Module-level `const` bindings in JavaScript/TypeScript and UPPER_CASE
+/// assignments in Python
+fn top_level_binding(node: Node, source: &str, language: &Language) -> Option<(String, &'static str)> {
+    let parent = node.parent()?;
+    let at_top = |n: Node| n.parent().is_some_and(|p| matches!(p.kind(), "program" | "module" | "export_statement"));
+
+    match node.kind() {
+        "variable_declarator" if parent.kind() == "lexical_declaration" && at_top(parent) => {
+            if !source[parent.byte_range()].starts_with("const") {
+                return None;
+            }
+            let name = &source[node.child_by_field_name("name")?.byte_range()];
+            let is_function = node
+                .child_by_field_name("value")
+                .is_some_and(|v| matches!(v.kind(), "arrow_function" | "function_expression" | "function"));
+            Some((name.to_string(), if is_function { "function" } else { "const" }))
+        }
+        "assignment" if language.name == "Python" && parent.kind() == "expression_statement" && at_top(parent) => {
+            let left = node.child_by_field_name("left")?;
+            let name = &source[left.byte_range()];
+            let is_constant = left.kind() == "identifier"
+                && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+            is_constant.then(|| (name.to_string(), "const"))
+        }
+        _ => None,
+    }
+}
+
+/// Symbols grouped by file, one `line kind name` row each
+pub fn render_text(symbols: &[Symbol]) -> String {
+    let mut out = String::new();
+    let mut current: Option<&PathBuf> = None;
+    for symbol in symbols {
+        if current != Some(&symbol.path) {
+            if current.is_some() {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "{}", symbol.path.display());
+            current = Some(&symbol.path);
+        }
+        let _ = writeln!(out, "  {:>5}  {:<10} {}", symbol.line, symbol.kind, symbol.name);
+    }
+    out
+}
+
+pub fn render_json(symbols: &[Symbol]) -> String {
+    let entries: Vec<Value> = symbols
+        .iter()
+        .map(|s| {
+            json!({
+                "name": s.name,
+                "kind": s.kind,
+                "path": s.path.to_string_lossy(),
+                "line": s.line,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).unwrap_or_default()
+}
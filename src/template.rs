@@ -0,0 +1,69 @@
+// template.rs
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use tera::{Context, Tera};
+
+use crate::language;
+use crate::stats;
+use crate::SourceFile;
+
+/// Everything a prompt template can refer to
+pub struct TemplateData<'a> {
+    pub files: &'a [SourceFile],
+    pub file_tree: &'a str,
+    /// The combined output in the selected format
+    pub combined: &'a str,
+    /// The question, when asking
+    pub question: Option<&'a str>,
+}
+
+/// Render a Tera template. Variables: `file_tree`, `files` (each with `path`,
+/// `language`, `content`, `size`, `tokens`), `stats`, `combined`,
+/// `total_tokens`, `file_count` and, for `ask`, `question`.
+pub fn render(template_path: &Path, data: &TemplateData) -> io::Result<String> {
+    let template = fs::read_to_string(template_path)?;
+
+    let files: Vec<_> = data
+        .files
+        .iter()
+        .map(|f| {
+            json!({
+                "path": f.relative_path.to_string_lossy().replace('\\', "/"),
+                "language": language::of(f).map(|l| l.name),
+                "size": f.size,
+                "tokens": f.tokens,
+                "content": f.content,
+            })
+        })
+        .collect();
+    let mut context = Context::new();
+    context.insert("files", &files);
+    context.insert("file_tree", data.file_tree);
+    context.insert("stats", &format!("{}\n{}", stats::language_summary(data.files), stats::extension_breakdown(data.files)));
+    context.insert("combined", data.combined);
+    context.insert("total_tokens", &data.files.iter().map(|f| f.tokens).sum::<usize>());
+    context.insert("file_count", &data.files.len());
+    context.insert("question", &data.question);
+
+    // Prompts are plain text, so nothing is HTML-escaped
+    Tera::one_off(&template, &context, false).map_err(|e| {
+        // The cause says what went wrong, the error itself only names the one-off template
+        let cause = std::error::Error::source(&e).map(|c| c.to_string()).unwrap_or_else(|| e.to_string());
+        io::Error::new(io::ErrorKind::InvalidInput, format!("Error in template {}: {}", template_path.display(), cause))
+    })
+}
+
+/// Where the rendered prompt goes: `review.md.tera` becomes `review.md`,
+/// `prompt.tera` becomes `prompt.txt`
+pub fn output_name(template_path: &Path) -> PathBuf {
+    let name = template_path.file_name().unwrap_or_default().to_string_lossy();
+    let name = name.strip_suffix(".tera").unwrap_or(&name);
+    if Path::new(name).extension().is_some() {
+        PathBuf::from(name)
+    } else {
+        PathBuf::from(format!("{}.txt", name))
+    }
+}
@@ -0,0 +1,101 @@
+// testfiles.rs
+use std::path::Path;
+use std::str::FromStr;
+
+/// Which files `--tests` lets through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestFilter {
+    Include,
+    Exclude,
+    Only,
+}
+
+impl FromStr for TestFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "include" => Ok(TestFilter::Include),
+            "exclude" => Ok(TestFilter::Exclude),
+            "only" => Ok(TestFilter::Only),
+            _ => Err(format!("Unknown test filter '{}' (expected include, exclude or only)", s)),
+        }
+    }
+}
+
+impl TestFilter {
+    /// Why the file is left out, if it is
+    pub fn skip_reason(&self, relative_path: &Path) -> Option<&'static str> {
+        match (self, is_test(relative_path)) {
+            (TestFilter::Exclude, true) => Some("test"),
+            (TestFilter::Only, false) => Some("not a test"),
+            _ => None,
+        }
+    }
+}
+
+/// Directories of recorded test output, skipped whatever their size
+const SNAPSHOT_DIRS: &[&str] = &["__snapshots__"];
+
+/// Directories of test inputs, skipped above `FIXTURE_MAX_SIZE`
+const FIXTURE_DIRS: &[&str] = &["fixtures", "__fixtures__", "testdata", "test-data", "test_data"];
+
+/// Small fixtures usually show what the tests exercise; large ones are recorded data
+const FIXTURE_MAX_SIZE: u64 = 10 * 1024;
+
+/// Why a snapshot or large fixture file is left out, if it is one. These are
+/// machine-written and rarely worth their tokens.
+pub fn fixture_reason(relative_path: &Path, size: u64) -> Option<&'static str> {
+    let names: Vec<String> = relative_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let (file_name, dirs) = names.split_last()?;
+    let extension = file_name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+    if matches!(extension, "snap" | "snapshot" | "golden") || dirs.iter().any(|d| SNAPSHOT_DIRS.contains(&d.as_str())) {
+        return Some("snapshot");
+    }
+    if size > FIXTURE_MAX_SIZE && dirs.iter().any(|d| FIXTURE_DIRS.contains(&d.as_str())) {
+        return Some("large fixture");
+    }
+    None
+}
+
+/// Directories that only hold tests
+const TEST_DIRS: &[&str] = &["tests", "test", "__tests__", "spec", "specs"];
+
+/// Whether the file is a test by the usual convention of its ecosystem
+pub fn is_test(relative_path: &Path) -> bool {
+    let mut components: Vec<String> = relative_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let Some(file_name) = components.pop() else {
+        return false;
+    };
+    if components.iter().any(|dir| TEST_DIRS.contains(&dir.as_str())) {
+        return true;
+    }
+
+    let (stem, extension) = file_name.rsplit_once('.').unwrap_or((&file_name, ""));
+    match extension {
+        // foo_test.go
+        "go" => stem.ends_with("_test"),
+        // test_foo.py, foo_test.py, conftest.py
+        "py" => stem.starts_with("test_") || stem.ends_with("_test") || stem == "conftest",
+        // foo.test.ts, foo.spec.js
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "mts" | "cts" => stem.ends_with(".test") || stem.ends_with(".spec"),
+        // foo_spec.rb, foo_test.rb
+        "rb" => stem.ends_with("_spec") || stem.ends_with("_test"),
+        // FooTest.java, FooTests.kt, FooSpec.scala
+        "java" | "kt" | "scala" | "cs" | "swift" | "php" => {
+            stem.ends_with("Test") || stem.ends_with("Tests") || stem.ends_with("Spec")
+        }
+        // foo_test.cpp, test_foo.c
+        "c" | "cc" | "cpp" | "cxx" => stem.ends_with("_test") || stem.starts_with("test_"),
+        // tests.rs, or foo_test.rs next to integration tests
+        "rs" => stem == "tests" || stem.ends_with("_test") || stem.ends_with("_tests"),
+        "exs" => stem.ends_with("_test"),
+        _ => false,
+    }
+}
@@ -0,0 +1,139 @@
+// timings.rs
+//! `--timings`: where a run's time goes (walking, reading, tokenizing, ...)
+//! and which files and directories took longest, written to timings.txt.
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Files and directories listed as the slowest
+const SLOWEST: usize = 10;
+
+/// Phases in the order a run goes through them
+const PHASES: &[&str] = &["walking", "reading", "tokenizing", "processing", "selecting and sorting", "writing"];
+
+/// Time per phase and per file, recorded only when enabled so the calls can
+/// stay in place. Files are processed on several threads, so the per-file
+/// phases add up the time of all of them.
+#[derive(Debug, Default)]
+pub struct Timings {
+    enabled: bool,
+    threads: usize,
+    phases: Mutex<Vec<(&'static str, Duration)>>,
+    files: Mutex<Vec<(PathBuf, Duration)>>,
+}
+
+impl Timings {
+    pub fn new(enabled: bool, threads: usize) -> Self {
+        Timings { enabled, threads, ..Default::default() }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Add to a phase's total
+    pub fn add(&self, phase: &'static str, elapsed: Duration) {
+        if !self.enabled {
+            return;
+        }
+        let mut phases = self.phases.lock().unwrap();
+        match phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += elapsed,
+            None => phases.push((phase, elapsed)),
+        }
+    }
+
+    /// Everything spent on one file, from the checks to its token count
+    pub fn file(&self, relative_path: &Path, elapsed: Duration) {
+        if self.enabled {
+            self.files.lock().unwrap().push((relative_path.to_path_buf(), elapsed));
+        }
+    }
+
+    pub fn files_total(&self) -> Duration {
+        self.files.lock().unwrap().iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+
+    fn phase(&self, phase: &str) -> Duration {
+        self.phases.lock().unwrap().iter().find(|(name, _)| *name == phase).map(|(_, elapsed)| *elapsed).unwrap_or_default()
+    }
+
+    /// One line for the console
+    pub fn summary(&self, total: Duration) -> String {
+        let phases: Vec<String> = self.phases(total).iter().map(|(name, elapsed)| format!("{} {}", name, format_duration(*elapsed))).collect();
+        format!("Took {}: {}", format_duration(total), phases.join(", "))
+    }
+
+    /// Per-file work other than reading and tokenizing shows up as "processing",
+    /// and the rest of the run (loading settings, ...) as "other"
+    fn phases(&self, total: Duration) -> Vec<(&'static str, Duration)> {
+        let per_file = self.phase("reading") + self.phase("tokenizing");
+        let processing = self.files_total().saturating_sub(per_file);
+        let mut phases: Vec<(&'static str, Duration)> = PHASES
+            .iter()
+            .map(|name| (*name, if *name == "processing" { processing } else { self.phase(name) }))
+            .filter(|(_, elapsed)| !elapsed.is_zero())
+            .collect();
+        let accounted: Duration = phases.iter().map(|(_, elapsed)| *elapsed).sum();
+        let other = total.saturating_sub(accounted);
+        if !other.is_zero() {
+            phases.push(("other", other));
+        }
+        phases
+    }
+
+    /// timings.txt
+    pub fn report(&self, total: Duration) -> String {
+        let mut out = format!("Total: {}\n\n", format_duration(total));
+        if self.threads > 1 {
+            let _ = writeln!(out, "Reading, tokenizing and processing add up {} threads, so they can exceed the total.\n", self.threads);
+        }
+        let phases = self.phases(total);
+        let width = phases.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        for (name, elapsed) in &phases {
+            let share = elapsed.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON) * 100.0;
+            let _ = writeln!(out, "{:<width$}  {:>9}  {:>3.0}%", name, format_duration(*elapsed), share, width = width);
+        }
+
+        let mut files = self.files.lock().unwrap().clone();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        if !files.is_empty() {
+            out.push_str("\nSlowest files:\n");
+            for (path, elapsed) in files.iter().take(SLOWEST) {
+                let _ = writeln!(out, "  {:>9}  {}", format_duration(*elapsed), display(path));
+            }
+        }
+
+        let mut dirs: HashMap<PathBuf, (Duration, usize)> = HashMap::new();
+        for (path, elapsed) in &files {
+            let entry = dirs.entry(path.parent().map(Path::to_path_buf).unwrap_or_default()).or_default();
+            entry.0 += *elapsed;
+            entry.1 += 1;
+        }
+        let mut dirs: Vec<(PathBuf, (Duration, usize))> = dirs.into_iter().collect();
+        dirs.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(&b.0)));
+        if !dirs.is_empty() {
+            out.push_str("\nSlowest directories:\n");
+            for (dir, (elapsed, count)) in dirs.iter().take(SLOWEST) {
+                let name = if dir.as_os_str().is_empty() { ".".to_string() } else { format!("{}/", display(dir)) };
+                let plural = if *count == 1 { "" } else { "s" };
+                let _ = writeln!(out, "  {:>9}  {} ({} file{})", format_duration(*elapsed), name, count, plural);
+            }
+        }
+        out
+    }
+}
+
+fn format_duration(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(1) {
+        format!("{:.1}ms", elapsed.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.2}s", elapsed.as_secs_f64())
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
@@ -0,0 +1,87 @@
+// todos.rs
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use regex::Regex;
+
+use crate::language;
+use crate::SourceFile;
+
+/// Comment markers for known problems, in the order they're summarized
+const MARKERS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+/// Comment openers assumed for files without a known language
+const FALLBACK_COMMENTS: &[&str] = &["//", "#", "/*", "--"];
+
+struct Todo<'a> {
+    path: String,
+    line: usize,
+    marker: &'a str,
+    text: &'a str,
+}
+
+/// Every TODO, FIXME, HACK and XXX comment as `path:line: text`, with a count per marker
+pub fn render(files: &[SourceFile]) -> String {
+    // A marker starts a note when a colon or a parenthesized name follows it, or when
+    // it's the comment's first word; prose that mentions one in passing doesn't count
+    let pattern = Regex::new(r"\b(TODO|FIXME|HACK|XXX)(\([^)]*\))?(:|\s|$)").expect("invalid todo pattern");
+    let mut todos = Vec::new();
+    for file in files {
+        let openers: Vec<&str> = match language::of(file) {
+            Some(language) => language
+                .line_comments
+                .iter()
+                .copied()
+                .chain(language.block_comments.iter().map(|(start, _)| *start))
+                .collect(),
+            None => FALLBACK_COMMENTS.to_vec(),
+        };
+        if openers.is_empty() {
+            continue;
+        }
+        for (i, line) in file.content.lines().enumerate() {
+            let in_block = line.trim_start().starts_with('*');
+            let found = pattern.captures_iter(line).find_map(|captures| {
+                let found = captures.get(0)?;
+                let before = &line[..found.start()];
+                // Only comments count, not identifiers or strings that mention the word
+                let comment = match openers.iter().filter_map(|opener| before.rfind(opener).map(|at| at + opener.len())).max() {
+                    Some(start) => &before[start..],
+                    None if in_block => before,
+                    None => return None,
+                };
+                let first_word = comment.trim_start_matches(|c: char| c.is_whitespace() || "/*!#-".contains(c)).is_empty();
+                let tagged = captures.get(2).is_some() || &captures[3] == ":";
+                (first_word || tagged).then_some(found)
+            });
+            let Some(found) = found else {
+                continue;
+            };
+            todos.push(Todo {
+                path: file.relative_path.to_string_lossy().replace('\\', "/"),
+                line: i + 1,
+                marker: MARKERS.iter().find(|m| found.as_str().starts_with(**m)).copied().unwrap_or("TODO"),
+                text: line[found.start()..].trim_end().trim_end_matches("*/").trim_end(),
+            });
+        }
+    }
+
+    let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+    for todo in &todos {
+        let index = MARKERS.iter().position(|m| *m == todo.marker).unwrap_or(0);
+        *counts.entry(index).or_insert(0) += 1;
+    }
+    let summary: Vec<String> = counts.iter().map(|(i, count)| format!("{} {}", count, MARKERS[*i])).collect();
+
+    let mut out = String::new();
+    if todos.is_empty() {
+        out.push_str("No TODO, FIXME, HACK or XXX comments found\n");
+        return out;
+    }
+    let _ = writeln!(out, "{} comments ({})", todos.len(), summary.join(", "));
+    let _ = writeln!(out);
+    for todo in &todos {
+        let _ = writeln!(out, "{}:{}: {}", todo.path, todo.line, todo.text);
+    }
+    out
+}
@@ -0,0 +1,172 @@
+// tokens.rs
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::language::Language;
+#[cfg(feature = "tree-sitter")]
+use crate::skeleton;
+
+/// Characters per token used when no model preset is selected
+pub const DEFAULT_CHARS_PER_TOKEN: f64 = 4.0;
+
+/// Rough token estimate for source code based on a characters-per-token ratio
+pub fn estimate_tokens(content: &str, chars_per_token: f64) -> usize {
+    (content.chars().count() as f64 / chars_per_token).ceil() as usize
+}
+
+/// What happens to a file over --max-tokens-per-file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverLimit {
+    Skip,
+    Truncate,
+}
+
+impl FromStr for OverLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(OverLimit::Skip),
+            "truncate" => Ok(OverLimit::Truncate),
+            _ => Err(format!("Unknown over-limit action '{}' (expected skip or truncate)", s)),
+        }
+    }
+}
+
+/// Cut a file down to `max_tokens`, for languages with a grammar by eliding
+/// function bodies first so the rest of its structure survives, and by whole
+/// lines for everything else (or, last, when the signatures alone are too many)
+pub fn truncate_source(content: &str, language: Option<&Language>, max_tokens: usize, chars_per_token: f64) -> String {
+    let max_chars = (max_tokens as f64 * chars_per_token) as usize;
+    let Some((elided, bodies)) = language.and_then(|l| elide_bodies(content, l, max_chars)).filter(|(_, bodies)| *bodies > 0) else {
+        return truncate(content, max_tokens, chars_per_token);
+    };
+    let (mut out, omitted) = if estimate_tokens(&elided, chars_per_token) <= max_tokens {
+        (elided, 0)
+    } else {
+        whole_lines(&elided, max_chars)
+    };
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    let lines = if omitted > 0 { format!(", {} more line{} omitted", omitted, if omitted == 1 { "" } else { "s" }) } else { String::new() };
+    out.push_str(&format!(
+        "... (truncated to ~{} of ~{} tokens, {} function bod{} elided{}) ...\n",
+        estimate_tokens(&out, chars_per_token),
+        estimate_tokens(content, chars_per_token),
+        bodies,
+        if bodies == 1 { "y" } else { "ies" },
+        lines
+    ));
+    out
+}
+
+/// Function bodies elided largest first until `content` fits in `max_chars`,
+/// or every body and everything but the signatures when that isn't enough,
+/// with how many bodies went
+#[cfg(feature = "tree-sitter")]
+fn elide_bodies(content: &str, language: &Language, max_chars: usize) -> Option<(String, usize)> {
+    let fitted = skeleton::fit(content, language, max_chars)?;
+    let size = |(text, _): &(String, usize)| text.chars().count();
+    if size(&fitted) <= max_chars {
+        return Some(fitted);
+    }
+    match skeleton::signatures(content, language) {
+        Some(signatures) if size(&signatures) < size(&fitted) => Some(signatures),
+        _ => Some(fitted),
+    }
+}
+
+/// Without the grammars everything is cut by whole lines
+#[cfg(not(feature = "tree-sitter"))]
+fn elide_bodies(_content: &str, _language: &Language, _max_chars: usize) -> Option<(String, usize)> {
+    None
+}
+
+/// Which files --token-budget keeps when not all of them fit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetBy {
+    /// The first ones in output order
+    Order,
+    /// The most central ones in the import graph, so hub modules don't lose
+    /// their place to leaf utilities that happen to sort first
+    Centrality,
+}
+
+impl FromStr for BudgetBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "order" => Ok(BudgetBy::Order),
+            "centrality" => Ok(BudgetBy::Centrality),
+            _ => Err(format!("Unknown budget selection '{}' (expected order or centrality)", s)),
+        }
+    }
+}
+
+/// `--budgets` entries as DIR=TOKENS, directories without their trailing slash
+pub fn parse_directory_budgets(entries: &[String]) -> Result<Vec<(PathBuf, usize)>, String> {
+    entries
+        .iter()
+        .map(|entry| {
+            let invalid = || format!("Invalid directory budget '{}' (expected DIR=TOKENS, e.g. src/=60000)", entry);
+            let (dir, tokens) = entry.rsplit_once('=').ok_or_else(invalid)?;
+            let dir = dir.trim().trim_start_matches("./").trim_end_matches(['/', '\\']);
+            let tokens = tokens.trim().parse().map_err(|_| invalid())?;
+            Ok((PathBuf::from(if dir == "." { "" } else { dir }), tokens))
+        })
+        .collect()
+}
+
+/// The most specific directory with a budget that `path` is in
+pub fn budget_directory<'a>(path: &Path, budgets: &'a [(PathBuf, usize)]) -> Option<&'a Path> {
+    budgets
+        .iter()
+        .filter(|(dir, _)| path.starts_with(dir))
+        .max_by_key(|(dir, _)| dir.components().count())
+        .map(|(dir, _)| dir.as_path())
+}
+
+/// How a budgeted directory is shown, "src/" or "./" for the whole tree
+pub fn directory_label(dir: &Path) -> String {
+    if dir.as_os_str().is_empty() { "./".to_string() } else { format!("{}/", dir.to_string_lossy().replace('\\', "/")) }
+}
+
+/// The whole lines that fit in `max_tokens`, then a note on how much was cut
+pub fn truncate(content: &str, max_tokens: usize, chars_per_token: f64) -> String {
+    let max_chars = (max_tokens as f64 * chars_per_token) as usize;
+    let (mut out, omitted) = whole_lines(content, max_chars);
+    out.push_str(&format!(
+        "... (truncated to ~{} of ~{} tokens, {} more line{} omitted) ...\n",
+        max_tokens,
+        estimate_tokens(content, chars_per_token),
+        omitted,
+        if omitted == 1 { "" } else { "s" }
+    ));
+    out
+}
+
+/// The whole lines that fit in `max_chars` and how many were left out
+fn whole_lines(content: &str, max_chars: usize) -> (String, usize) {
+    let mut out = String::new();
+    let mut chars = 0;
+    let mut kept = 0;
+    for line in content.lines() {
+        let length = line.chars().count() + 1;
+        if chars + length > max_chars {
+            // A first line over the limit on its own (minified code) is cut short
+            if kept == 0 {
+                out.extend(line.chars().take(max_chars));
+                out.push('\n');
+                kept = 1;
+            }
+            break;
+        }
+        out.push_str(line);
+        out.push('\n');
+        chars += length;
+        kept += 1;
+    }
+    (out, content.lines().count() - kept)
+}
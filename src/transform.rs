@@ -0,0 +1,356 @@
+// transform.rs
+use regex::Regex;
+
+use crate::language::Language;
+
+enum State<'a> {
+    Code,
+    /// Inside a block comment, waiting for its closing delimiter
+    Block(&'a str),
+    /// Inside a string literal opened with this quote
+    Str(char),
+}
+
+/// Characters that open string literals, so comment markers inside strings are kept
+fn string_quotes(language: &Language) -> &'static [char] {
+    match language.name {
+        "Rust" | "C" | "C++" | "C#" | "Java" | "Kotlin" | "Scala" | "Swift" | "Go" | "Haskell" => &['"'],
+        "JavaScript" | "JSX" | "TypeScript" | "TSX" | "Vue" | "Svelte" => &['"', '\'', '`'],
+        _ => &['"', '\''],
+    }
+}
+
+/// Length of a character literal (`'x'`, `'\n'`) at the start of `rest`,
+/// for languages where single quotes don't delimit strings
+fn char_literal_len(rest: &str, quotes: &[char]) -> Option<usize> {
+    if quotes.contains(&'\'') || !rest.starts_with('\'') {
+        return None;
+    }
+    let mut chars = rest.char_indices().skip(1);
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        // Escapes like '\n', '\'' or '\u{1F600}'
+        chars.next()?;
+        return chars.take(10).find(|(_, c)| *c == '\'').map(|(pos, _)| pos + 1);
+    }
+    match chars.next() {
+        Some((pos, '\'')) => Some(pos + 1),
+        _ => None,
+    }
+}
+
+/// Remove line and block comments using the language's comment syntax.
+/// Lines that only contained comments are dropped entirely.
+pub fn strip_comments(content: &str, language: &Language) -> String {
+    let quotes = string_quotes(language);
+    let mut state = State::Code;
+    let mut out = String::with_capacity(content.len());
+
+    for (line_number, line) in content.split_inclusive('\n').enumerate() {
+        let (body, newline) = match line.strip_suffix('\n') {
+            Some(body) => (body, "\n"),
+            None => (line, ""),
+        };
+
+        // Keep shebang lines
+        if line_number == 0 && body.starts_with("#!") {
+            out.push_str(line);
+            continue;
+        }
+
+        let mut kept = String::with_capacity(body.len());
+        let mut removed = false;
+        let mut i = 0;
+        while i < body.len() {
+            let rest = &body[i..];
+            match state {
+                State::Block(end) => {
+                    removed = true;
+                    match rest.find(end) {
+                        Some(pos) => {
+                            i += pos + end.len();
+                            state = State::Code;
+                        }
+                        None => i = body.len(),
+                    }
+                }
+                State::Str(quote) => {
+                    let c = rest.chars().next().unwrap_or_default();
+                    kept.push(c);
+                    i += c.len_utf8();
+                    if c == '\\' {
+                        if let Some(escaped) = body[i..].chars().next() {
+                            kept.push(escaped);
+                            i += escaped.len_utf8();
+                        }
+                    } else if c == quote {
+                        state = State::Code;
+                    }
+                }
+                State::Code => {
+                    if language.line_comments.iter().any(|prefix| rest.starts_with(prefix)) {
+                        removed = true;
+                        break;
+                    }
+
+                    // Quote-like block delimiters (Python docstrings) only count at line start
+                    let block = language.block_comments.iter().find(|(start, _)| {
+                        rest.starts_with(start)
+                            && (!start.starts_with(quotes) || body[..i].trim().is_empty())
+                    });
+                    if let Some((start, end)) = block {
+                        removed = true;
+                        i += start.len();
+                        state = State::Block(end);
+                        continue;
+                    }
+
+                    // Character literals such as '"' would otherwise open a string
+                    if let Some(len) = char_literal_len(rest, quotes) {
+                        kept.push_str(&rest[..len]);
+                        i += len;
+                        continue;
+                    }
+
+                    let c = rest.chars().next().unwrap_or_default();
+                    if quotes.contains(&c) {
+                        state = State::Str(c);
+                    }
+                    kept.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+
+        // Single-quoted strings never span lines, so a stray apostrophe can't swallow the file
+        if let State::Str('\'') = state {
+            state = State::Code;
+        }
+
+        if removed {
+            let trimmed = kept.trim_end();
+            if trimmed.trim_start().is_empty() {
+                continue;
+            }
+            out.push_str(trimmed);
+        } else {
+            out.push_str(&kept);
+        }
+        out.push_str(newline);
+    }
+
+    out
+}
+
+const LICENSE_MARKERS: &[&str] = &[
+    "copyright", "license", "spdx-license-identifier", "all rights reserved",
+    "permission is hereby granted",
+];
+
+/// Longest comment banner considered a license header
+const MAX_LICENSE_HEADER_LINES: usize = 60;
+
+/// Remove the leading comment block when it looks like a copyright/license banner
+pub fn strip_license_header(content: &str, language: &Language) -> String {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+
+    // Keep a shebang and find where the banner starts
+    let mut start = 0;
+    if lines.first().is_some_and(|l| l.starts_with("#!")) {
+        start = 1;
+    }
+    while start < lines.len() && lines[start].trim().is_empty() {
+        start += 1;
+    }
+
+    let end = match leading_comment_end(&lines[start..], language) {
+        Some(len) => start + len,
+        None => return content.to_string(),
+    };
+    if end - start > MAX_LICENSE_HEADER_LINES {
+        return content.to_string();
+    }
+
+    let banner = lines[start..end].concat().to_lowercase();
+    if !LICENSE_MARKERS.iter().any(|marker| banner.contains(marker)) {
+        return content.to_string();
+    }
+
+    // Drop blank lines that separated the banner from the code
+    let mut resume = end;
+    while resume < lines.len() && lines[resume].trim().is_empty() {
+        resume += 1;
+    }
+
+    let mut out = String::new();
+    if lines[0].starts_with("#!") {
+        out.push_str(lines[0]);
+    }
+    out.push_str(&lines[resume..].concat());
+    out
+}
+
+/// Number of lines in the comment block at the start of `lines`, if any
+fn leading_comment_end(lines: &[&str], language: &Language) -> Option<usize> {
+    let first = lines.first()?.trim_start();
+
+    if let Some((open, close)) = language.block_comments.iter().find(|(open, _)| first.starts_with(open)) {
+        return lines.iter().enumerate().position(|(i, line)| {
+            let search = if i == 0 { &first[open.len()..] } else { line };
+            search.contains(close)
+        })
+        .map(|pos| pos + 1);
+    }
+
+    let count = lines
+        .iter()
+        .take_while(|l| {
+            let l = l.trim_start();
+            language.line_comments.iter().any(|prefix| l.starts_with(prefix))
+        })
+        .count();
+    if count == 0 { None } else { Some(count) }
+}
+
+/// Only the lines matching `pattern` plus `context` lines around them, numbered
+/// like `grep -n` with `--` between separate groups
+pub fn matching_lines(content: &str, pattern: &Regex, context: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut keep = vec![false; lines.len()];
+    let mut matched = vec![false; lines.len()];
+    for (i, line) in lines.iter().enumerate() {
+        if pattern.is_match(line) {
+            matched[i] = true;
+            let end = (i + context).min(lines.len() - 1);
+            keep[i.saturating_sub(context)..=end].iter_mut().for_each(|k| *k = true);
+        }
+    }
+
+    let mut out = String::new();
+    let mut previous: Option<usize> = None;
+    for (i, line) in lines.iter().enumerate().filter(|(i, _)| keep[*i]) {
+        if previous.is_some_and(|p| p + 1 < i) {
+            out.push_str("--\n");
+        }
+        let separator = if matched[i] { ':' } else { '-' };
+        out.push_str(&format!("{}{} {}\n", i + 1, separator, line));
+        previous = Some(i);
+    }
+    out
+}
+
+/// Only the lines in `ranges` (1-based, inclusive), with a marker for each omitted stretch
+pub fn select_lines(content: &str, ranges: &[(usize, usize)]) -> String {
+    String::from_utf8(select_line_bytes(content.as_bytes(), ranges)).expect("whole lines of UTF-8 are UTF-8")
+}
+
+/// `select_lines` on bytes not decoded yet, so a large file is only decoded for
+/// the lines kept; lines end at `\n` in any ASCII-compatible encoding
+pub fn select_line_bytes(content: &[u8], ranges: &[(usize, usize)]) -> Vec<u8> {
+    let total = content.iter().filter(|b| **b == b'\n').count() + usize::from(!content.is_empty() && !content.ends_with(b"\n"));
+    let mut ranges: Vec<(usize, usize)> = ranges
+        .iter()
+        .filter(|(start, _)| *start <= total)
+        .map(|&(start, end)| (start, end.min(total)))
+        .collect();
+    ranges.sort();
+
+    let mut lines = content.split(|b| *b == b'\n').map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+    let mut out = Vec::new();
+    let mut next = 1;
+    for (start, end) in ranges {
+        if end < next {
+            continue;
+        }
+        let start = start.max(next);
+        if start > next {
+            out.extend_from_slice(format!("... (lines {}-{} omitted) ...\n", next, start - 1).as_bytes());
+        }
+        for line in lines.by_ref().skip(start - next).take(end - start + 1) {
+            out.extend_from_slice(line);
+            out.push(b'\n');
+        }
+        next = end + 1;
+    }
+    if next <= total {
+        out.extend_from_slice(format!("... (lines {}-{} omitted) ...\n", next, total).as_bytes());
+    }
+    out
+}
+
+/// Whitespace clean-ups applied to emitted content
+#[derive(Default)]
+pub struct WhitespaceOptions {
+    pub trim_trailing: bool,
+    pub tab_width: Option<usize>,
+    pub squeeze_blank_lines: bool,
+    pub normalize_line_endings: bool,
+}
+
+impl WhitespaceOptions {
+    pub fn is_noop(&self) -> bool {
+        !self.trim_trailing && self.tab_width.is_none() && !self.squeeze_blank_lines && !self.normalize_line_endings
+    }
+}
+
+pub fn normalize_whitespace(content: &str, options: &WhitespaceOptions, language: Option<&Language>) -> String {
+    let mut content = if options.normalize_line_endings {
+        content.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        content.to_string()
+    };
+
+    // Makefiles need their tabs
+    let tab_width = options.tab_width.filter(|_| language.is_none_or(|l| l.name != "Makefile"));
+
+    if options.trim_trailing || tab_width.is_some() || options.squeeze_blank_lines {
+        let mut out = String::with_capacity(content.len());
+        let mut previous_blank = false;
+        for line in content.split_inclusive('\n') {
+            let (body, newline) = match line.strip_suffix("\r\n") {
+                Some(body) => (body, "\r\n"),
+                None => match line.strip_suffix('\n') {
+                    Some(body) => (body, "\n"),
+                    None => (line, ""),
+                },
+            };
+
+            let mut body = match tab_width {
+                Some(width) => expand_tabs(body, width),
+                None => body.to_string(),
+            };
+            if options.trim_trailing {
+                body.truncate(body.trim_end().len());
+            }
+
+            let blank = body.trim().is_empty();
+            if options.squeeze_blank_lines && blank && previous_blank {
+                continue;
+            }
+            previous_blank = blank;
+
+            out.push_str(&body);
+            out.push_str(newline);
+        }
+        content = out;
+    }
+
+    content
+}
+
+/// Replace tabs with spaces up to the next tab stop
+fn expand_tabs(line: &str, width: usize) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = width - column % width.max(1);
+            out.extend(std::iter::repeat_n(' ', spaces));
+            column += spaces;
+        } else {
+            out.push(c);
+            column += 1;
+        }
+    }
+    out
+}
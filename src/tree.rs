@@ -0,0 +1,323 @@
+// tree.rs
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Diagram formats the tree can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Mermaid,
+}
+
+impl DiagramFormat {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            DiagramFormat::Mermaid => "file-tree.mmd",
+        }
+    }
+}
+
+impl FromStr for DiagramFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mermaid" => Ok(DiagramFormat::Mermaid),
+            _ => Err(format!("Unknown diagram format '{}' (expected mermaid)", s)),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+    is_dir: bool,
+    size: u64,
+    tokens: usize,
+    files: usize,
+    skipped: Option<String>,
+    /// Shown in brackets after the name, e.g. how the file changed since the last run
+    marker: Option<String>,
+}
+
+/// Directory tree collected during the walk and rendered once complete,
+/// so children are grouped under their directory regardless of walk order
+pub struct FileTree {
+    root: Node,
+    annotate: bool,
+}
+
+impl FileTree {
+    /// Create an empty tree; `annotate` adds sizes and token estimates to every entry
+    pub fn new(annotate: bool) -> Self {
+        FileTree {
+            root: Node { is_dir: true, ..Node::default() },
+            annotate,
+        }
+    }
+
+    pub fn add_dir(&mut self, path: &Path) {
+        self.node_mut(path, true);
+    }
+
+    pub fn add_file(&mut self, path: &Path, size: u64, tokens: usize) {
+        // Sizes are accumulated into every ancestor directory
+        let mut node = &mut self.root;
+        node.size += size;
+        node.tokens += tokens;
+        node.files += 1;
+        let names = component_names(path);
+        for (i, name) in names.iter().enumerate() {
+            let is_dir = i + 1 < names.len();
+            node = node.children.entry(name.clone()).or_insert_with(|| Node {
+                is_dir,
+                ..Node::default()
+            });
+            node.size += size;
+            node.tokens += tokens;
+            node.files += 1;
+        }
+    }
+
+    pub fn add_skipped(&mut self, path: &Path, reason: &str) {
+        self.node_mut(path, false).skipped = Some(reason.to_string());
+    }
+
+    /// Label an entry, adding it if it isn't in the tree (e.g. a deleted file)
+    pub fn mark(&mut self, path: &Path, marker: &str) {
+        self.node_mut(path, false).marker = Some(marker.to_string());
+    }
+
+    /// Add everything in a tree collected separately (e.g. by another thread)
+    pub fn merge(&mut self, other: FileTree) {
+        merge_node(&mut self.root, other.root);
+    }
+
+    /// Mark a previously added file as skipped and remove it from the totals
+    pub fn exclude_file(&mut self, path: &Path, size: u64, tokens: usize, reason: &str) {
+        let mut node = &mut self.root;
+        let names = component_names(path);
+        for name in &names {
+            node.size = node.size.saturating_sub(size);
+            node.tokens = node.tokens.saturating_sub(tokens);
+            node.files = node.files.saturating_sub(1);
+            node = match node.children.get_mut(name) {
+                Some(child) => child,
+                None => return,
+            };
+        }
+        node.size = 0;
+        node.tokens = 0;
+        node.files = 0;
+        node.skipped = Some(reason.to_string());
+    }
+
+    /// Remove a previously added file, along with directories left empty
+    pub fn remove_file(&mut self, path: &Path, size: u64, tokens: usize) {
+        fn remove(node: &mut Node, names: &[String], size: u64, tokens: usize) {
+            let Some((name, rest)) = names.split_first() else { return };
+            node.size = node.size.saturating_sub(size);
+            node.tokens = node.tokens.saturating_sub(tokens);
+            node.files = node.files.saturating_sub(1);
+            let Some(child) = node.children.get_mut(name) else { return };
+            if rest.is_empty() {
+                node.children.remove(name);
+                return;
+            }
+            remove(child, rest, size, tokens);
+            if child.children.is_empty() {
+                node.children.remove(name);
+            }
+        }
+        remove(&mut self.root, &component_names(path), size, tokens);
+    }
+
+    /// Rename every entry, e.g. to anonymize the paths; entries whose new names
+    /// coincide are merged
+    pub fn rename(&mut self, rename: &dyn Fn(&str) -> String) {
+        fn rename_node(node: &mut Node, rename: &dyn Fn(&str) -> String) {
+            for (name, mut child) in std::mem::take(&mut node.children) {
+                rename_node(&mut child, rename);
+                merge_node(node.children.entry(rename(&name)).or_default(), child);
+            }
+        }
+        rename_node(&mut self.root, rename);
+    }
+
+    /// Adjust the totals after a file's content was reduced
+    pub fn update_tokens(&mut self, path: &Path, before: usize, after: usize) {
+        let mut node = &mut self.root;
+        node.tokens = node.tokens - before + after;
+        for name in component_names(path) {
+            node = match node.children.get_mut(&name) {
+                Some(child) => child,
+                None => return,
+            };
+            node.tokens = node.tokens - before + after;
+        }
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.root.size
+    }
+
+    /// Whether entries are annotated with sizes and token estimates
+    pub fn annotates(&self) -> bool {
+        self.annotate
+    }
+
+    pub fn total_tokens(&self) -> usize {
+        self.root.tokens
+    }
+
+    pub fn render(&self) -> String {
+        self.render_folded(None)
+    }
+
+    /// Render with directories below `fold_depth` shown as a single line with
+    /// their file count, so deep trees stay readable without losing structure
+    pub fn render_folded(&self, fold_depth: Option<usize>) -> String {
+        let mut out = String::from(".");
+        if self.annotate {
+            out.push_str(&self.annotation(&self.root));
+        }
+        out.push('\n');
+        self.render_children(&self.root, "", 1, fold_depth, &mut out);
+        out
+    }
+
+    pub fn render_diagram(&self, format: DiagramFormat, collapse_depth: Option<usize>) -> String {
+        match format {
+            DiagramFormat::Mermaid => self.render_mermaid(collapse_depth),
+        }
+    }
+
+    /// Render as a Mermaid flowchart; directories deeper than `collapse_depth`
+    /// are shown as a single node with their file count
+    fn render_mermaid(&self, collapse_depth: Option<usize>) -> String {
+        let mut out = String::from("flowchart LR\n    n0[\".\"]\n");
+        let mut next_id = 1;
+        self.render_mermaid_children(&self.root, 0, 1, collapse_depth, &mut next_id, &mut out);
+        out
+    }
+
+    fn render_mermaid_children(
+        &self,
+        node: &Node,
+        parent_id: usize,
+        depth: usize,
+        collapse_depth: Option<usize>,
+        next_id: &mut usize,
+        out: &mut String,
+    ) {
+        let mut children: Vec<(&String, &Node)> = node.children.iter().collect();
+        children.sort_by_key(|(_, child)| !child.is_dir);
+
+        for (name, child) in children {
+            let id = *next_id;
+            *next_id += 1;
+
+            let collapsed = child.is_dir
+                && !child.children.is_empty()
+                && collapse_depth.is_some_and(|max| depth >= max);
+            let mut label = name.clone();
+            if child.is_dir {
+                label.push('/');
+            }
+            if collapsed {
+                label.push_str(&format!(" ({} files)", child.files));
+            }
+
+            out.push_str(&format!(
+                "    n{} --> n{}[\"{}\"]\n",
+                parent_id,
+                id,
+                label.replace('"', "#quot;")
+            ));
+
+            if child.is_dir && !collapsed {
+                self.render_mermaid_children(child, id, depth + 1, collapse_depth, next_id, out);
+            }
+        }
+    }
+
+    fn node_mut(&mut self, path: &Path, is_dir: bool) -> &mut Node {
+        let mut node = &mut self.root;
+        let names = component_names(path);
+        for (i, name) in names.iter().enumerate() {
+            let is_dir = is_dir || i + 1 < names.len();
+            node = node.children.entry(name.clone()).or_insert_with(|| Node {
+                is_dir,
+                ..Node::default()
+            });
+        }
+        node
+    }
+
+    fn render_children(&self, node: &Node, prefix: &str, depth: usize, fold_depth: Option<usize>, out: &mut String) {
+        // Directories first, then files, each group alphabetical
+        let mut children: Vec<(&String, &Node)> = node.children.iter().collect();
+        children.sort_by_key(|(_, child)| !child.is_dir);
+
+        let count = children.len();
+        for (i, (name, child)) in children.into_iter().enumerate() {
+            let last = i + 1 == count;
+            out.push_str(prefix);
+            out.push_str(if last { "└── " } else { "├── " });
+            out.push_str(name);
+            if child.is_dir {
+                out.push('/');
+            }
+            let folded = child.is_dir && !child.children.is_empty() && fold_depth.is_some_and(|max| depth >= max);
+            if folded {
+                out.push_str(&format!(" ({} files folded)", child.files));
+            }
+            if let Some(marker) = &child.marker {
+                out.push_str(&format!(" [{}]", marker));
+            }
+            if let Some(reason) = &child.skipped {
+                out.push_str(&format!(" (skipped - {})", reason));
+            } else if self.annotate {
+                out.push_str(&self.annotation(child));
+            }
+            out.push('\n');
+
+            if child.is_dir && !folded {
+                let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                self.render_children(child, &child_prefix, depth + 1, fold_depth, out);
+            }
+        }
+    }
+
+    fn annotation(&self, node: &Node) -> String {
+        format!(" ({}, ~{} tokens)", format_size(node.size), node.tokens)
+    }
+}
+
+fn merge_node(node: &mut Node, other: Node) {
+    node.is_dir |= other.is_dir;
+    node.size += other.size;
+    node.tokens += other.tokens;
+    node.files += other.files;
+    node.skipped = node.skipped.take().or(other.skipped);
+    node.marker = node.marker.take().or(other.marker);
+    for (name, child) in other.children {
+        merge_node(node.children.entry(name).or_default(), child);
+    }
+}
+
+fn component_names(path: &Path) -> Vec<String> {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect()
+}
+
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
@@ -0,0 +1,23 @@
+// vendored.rs
+use std::fs;
+use std::path::Path;
+
+/// Directory names that conventionally hold copies of third-party code
+const VENDOR_DIRS: &[&str] = &["vendor", "vendors", "third_party", "third-party", "thirdparty", "bower_components", "jspm_packages"];
+
+/// Whether a directory holds vendored dependencies: named like a vendor
+/// directory, a crate copied by `cargo vendor`, or a copy of a
+/// package installed by npm (whose package.json records where it came from)
+pub fn is_vendored(dir: &Path) -> bool {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    if VENDOR_DIRS.contains(&name.as_ref()) {
+        return true;
+    }
+    if dir.join(".cargo-checksum.json").is_file() {
+        return true;
+    }
+    match fs::read_to_string(dir.join("package.json")) {
+        Ok(manifest) => manifest.contains("\"_resolved\"") || manifest.contains("\"_integrity\""),
+        Err(_) => false,
+    }
+}
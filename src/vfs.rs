@@ -0,0 +1,186 @@
+// vfs.rs
+//! Where file contents come from. Files are read through `FileSystem` rather
+//! than std::fs directly, so the pipeline also runs on files that aren't on a
+//! disk: `Memory` holds a folder dropped into a browser tab or the entries of
+//! a fetched tarball in the wasm32 build. The command line's walk (the ignore
+//! crate), document extraction and preprocessors are still tied to the disk.
+use std::collections::BTreeMap;
+use std::fs;
+#[cfg(feature = "native")]
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(feature = "native")]
+use memmap2::Mmap;
+
+/// Files this big are mapped rather than read onto the heap
+#[cfg(feature = "native")]
+const MAP_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// What the pipeline needs to know about a file besides its content
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// A file's bytes, on the heap or mapped from the file
+pub enum Contents {
+    Heap(Vec<u8>),
+    #[cfg(feature = "native")]
+    Mapped(Mmap),
+}
+
+impl Contents {
+    pub fn is_mapped(&self) -> bool {
+        !matches!(self, Contents::Heap(_))
+    }
+}
+
+impl Deref for Contents {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Contents::Heap(bytes) => bytes,
+            #[cfg(feature = "native")]
+            Contents::Mapped(map) => map,
+        }
+    }
+}
+
+pub trait FileSystem: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Contents>;
+
+    /// Up to the first `len` bytes, to tell binaries from text without reading them whole
+    fn read_head(&self, path: &Path, len: usize) -> io::Result<Vec<u8>>;
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+}
+
+/// The real filesystem; large files are memory-mapped, so a 100 MB dump cut
+/// down to a few lines or rows never sits on the heap whole
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Disk;
+
+impl FileSystem for Disk {
+    #[cfg(feature = "native")]
+    fn read(&self, path: &Path) -> io::Result<Contents> {
+        let file = File::open(path)?;
+        if file.metadata()?.len() < MAP_THRESHOLD {
+            return fs::read(path).map(Contents::Heap);
+        }
+        // Safety: the map is only read, and dropped as soon as the file is decoded.
+        // A file truncated by another process meanwhile can still fault the read,
+        // the same trade-off every mmap-based tool (ripgrep, ...) makes.
+        unsafe { Mmap::map(&file) }.map(Contents::Mapped)
+    }
+
+    #[cfg(not(feature = "native"))]
+    fn read(&self, path: &Path) -> io::Result<Contents> {
+        fs::read(path).map(Contents::Heap)
+    }
+
+    fn read_head(&self, path: &Path, len: usize) -> io::Result<Vec<u8>> {
+        let mut head = Vec::with_capacity(len);
+        fs::File::open(path)?.take(len as u64).read_to_end(&mut head)?;
+        Ok(head)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = fs::metadata(path)?;
+        Ok(Metadata { len: metadata.len(), modified: metadata.modified().ok() })
+    }
+}
+
+/// Files held in memory by the path they're read with, like a folder dropped
+/// into a browser tab or the entries of a fetched tarball
+#[derive(Debug, Clone, Default)]
+pub struct Memory {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Memory::default()
+    }
+
+    pub fn insert(&mut self, path: impl Into<PathBuf>, bytes: Vec<u8>) {
+        self.files.insert(path.into(), bytes);
+    }
+
+    /// Every file, in path order
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files.keys().cloned().collect()
+    }
+}
+
+impl FileSystem for Memory {
+    fn read(&self, path: &Path) -> io::Result<Contents> {
+        self.files.get(path).map(|bytes| Contents::Heap(bytes.clone())).ok_or_else(|| not_found(path))
+    }
+
+    fn read_head(&self, path: &Path, len: usize) -> io::Result<Vec<u8>> {
+        self.files.get(path).map(|bytes| bytes[..bytes.len().min(len)].to_vec()).ok_or_else(|| not_found(path))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.files.get(path).map(|bytes| Metadata { len: bytes.len() as u64, modified: None }).ok_or_else(|| not_found(path))
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} is not in memory", path.display()))
+}
+
+/// Longest wait between requests, whatever rate is asked for
+const MAX_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// At most `per_second` requests (reads, first bytes and metadata) a second
+/// across all threads, so a network share or a cloud-synced folder (which
+/// fetches every file it's asked for) isn't flooded with them
+pub struct Throttled<F> {
+    inner: F,
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl<F: FileSystem> Throttled<F> {
+    pub fn new(inner: F, per_second: f64) -> Self {
+        // A rate too small for a Duration waits the longest interval instead
+        let interval = Duration::try_from_secs_f64(1.0 / per_second).unwrap_or(MAX_INTERVAL).min(MAX_INTERVAL);
+        Throttled { inner, interval, next: Mutex::new(Instant::now()) }
+    }
+
+    fn wait_turn(&self) {
+        let turn = {
+            let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+            let turn = (*next).max(Instant::now());
+            *next = turn + self.interval;
+            turn
+        };
+        thread::sleep(turn.saturating_duration_since(Instant::now()));
+    }
+}
+
+impl<F: FileSystem> FileSystem for Throttled<F> {
+    fn read(&self, path: &Path) -> io::Result<Contents> {
+        self.wait_turn();
+        self.inner.read(path)
+    }
+
+    fn read_head(&self, path: &Path, len: usize) -> io::Result<Vec<u8>> {
+        self.wait_turn();
+        self.inner.read_head(path, len)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.wait_turn();
+        self.inner.metadata(path)
+    }
+}
@@ -0,0 +1,307 @@
+// workspace.rs
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use globset::Glob;
+use toml::Value;
+
+use crate::imports;
+use crate::SourceFile;
+
+/// A member of a Cargo or JavaScript workspace
+pub struct Package {
+    pub name: String,
+    /// Directory relative to the workspace root
+    pub dir: PathBuf,
+    /// Names of workspace members this package depends on by path
+    pub path_dependencies: Vec<String>,
+}
+
+/// Members of the Cargo, pnpm, yarn or npm workspace rooted at `root`,
+/// or None if it isn't one
+pub fn detect(root: &Path) -> Option<Vec<Package>> {
+    cargo_packages(root).or_else(|| js_packages(root))
+}
+
+fn cargo_packages(root: &Path) -> Option<Vec<Package>> {
+    let manifest = read_manifest(&root.join("Cargo.toml"))?;
+    let workspace = manifest.get("workspace")?;
+
+    let patterns = string_list(workspace.get("members"));
+    let excluded: HashSet<PathBuf> = string_list(workspace.get("exclude"))
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    // A root manifest with [package] is a member itself
+    if manifest.get("package").is_some() {
+        dirs.push(PathBuf::new());
+    }
+    for pattern in &patterns {
+        for dir in expand_pattern(root, pattern, "Cargo.toml") {
+            if !excluded.contains(&dir) && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+
+    // Path dependencies are recorded by directory first, then mapped to names
+    let mut packages = Vec::new();
+    let mut dependency_dirs = Vec::new();
+    for dir in dirs {
+        let Some(member) = read_manifest(&root.join(&dir).join("Cargo.toml")) else { continue };
+        let Some(name) = member.get("package").and_then(|p| p.get("name")).and_then(Value::as_str) else {
+            continue;
+        };
+        dependency_dirs.push(path_dependencies(&member, &dir, workspace.get("dependencies")));
+        packages.push(Package { name: name.to_string(), dir, path_dependencies: Vec::new() });
+    }
+    for (i, deps) in dependency_dirs.into_iter().enumerate() {
+        packages[i].path_dependencies = deps
+            .iter()
+            .filter_map(|dep| packages.iter().find(|p| p.dir == *dep).map(|p| p.name.clone()))
+            .collect();
+    }
+    Some(packages)
+}
+
+/// The package a file belongs to: the member with the deepest directory containing it
+pub fn package_of<'a>(packages: &'a [Package], relative_path: &Path) -> Option<&'a Package> {
+    packages
+        .iter()
+        .filter(|p| relative_path.starts_with(&p.dir))
+        .max_by_key(|p| p.dir.components().count())
+}
+
+/// The named packages plus, if requested, everything they depend on by path
+pub fn select(packages: &[Package], names: &[&str], with_path_dependencies: bool) -> Result<HashSet<String>, String> {
+    let mut selected = HashSet::new();
+    let mut pending: Vec<String> = Vec::new();
+    for name in names {
+        if !packages.iter().any(|p| p.name == *name) {
+            let known: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+            return Err(format!("Unknown package '{}' (workspace members: {})", name, known.join(", ")));
+        }
+        pending.push(name.to_string());
+    }
+    while let Some(name) = pending.pop() {
+        if !selected.insert(name.clone()) || !with_path_dependencies {
+            continue;
+        }
+        if let Some(package) = packages.iter().find(|p| p.name == name) {
+            pending.extend(package.path_dependencies.iter().cloned());
+        }
+    }
+    Ok(selected)
+}
+
+/// Shared overview for per-package outputs: every member with its size and
+/// workspace dependencies, then the files that belong to no member
+pub fn summary(packages: &[Package], files: &[SourceFile]) -> String {
+    let mut out = format!("Workspace packages ({}):\n", packages.len());
+    for package in packages {
+        let members: Vec<&SourceFile> = files
+            .iter()
+            .filter(|f| package_of(packages, &f.relative_path).is_some_and(|p| p.name == package.name))
+            .collect();
+        let tokens: usize = members.iter().map(|f| f.tokens).sum();
+        let dir = if package.dir.as_os_str().is_empty() { ".".to_string() } else { package.dir.display().to_string() };
+        let _ = writeln!(out, "  {} ({}) - {} files, ~{} tokens", package.name, dir, members.len(), tokens);
+        if !package.path_dependencies.is_empty() {
+            let _ = writeln!(out, "    depends on: {}", package.path_dependencies.join(", "));
+        }
+    }
+
+    let shared: Vec<&SourceFile> = files.iter().filter(|f| package_of(packages, &f.relative_path).is_none()).collect();
+    if !shared.is_empty() {
+        let _ = writeln!(out, "\nShared root files ({}):", shared.len());
+        for file in shared {
+            let _ = writeln!(out, "  {}", file.relative_path.display());
+        }
+    }
+    out
+}
+
+fn read_manifest(path: &Path) -> Option<Value> {
+    let content = fs::read_to_string(path).ok()?;
+    match content.parse::<Value>() {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("Error parsing {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).map(|s| s.trim_end_matches('/').to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// JavaScript workspaces: pnpm-workspace.yaml, or "workspaces" in package.json
+/// (npm, yarn; turborepo builds on these), or lerna.json
+fn js_packages(root: &Path) -> Option<Vec<Package>> {
+    let root_manifest = read_json(&root.join("package.json"));
+    let patterns = pnpm_patterns(root)
+        .or_else(|| {
+            let workspaces = root_manifest.as_ref()?.get("workspaces")?;
+            // Either an array or yarn's { "packages": [...] }
+            let list = workspaces.get("packages").unwrap_or(workspaces);
+            Some(json_strings(list))
+        })
+        .or_else(|| read_json(&root.join("lerna.json")).and_then(|l| l.get("packages").map(json_strings)))?;
+
+    let (excluded, included): (Vec<&String>, Vec<&String>) = patterns.iter().partition(|p| p.starts_with('!'));
+    let excluded: Vec<PathBuf> = excluded
+        .iter()
+        .flat_map(|p| expand_pattern(root, p.trim_start_matches('!'), "package.json"))
+        .collect();
+
+    let mut manifests = Vec::new();
+    for pattern in included {
+        for dir in expand_pattern(root, pattern.trim_start_matches("./"), "package.json") {
+            if excluded.contains(&dir) || manifests.iter().any(|(d, _)| *d == dir) {
+                continue;
+            }
+            if let Some(manifest) = read_json(&root.join(&dir).join("package.json")) {
+                manifests.push((dir, manifest));
+            }
+        }
+    }
+
+    let names: Vec<String> = manifests
+        .iter()
+        .map(|(dir, manifest)| match manifest.get("name").and_then(serde_json::Value::as_str) {
+            Some(name) => name.to_string(),
+            None => dir.to_string_lossy().into_owned(),
+        })
+        .collect();
+    let packages = manifests
+        .iter()
+        .zip(&names)
+        .map(|((dir, manifest), name)| {
+            // Dependencies on other workspace packages, whatever their version range
+            let mut path_dependencies: Vec<String> = Vec::new();
+            for section in ["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"] {
+                let Some(deps) = manifest.get(section).and_then(serde_json::Value::as_object) else { continue };
+                for dep in deps.keys() {
+                    if names.contains(dep) && dep != name && !path_dependencies.contains(dep) {
+                        path_dependencies.push(dep.clone());
+                    }
+                }
+            }
+            Package { name: name.clone(), dir: dir.clone(), path_dependencies }
+        })
+        .collect();
+    Some(packages)
+}
+
+/// The `packages:` list of pnpm-workspace.yaml
+fn pnpm_patterns(root: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(root.join("pnpm-workspace.yaml")).ok()?;
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed == "packages:";
+        } else if in_packages {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                patterns.push(item.trim().trim_matches(|c| c == '"' || c == '\'').to_string());
+            }
+        }
+    }
+    Some(patterns)
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn json_strings(value: &serde_json::Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| items.iter().filter_map(serde_json::Value::as_str).map(|s| s.trim_end_matches('/').to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Directories skipped when expanding `**` in member patterns
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", ".git"];
+
+/// Expand a member pattern such as `crates/*` or `packages/**` to the
+/// directories it matches that contain `manifest`
+fn expand_pattern(root: &Path, pattern: &str, manifest: &str) -> Vec<PathBuf> {
+    let mut dirs = expand_dirs(root, pattern);
+    dirs.retain(|dir| root.join(dir).join(manifest).is_file());
+    dirs
+}
+
+/// Directories under `root` matching a pattern with `*`, `?`, `[...]` or `**`
+/// components, relative to `root`
+pub fn expand_dirs(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::new()];
+    for component in pattern.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        let mut next = Vec::new();
+        if component == "**" {
+            let mut pending = dirs.clone();
+            while let Some(dir) = pending.pop() {
+                next.push(dir.clone());
+                let Ok(entries) = fs::read_dir(root.join(&dir)) else { continue };
+                for entry in entries.flatten() {
+                    let name = entry.file_name();
+                    if entry.path().is_dir() && !SKIPPED_DIRS.iter().any(|s| name == *s) {
+                        pending.push(dir.join(name));
+                    }
+                }
+            }
+            next.sort();
+        } else if component.contains(['*', '?', '[']) {
+            let Ok(glob) = Glob::new(component) else { return Vec::new() };
+            let matcher = glob.compile_matcher();
+            for dir in &dirs {
+                let Ok(entries) = fs::read_dir(root.join(dir)) else { continue };
+                let mut matched: Vec<PathBuf> = entries
+                    .flatten()
+                    .filter(|e| e.path().is_dir() && matcher.is_match(e.file_name()))
+                    .map(|e| dir.join(e.file_name()))
+                    .collect();
+                matched.sort();
+                next.extend(matched);
+            }
+        } else {
+            next = dirs.iter().map(|dir| dir.join(component)).collect();
+        }
+        dirs = next;
+    }
+    dirs.retain(|dir| root.join(dir).is_dir());
+    dirs
+}
+
+/// Directories of `path = "..."` dependencies, relative to the workspace root.
+/// `dep.workspace = true` entries are looked up in `[workspace.dependencies]`.
+fn path_dependencies(manifest: &Value, dir: &Path, workspace_deps: Option<&Value>) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        let Some(deps) = manifest.get(section).and_then(Value::as_table) else { continue };
+        for (name, dep) in deps {
+            if let Some(path) = dep.get("path").and_then(Value::as_str) {
+                dirs.push(imports::normalize(&dir.join(path)));
+            } else if dep.get("workspace").and_then(Value::as_bool) == Some(true) {
+                let path = workspace_deps.and_then(|w| w.get(name)).and_then(|d| d.get("path")).and_then(Value::as_str);
+                if let Some(path) = path {
+                    dirs.push(imports::normalize(Path::new(path)));
+                }
+            }
+        }
+    }
+    dirs
+}